@@ -10,6 +10,17 @@ pub enum Error {
 
     /// Failed to trace the full cycle path, usually due to broken predecessor chains.
     CycleReconstructionFailed,
+
+    /// The operation requires at least one node, but the graph is empty.
+    EmptyGraph,
+
+    /// The edge at the given input index produced a NaN or infinite weight
+    /// (e.g., a zero, negative, or non-finite rate).
+    NonFiniteWeight(usize),
+
+    /// A profitable (negative) cycle is reachable, so shortest-path results such as
+    /// a best conversion rate are unbounded.
+    UnboundedRate,
 }
 
 impl fmt::Display for Error {
@@ -23,6 +34,17 @@ impl fmt::Display for Error {
                 f,
                 "Cycle path reconstruction failed due to broken predecessor chain."
             ),
+
+            Error::EmptyGraph => write!(f, "Graph is empty."),
+
+            Error::NonFiniteWeight(idx) => {
+                write!(f, "Edge at index {} produced a non-finite weight.", idx)
+            }
+
+            Error::UnboundedRate => write!(
+                f,
+                "A reachable profitable cycle makes the best rate unbounded."
+            ),
         }
     }
 }
@@ -55,9 +55,41 @@
 //!
 //! ## Function
 //! - [`log_mul_eps`]: Core function performing the log-space multiply–quantize–gate operation.
+//! - [`eps_gate_closed`]: The epsilon gate on its own, for callers that only need idempotence.
+//! - [`log_mul`]: The bare log-space multiplication, without clamping, quantization or gating.
+//! - [`KernelParams`]: The `[min_r, max_r]` range, reusable wherever rates need the same bounds.
 
 use std::f64;
 
+/// Dynamic range `[min_r, max_r]` that factors are clamped to, as in [`log_mul_eps`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KernelParams {
+    pub min_r: f64,
+    pub max_r: f64,
+}
+
+impl KernelParams {
+    /// # Panics
+    /// Panics if `min_r > max_r` or either bound is NaN.
+    pub fn new(min_r: f64, max_r: f64) -> Self {
+        assert!(min_r <= max_r, "min_r must not exceed max_r");
+        Self { min_r, max_r }
+    }
+
+    /// Clamps `rate` into `[min_r, max_r]`.
+    ///
+    /// Non-positive and NaN rates are returned unchanged, so clamping never turns an
+    /// invalid quote into a valid one.
+    pub fn clamp(&self, rate: f64) -> f64 {
+        if rate > 0.0 {
+            rate.clamp(self.min_r, self.max_r)
+        } else {
+            rate
+        }
+    }
+}
+
 pub fn log_mul_eps(
     old_value: f64,
     a: f64,
@@ -70,19 +102,31 @@ pub fn log_mul_eps(
     let a_clamped = a.clamp(min_r, max_r);
     let b_clamped = b.clamp(min_r, max_r);
 
-    // Multiplication via log-space addition (ln(a) + ln(b)) mitigates cumulative floating-point errors.
-    let log_product = a_clamped.ln() + b_clamped.ln();
-    let new_value_raw = log_product.exp();
+    let new_value_raw = log_mul(a_clamped, b_clamped);
 
     let quantized_value = (new_value_raw / quantum).round() * quantum;
 
-    if (quantized_value - old_value).abs() < eps {
+    if eps_gate_closed(old_value, quantized_value, eps) {
         return old_value;
     }
 
     quantized_value
 }
 
+/// Multiplies `a` by `b` in log-space: `exp(ln(a) + ln(b))`.
+///
+/// Multiplication via log-space addition mitigates cumulative floating-point errors
+/// when a value is scaled repeatedly. Non-positive factors give NaN.
+pub fn log_mul(a: f64, b: f64) -> f64 {
+    (a.ln() + b.ln()).exp()
+}
+
+/// Returns true if moving from `old_value` to `new_value` is a negligible change
+/// (`|new_value - old_value| < eps`) that should not be committed.
+pub fn eps_gate_closed(old_value: f64, new_value: f64, eps: f64) -> bool {
+    (new_value - old_value).abs() < eps
+}
+
 #[cfg(test)]
 mod numerical_kernel_tests {
     use super::*;
@@ -10,7 +10,12 @@
 /// - `rates`: Original weights of the edges along the cycle.
 /// - `product_rate`: Cumulative product of all rates along the cycle (useful for profit calculation).
 /// - `transformed_profit`: Sum of transformed weights (e.g., `-ln(rate)`); negative values may indicate profit.
+///
+/// With the `serde` feature enabled, the serialized form also carries the computed
+/// `product_rate` so downstream sinks do not need to re-derive it. The field is
+/// ignored on deserialization.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub struct WeightedCycle {
     pub path: Vec<Edge>,
     pub rates: Vec<f64>,
@@ -29,15 +34,422 @@ impl WeightedCycle {
     /// stored sum (log_rate_sum) = -ln(24.0) ≈ -3.178.
     /// product_rate = exp(-(-3.178)) = 24.0
     /// ```
+    ///
+    /// Overflows to `inf` once `-log_rate_sum` exceeds ~709 (very long or high-rate
+    /// cycles); see [`WeightedCycle::product_rate_checked`].
     pub fn product_rate(&self) -> f64 {
         (-self.log_rate_sum).exp()
     }
 
-    /// Returns true if the cycle is profitable (product_rate > 1.0).
+    /// [`WeightedCycle::product_rate`], or `None` if the product is not finite
+    /// (overflow, or a NaN/infinite `log_rate_sum`).
+    pub fn product_rate_checked(&self) -> Option<f64> {
+        Some(self.product_rate()).filter(|rate| rate.is_finite())
+    }
+
+    /// Returns true if the cycle is profitable (finite product_rate > 1.0).
+    ///
+    /// A product that overflows is not a real opportunity (it usually means corrupt
+    /// rates), so it counts as unprofitable instead of trivially passing `inf > 1.0`.
     pub fn is_profitable(&self) -> bool {
-        self.product_rate() > 1.0
+        self.product_rate_checked().is_some_and(|rate| rate > 1.0)
+    }
+
+    /// Returns the profit left after charging `fee_bps_per_hop` on every hop, as a
+    /// fraction of the starting amount (0.01 = 1%).
+    ///
+    /// This is the headroom between gross profit and total fees: positive means the
+    /// cycle survives fees, and larger values tolerate more slippage or fee drift. A
+    /// long, thin cycle can out-earn a short one gross yet rank below it here, since
+    /// it pays the fee on more hops.
+    pub fn margin_over_fees(&self, fee_bps_per_hop: f64) -> f64 {
+        let fee_log = self.path.len() as f64 * (-fee_bps_per_hop / 10_000.0).ln_1p();
+        (fee_log - self.log_rate_sum).exp_m1()
+    }
+
+    /// Returns true if the cycle is structurally sound: a non-empty, simple, closed loop.
+    ///
+    /// Checks that each hop starts where the previous one ended, the last hop returns
+    /// to the first node, no node is visited twice, `rates` mirrors the rates in
+    /// `path`, and `log_rate_sum` is finite. Profitability is not checked.
+    pub fn is_valid(&self) -> bool {
+        let (Some(first), Some(last)) = (self.path.first(), self.path.last()) else {
+            return false;
+        };
+
+        let chained = self.path.windows(2).all(|hops| hops[0].1 == hops[1].0);
+        let closed = last.1 == first.0;
+
+        let mut nodes = self.nodes();
+        nodes.sort_unstable();
+        let simple = nodes.windows(2).all(|pair| pair[0] != pair[1]);
+
+        let rates_match = self.rates.len() == self.path.len()
+            && self
+                .path
+                .iter()
+                .zip(&self.rates)
+                .all(|(&(_, _, rate), &r)| rate == r);
+
+        chained && closed && simple && rates_match && self.log_rate_sum.is_finite()
+    }
+
+    /// Returns the sequence of nodes visited by the cycle (the source of each hop).
+    pub fn nodes(&self) -> Vec<usize> {
+        self.path.iter().map(|&(u, _, _)| u).collect()
+    }
+
+    /// Returns the hops in execution order, each with the running product of rates
+    /// up to and including it, i.e. the value held after that trade per unit started with.
+    ///
+    /// The last leg's `cumulative_rate` is the cycle's product rate.
+    pub fn execution_legs(&self) -> Vec<ExecutionLeg> {
+        self.path
+            .iter()
+            .scan(1.0, |cumulative, &(from, to, rate)| {
+                *cumulative *= rate;
+                Some(ExecutionLeg {
+                    from,
+                    to,
+                    rate,
+                    cumulative_rate: *cumulative,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns a copy of the cycle rotated so that the hop leaving the minimum node id comes first.
+    ///
+    /// The loop itself (edges, rates, `log_rate_sum`) is unchanged; only the starting point moves.
+    pub fn canonicalize(&self) -> WeightedCycle {
+        self.rotated(Self::min_position(&self.nodes()))
+    }
+
+    /// Returns a copy of the cycle rotated so that it starts at `start_node`
+    /// (e.g. the caller's base currency), or `None` if the node is not on the cycle.
+    ///
+    /// Like [`WeightedCycle::canonicalize`], this only moves the starting point.
+    pub fn rotate_to(&self, start_node: usize) -> Option<WeightedCycle> {
+        self.path
+            .iter()
+            .position(|&(u, _, _)| u == start_node)
+            .map(|start| self.rotated(start))
+    }
+
+    fn rotated(&self, start: usize) -> WeightedCycle {
+        let mut path = self.path.clone();
+        let mut rates = self.rates.clone();
+        path.rotate_left(start);
+        rates.rotate_left(start);
+
+        WeightedCycle {
+            path,
+            rates,
+            log_rate_sum: self.log_rate_sum,
+        }
+    }
+
+    /// Rotation-invariant identity of the cycle: its node sequence starting at the minimum node id.
+    ///
+    /// Two cycles describing the same directed loop from different starting nodes share this key.
+    pub fn canonical_key(&self) -> Vec<usize> {
+        let mut nodes = self.nodes();
+        let start = Self::min_position(&nodes);
+        nodes.rotate_left(start);
+        nodes
+    }
+
+    /// Rotation- and reversal-invariant identity of the cycle.
+    ///
+    /// Useful in undirected-like setups where `a -> b -> c -> a` and `a -> c -> b -> a`
+    /// describe the same market loop traversed in opposite directions.
+    pub fn undirected_key(&self) -> Vec<usize> {
+        let forward = self.canonical_key();
+
+        let mut reversed = forward.clone();
+        reversed.reverse();
+        let start = Self::min_position(&reversed);
+        reversed.rotate_left(start);
+
+        forward.min(reversed)
+    }
+
+    fn min_position(nodes: &[usize]) -> usize {
+        nodes
+            .iter()
+            .enumerate()
+            .min_by_key(|&(_, node)| node)
+            .map_or(0, |(pos, _)| pos)
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for WeightedCycle {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("WeightedCycle", 4)?;
+        state.serialize_field("path", &self.path)?;
+        state.serialize_field("rates", &self.rates)?;
+        state.serialize_field("log_rate_sum", &self.log_rate_sum)?;
+        state.serialize_field("product_rate", &self.product_rate())?;
+        state.end()
+    }
+}
+
+/// One hop of a cycle as an execution engine walks it, see [`WeightedCycle::execution_legs`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExecutionLeg {
+    pub from: usize,
+    pub to: usize,
+    pub rate: f64,
+    /// Product of the rates of this leg and every leg before it.
+    pub cumulative_rate: f64,
+}
+
 /// Type alias for a single edge list: (from, to, rate)
 pub type Edge = (usize, usize, f64);
+
+/// Strongly-typed node (token) identifier used at the public API boundary.
+///
+/// Internally the graph keeps raw `usize` indices for performance; `NodeId`
+/// exists so callers cannot accidentally pass a rate where a node is expected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct NodeId(pub usize);
+
+impl From<usize> for NodeId {
+    fn from(id: usize) -> Self {
+        NodeId(id)
+    }
+}
+
+impl From<NodeId> for usize {
+    fn from(id: NodeId) -> Self {
+        id.0
+    }
+}
+
+/// Strongly-typed multiplicative exchange rate (e.g., `1.05` = 5% gain per hop).
+///
+/// Distinguishes a raw rate from a transformed log-space weight (`-ln(rate)`).
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Rate(pub f64);
+
+impl Rate {
+    /// Returns the log-space weight `-ln(rate)` used by the solver.
+    pub fn to_weight(self) -> f64 {
+        -self.0.ln()
+    }
+
+    /// Recovers a rate from a log-space weight: `rate = e^(-weight)`.
+    pub fn from_weight(weight: f64) -> Self {
+        Rate((-weight).exp())
+    }
+}
+
+impl From<f64> for Rate {
+    fn from(rate: f64) -> Self {
+        Rate(rate)
+    }
+}
+
+impl From<Rate> for f64 {
+    fn from(rate: Rate) -> Self {
+        rate.0
+    }
+}
+
+/// Chaining two hops multiplies their rates.
+impl std::ops::Mul for Rate {
+    type Output = Rate;
+
+    fn mul(self, rhs: Rate) -> Rate {
+        Rate(self.0 * rhs.0)
+    }
+}
+
+/// Builds a raw [`Edge`] from typed components.
+///
+/// The argument types make swapped node/rate arguments a compile error:
+///
+/// ```compile_fail
+/// use common::types::{edge, NodeId, Rate};
+///
+/// // `rate` and `to` are swapped; this does not compile.
+/// let _ = edge(NodeId(0), Rate(1.05), NodeId(1));
+/// ```
+pub fn edge(from: NodeId, to: NodeId, rate: Rate) -> Edge {
+    (from.into(), to.into(), rate.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn margin_over_fees_ranks_a_fat_short_cycle_above_a_thin_long_one() {
+        // Gross: +1.2% over 3 hops vs +1.0% over 2 hops.
+        let thin = cycle_from(vec![(0, 1, 1.004), (1, 2, 1.004), (2, 0, 1.004)]);
+        let fat = cycle_from(vec![(3, 4, 1.01), (4, 3, 1.0)]);
+        assert!(thin.product_rate() > fat.product_rate());
+
+        // At 30 bps per hop the thin cycle pays 0.9% in fees, the fat one 0.6%.
+        let (thin_margin, fat_margin) = (thin.margin_over_fees(30.0), fat.margin_over_fees(30.0));
+        assert!(fat_margin > thin_margin);
+        assert!((fat_margin - (1.01 * 0.997f64.powi(2) - 1.0)).abs() < 1e-12);
+        assert!(thin_margin > 0.0);
+
+        // Without fees the margin is just the gross profit.
+        assert!((thin.margin_over_fees(0.0) - (thin.product_rate() - 1.0)).abs() < 1e-12);
+        assert!(thin.margin_over_fees(50.0) < 0.0);
+    }
+
+    #[test]
+    fn execution_legs_carry_the_running_product() {
+        let triangle = cycle_from(vec![(0, 1, 1.1), (1, 2, 0.95), (2, 0, 1.02)]);
+        let legs = triangle.execution_legs();
+
+        assert_eq!(legs.len(), 3);
+        assert_eq!((legs[0].from, legs[0].to, legs[0].rate), (0, 1, 1.1));
+        assert_eq!(legs[0].cumulative_rate, 1.1);
+        assert!((legs[1].cumulative_rate - 1.1 * 0.95).abs() < 1e-12);
+        assert_eq!((legs[2].from, legs[2].to), (2, 0));
+        assert!((legs[2].cumulative_rate - triangle.product_rate()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn overflowing_product_rate_is_not_profitable() {
+        // 200 hops at 100x: ln(100^200) ≈ 921 overflows f64::exp, yet each hop is finite.
+        let path: Vec<Edge> = (0..200).map(|i| (i, (i + 1) % 200, 100.0)).collect();
+        let long = cycle_from(path);
+
+        assert!(long.log_rate_sum.is_finite());
+        assert_eq!(long.product_rate(), f64::INFINITY);
+        assert_eq!(long.product_rate_checked(), None);
+        assert!(!long.is_profitable());
+
+        let short = cycle_from(vec![(0, 1, 2.0), (1, 0, 0.6)]);
+        assert!((short.product_rate_checked().unwrap() - 1.2).abs() < 1e-12);
+        assert!(short.is_profitable());
+    }
+
+    #[test]
+    fn is_valid_accepts_closed_simple_loops_only() {
+        assert!(cycle_from(vec![(0, 1, 1.1), (1, 2, 1.0), (2, 0, 1.0)]).is_valid());
+        assert!(cycle_from(vec![(3, 3, 1.1)]).is_valid());
+
+        assert!(!cycle_from(vec![]).is_valid());
+        assert!(!cycle_from(vec![(0, 1, 1.1), (1, 2, 1.0)]).is_valid());
+        assert!(!cycle_from(vec![(0, 1, 1.1), (2, 0, 1.0)]).is_valid());
+        assert!(!cycle_from(vec![(0, 1, 1.0), (1, 0, 1.0), (0, 1, 1.0), (1, 0, 1.1)]).is_valid());
+
+        let mut mismatched = cycle_from(vec![(0, 1, 1.1), (1, 0, 1.0)]);
+        mismatched.rates.pop();
+        assert!(!mismatched.is_valid());
+    }
+
+    #[test]
+    fn node_id_round_trips_through_usize() {
+        let id = NodeId::from(42usize);
+        let raw: usize = id.into();
+
+        assert_eq!(raw, 42);
+        assert_eq!(NodeId::from(raw), id);
+    }
+
+    #[test]
+    fn rate_round_trips_through_f64_and_weight() {
+        let rate = Rate::from(1.05);
+        let raw: f64 = rate.into();
+        assert_eq!(raw, 1.05);
+
+        let recovered = Rate::from_weight(rate.to_weight());
+        assert!((recovered.0 - rate.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn rate_multiplication_chains_hops() {
+        let product = Rate(2.0) * Rate(0.5) * Rate(1.1);
+        assert!((product.0 - 1.1).abs() < 1e-12);
+    }
+
+    #[test]
+    fn typed_edge_builds_raw_tuple_in_order() {
+        let e = edge(NodeId(3), NodeId(7), Rate(0.99));
+        assert_eq!(e, (3, 7, 0.99));
+    }
+
+    fn cycle_from(path: Vec<Edge>) -> WeightedCycle {
+        let rates: Vec<f64> = path.iter().map(|&(_, _, r)| r).collect();
+        WeightedCycle {
+            log_rate_sum: rates.iter().map(|r| -r.ln()).sum(),
+            path,
+            rates,
+        }
+    }
+
+    #[test]
+    fn rotate_to_starts_at_requested_node() {
+        let cycle = cycle_from(vec![(0, 1, 2.0), (1, 2, 0.6), (2, 0, 0.9)]);
+
+        let rotated = cycle.rotate_to(1).expect("Node 1 is on the cycle");
+
+        assert_eq!(rotated.path, vec![(1, 2, 0.6), (2, 0, 0.9), (0, 1, 2.0)]);
+        assert_eq!(rotated.rates, vec![0.6, 0.9, 2.0]);
+        assert_eq!(rotated.nodes(), vec![1, 2, 0]);
+        assert_eq!(rotated.product_rate(), cycle.product_rate());
+        assert_eq!(rotated.canonical_key(), cycle.canonical_key());
+        assert!(cycle.rotate_to(5).is_none());
+    }
+
+    #[test]
+    fn rotated_cycles_share_canonical_key() {
+        let a = cycle_from(vec![(2, 0, 1.1), (0, 1, 0.9), (1, 2, 1.05)]);
+        let b = cycle_from(vec![(1, 2, 1.05), (2, 0, 1.1), (0, 1, 0.9)]);
+
+        assert_eq!(a.canonical_key(), vec![0, 1, 2]);
+        assert_eq!(a.canonical_key(), b.canonical_key());
+
+        let canonical = b.canonicalize();
+        assert_eq!(canonical.path, vec![(0, 1, 0.9), (1, 2, 1.05), (2, 0, 1.1)]);
+        assert_eq!(canonical.rates, vec![0.9, 1.05, 1.1]);
+        assert_eq!(canonical.log_rate_sum, b.log_rate_sum);
+    }
+
+    #[test]
+    fn reversed_cycle_shares_only_undirected_key() {
+        let forward = cycle_from(vec![(0, 1, 1.1), (1, 2, 1.0), (2, 0, 1.0)]);
+        let reversed = cycle_from(vec![(1, 0, 0.9), (0, 2, 1.0), (2, 1, 1.0)]);
+
+        assert_ne!(forward.canonical_key(), reversed.canonical_key());
+        assert_eq!(forward.undirected_key(), reversed.undirected_key());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn weighted_cycle_serializes_with_product_rate() {
+        let rates = vec![2.0, 0.6];
+        let cycle = WeightedCycle {
+            path: vec![(0, 1, 2.0), (1, 0, 0.6)],
+            log_rate_sum: rates.iter().map(|r: &f64| -r.ln()).sum(),
+            rates,
+        };
+
+        let json = serde_json::to_value(&cycle).expect("Serialization failed");
+
+        assert_eq!(json["path"], serde_json::json!([[0, 1, 2.0], [1, 0, 0.6]]));
+        assert_eq!(json["rates"], serde_json::json!([2.0, 0.6]));
+        assert!(json["log_rate_sum"].as_f64().unwrap() < 0.0);
+
+        let product = json["product_rate"].as_f64().expect("Missing product_rate");
+        assert!((product - 1.2).abs() < 1e-12);
+
+        let decoded: WeightedCycle = serde_json::from_value(json).expect("Deserialization failed");
+        assert_eq!(decoded.path, cycle.path);
+    }
+}
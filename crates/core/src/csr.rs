@@ -1,11 +1,276 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+#[cfg(any(test, feature = "random"))]
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[cfg(any(test, feature = "random"))]
+use rand::rngs::StdRng;
+#[cfg(any(test, feature = "random"))]
+use rand::{Rng, SeedableRng};
+
 use common::error::Error;
-use common::types::Edge;
+use common::numeric_kernel::KernelParams;
+use common::types::{Edge, WeightedCycle};
+
+use super::transform::{Transform, WeightTransform};
 
 pub enum AddEdgeResult {
     Success,
     RebuildNeeded(Vec<Edge>),
 }
 
+/// How a rebuild resolves several rates for the same `(src, dst)` pair.
+///
+/// "Latest" and "earliest" follow insertion order: committed edges are older than
+/// any new edge, and new edges are ordered as they were passed to the rebuild
+/// (i.e. in the order their batches arrived).
+///
+/// - `KeepLatest`: the most recent quote wins.
+/// - `KeepEarliest`: the first quote seen wins; later quotes for a committed pair
+///   are ignored.
+/// - `KeepMax`: the best (highest) rate seen so far wins, including the committed one.
+/// - `WeightedAverage`: quotes within one rebuild batch are combined into their
+///   count-weighted mean (each quote weighs 1), which then replaces the committed rate.
+///   Useful when several venues quote the same pair and no single quote is trusted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum DedupPolicy {
+    #[default]
+    KeepLatest,
+    KeepEarliest,
+    KeepMax,
+    WeightedAverage,
+}
+
+/// Which quotes count as the same market when deduplicating on rebuild.
+///
+/// - `Directed`: `(a, b)` and `(b, a)` are independent pairs.
+/// - `Undirected`: `(a, b)` and `(b, a)` are two sides of one market, keyed by the
+///   unordered `{a, b}`. A `b -> a` quote at `r` is read as an `a -> b` quote at
+///   `1 / r`; the [`DedupPolicy`] resolves the market's quotes in `min -> max`
+///   orientation and both directions are committed with mutually inverse rates.
+///   Only meaningful for multiplicative rates (the default transform).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum DedupKey {
+    #[default]
+    Directed,
+    Undirected,
+}
+
+/// Callback invoked with the freshly committed graph after every successful rebuild.
+///
+/// Shared (`Arc`) so that cloned graphs, e.g. searcher snapshots, keep the hook.
+#[derive(Clone)]
+pub struct RebuildHook(Arc<dyn Fn(&GraphCSR) + Send + Sync>);
+
+impl RebuildHook {
+    pub fn new(hook: impl Fn(&GraphCSR) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(hook))
+    }
+}
+
+impl fmt::Debug for RebuildHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RebuildHook")
+    }
+}
+
+/// Decides when buffered updates are worth folding into the CSR arrays.
+///
+/// Consulted after every batch is buffered; `graph.pending_updates()` already holds the
+/// new batch. A rebuild costs O(E log E) over all edges, so policies trade update
+/// latency (pending edges are invisible to solvers) against rebuild work.
+pub trait RebuildPolicy: Send + Sync {
+    fn should_rebuild(&self, graph: &GraphCSR) -> bool;
+}
+
+/// Default policy: rebuild once `pending_len() >= rebuild_limit`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PendingLimit;
+
+impl RebuildPolicy for PendingLimit {
+    fn should_rebuild(&self, graph: &GraphCSR) -> bool {
+        graph.pending_len() >= graph.rebuild_limit
+    }
+}
+
+/// Rebuild once pending updates reach `ratio` times the committed edge count.
+///
+/// Scales the batch with the graph: small graphs rebuild often (cheap), large ones
+/// amortize each O(E log E) rebuild over proportionally more updates.
+#[derive(Debug, Clone, Copy)]
+pub struct EdgeRatio(pub f64);
+
+impl RebuildPolicy for EdgeRatio {
+    fn should_rebuild(&self, graph: &GraphCSR) -> bool {
+        let pending = graph.pending_len();
+        pending > 0 && pending as f64 >= self.0 * graph.edge_targets.len() as f64
+    }
+}
+
+/// Rebuild limit that follows the update rate: about one rebuild per `target_interval`
+/// worth of updates, clamped to `min_limit..=max_limit`.
+///
+/// Under heavy flow the limit rises so each O(E log E) rebuild is amortized over more
+/// updates; when updates are sparse it falls so they become visible sooner. The rate
+/// is a moving average over the batches seen by [`RebuildPolicy::should_rebuild`]; the
+/// limit starts at `min_limit` until a rate is known.
+///
+/// Clones share their state, so a caller can keep a handle to read
+/// [`AdaptiveLimit::effective_limit`] after installing the policy on a graph.
+#[derive(Debug, Clone)]
+pub struct AdaptiveLimit {
+    min_limit: usize,
+    max_limit: usize,
+    target_interval: Duration,
+    state: Arc<Mutex<AdaptiveState>>,
+}
+
+#[derive(Debug, Default)]
+struct AdaptiveState {
+    /// Pending updates at the previous call, to count the new ones.
+    last_pending: usize,
+    last_seen: Option<Instant>,
+    /// Moving average of updates per second.
+    rate: Option<f64>,
+}
+
+impl AdaptiveLimit {
+    /// Weight of the newest sample in the moving average.
+    const SMOOTHING: f64 = 0.3;
+
+    /// # Panics
+    /// Panics if `min_limit` is 0 or above `max_limit`.
+    pub fn new(min_limit: usize, max_limit: usize, target_interval: Duration) -> Self {
+        assert!(
+            0 < min_limit && min_limit <= max_limit,
+            "AdaptiveLimit needs 0 < min_limit <= max_limit"
+        );
+        Self {
+            min_limit,
+            max_limit,
+            target_interval,
+            state: Arc::default(),
+        }
+    }
+
+    /// Records `updates` new updates arriving at `now` and returns the resulting limit.
+    pub fn observe(&self, updates: usize, now: Instant) -> usize {
+        let mut state = self.state.lock().unwrap();
+        if let Some(last) = state.last_seen {
+            let elapsed = now.saturating_duration_since(last).as_secs_f64().max(1e-6);
+            let sample = updates as f64 / elapsed;
+            state.rate = Some(match state.rate {
+                Some(rate) => Self::SMOOTHING * sample + (1.0 - Self::SMOOTHING) * rate,
+                None => sample,
+            });
+        }
+        state.last_seen = Some(now);
+        self.limit_for(state.rate)
+    }
+
+    /// The limit the current update rate calls for.
+    pub fn effective_limit(&self) -> usize {
+        self.limit_for(self.state.lock().unwrap().rate)
+    }
+
+    fn limit_for(&self, rate: Option<f64>) -> usize {
+        let Some(rate) = rate else {
+            return self.min_limit;
+        };
+        let wanted = rate * self.target_interval.as_secs_f64();
+        (wanted.round() as usize).clamp(self.min_limit, self.max_limit)
+    }
+}
+
+impl RebuildPolicy for AdaptiveLimit {
+    fn should_rebuild(&self, graph: &GraphCSR) -> bool {
+        let pending = graph.pending_len();
+        let last_pending = std::mem::take(&mut self.state.lock().unwrap().last_pending);
+        // Pending updates only shrink when a rebuild takes them all.
+        let new_updates = pending.checked_sub(last_pending).unwrap_or(pending);
+
+        let rebuild = pending >= self.observe(new_updates, Instant::now());
+        if !rebuild {
+            self.state.lock().unwrap().last_pending = pending;
+        }
+        rebuild
+    }
+}
+
+/// Shared handle to the graph's [`RebuildPolicy`]; clones (e.g. snapshots) keep it.
+#[derive(Clone)]
+pub struct SharedRebuildPolicy(Arc<dyn RebuildPolicy>);
+
+impl Default for SharedRebuildPolicy {
+    fn default() -> Self {
+        Self(Arc::new(PendingLimit))
+    }
+}
+
+impl fmt::Debug for SharedRebuildPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SharedRebuildPolicy")
+    }
+}
+
+/// One CSR edge with its target and weight side by side, see [`GraphCSR::packed_edges`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PackedEdge {
+    pub target: usize,
+    pub weight: f64,
+}
+
+/// Memory layout the solver reads edges from.
+///
+/// - `Split`: the graph's own `edge_targets` / `edge_weights` vectors (two loads from
+///   two arrays per edge).
+/// - `Packed`: a [`PackedEdge`] array, so a relaxation touches one cache line per edge.
+///   Kept by the graph with the `packed-edges` feature; otherwise built per search, an
+///   O(E) copy up front.
+///
+/// The default is `Split`, or `Packed` with the `packed-edges` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EdgeLayout {
+    #[cfg_attr(not(feature = "packed-edges"), default)]
+    Split,
+    #[cfg_attr(feature = "packed-edges", default)]
+    Packed,
+}
+
+/// Edge-level difference between two graph versions, as returned by [`GraphCSR::diff`].
+///
+/// Every list is sorted by `(src, dst)`. Rates are gross rates (fee undone), as in
+/// [`GraphCSR::to_edges`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphDiff {
+    /// Edges present only in the newer graph.
+    pub added: Vec<Edge>,
+    /// Edges present only in the older graph.
+    pub removed: Vec<Edge>,
+    /// `(src, dst, old_rate, new_rate)` for edges present in both with a different rate.
+    pub changed: Vec<(usize, usize, f64, f64)>,
+}
+
+impl GraphDiff {
+    /// Returns true if the two graphs have the same edges and rates.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
 /// Graph in Compressed Sparse Row (CSR) format for fast graph traversal.
 ///
 /// CSR format stores outgoing edges of each node contiguously in memory:
@@ -16,7 +281,11 @@ pub enum AddEdgeResult {
 ///
 /// This structure allows O(1) edge lookup per node and compact memory usage.
 /// Pending updates are batched and applied on rebuild to maintain efficiency.
+///
+/// With the `serde` feature enabled the whole structure (including pending
+/// updates) can be serialized, e.g. for on-disk snapshots.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GraphCSR {
     pub num_nodes: usize,
     pub node_pointers: Vec<usize>,
@@ -25,6 +294,72 @@ pub struct GraphCSR {
     pub edge_source_by_index: Vec<usize>,
     pub rebuild_limit: usize,
     pub pending_updates: Vec<Edge>,
+    /// Net multiplier applied to every rate before the log transform (`1 - fee`).
+    pub fee_multiplier: f64,
+    /// Per-pair overrides of `fee_multiplier`, e.g. for venues with their own fees.
+    /// Pairs without an entry use the global `fee_multiplier`.
+    #[cfg_attr(feature = "serde", serde(default, with = "edge_fee_serde"))]
+    pub edge_fee_multipliers: HashMap<(usize, usize), f64>,
+    /// `(src, dst)` pairs immune to [`GraphCSR::evict_edges`] (e.g. stablecoin pegs).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub pinned_edges: HashSet<(usize, usize)>,
+    /// How rates map to weights. Defaults to `NegLnRate` for older snapshots.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub weight_transform: Transform,
+    /// How duplicate `(src, dst)` quotes are resolved on rebuild.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub dedup_policy: DedupPolicy,
+    /// Whether `(a, b)` and `(b, a)` are deduplicated as one market on rebuild.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub dedup_key: DedupKey,
+    /// Drop edges quoted at exactly 1.0 (zero weight before fees). Off by default, so
+    /// such edges are kept like any other.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub drop_unit_rates: bool,
+    /// Maximum outgoing edges kept per node on rebuild; `None` (the default) is unbounded.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub max_out_degree: Option<usize>,
+    /// Bounds gross rates are clamped to before fees and the transform; `None` (the
+    /// default) stores rates as quoted.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rate_clamp: Option<KernelParams>,
+    /// Hard ceiling on `pending_updates`, enforced regardless of the rebuild policy;
+    /// `None` (the default) leaves the buffer bounded by the policy alone.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub max_pending: Option<usize>,
+    /// Optional callback run at the end of every successful `rebuild_with_edges`.
+    /// Not serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub on_rebuild: Option<RebuildHook>,
+    /// When buffered updates trigger a rebuild. Not serialized; defaults to [`PendingLimit`].
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub rebuild_policy: SharedRebuildPolicy,
+    /// `edge_targets` and `edge_weights` interleaved, refreshed whenever they change.
+    /// Not serialized.
+    #[cfg(feature = "packed-edges")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    packed: Vec<PackedEdge>,
+}
+
+/// Interleaves `targets` and `weights` into one array with the same indices.
+fn interleave(targets: &[usize], weights: &[f64]) -> Vec<PackedEdge> {
+    targets
+        .iter()
+        .zip(weights)
+        .map(|(&target, &weight)| PackedEdge { target, weight })
+        .collect()
+}
+
+/// Net multiplier (`1 - fee`) for a fee of `fee_bps` basis points.
+///
+/// # Errors
+/// `Error::InvalidGraph` unless `fee_bps` is in `0..10_000`: a negative fee would pay
+/// out on every hop, and 100% or more leaves a zero or negative rate.
+fn fee_multiplier(fee_bps: f64) -> Result<f64, Error> {
+    if !(0.0..10_000.0).contains(&fee_bps) {
+        return Err(Error::InvalidGraph);
+    }
+    Ok(1.0 - fee_bps / 10_000.0)
 }
 
 impl GraphCSR {
@@ -43,13 +378,148 @@ impl GraphCSR {
     ///
     /// # Returns
     /// A fully initialized `GraphCSR` instance.
+    ///
+    /// # Panics
+    /// If an edge's source is `>= num_nodes`. Use [`GraphCSR::try_from_edges`] for
+    /// untrusted input.
     pub fn from_edges(num_nodes: usize, edges: &mut [Edge], rebuild_limit: usize) -> Self {
+        Self::from_edges_with_fee(num_nodes, edges, rebuild_limit, 0.0)
+    }
+
+    /// Checked [`GraphCSR::from_edges`].
+    ///
+    /// # Errors
+    /// `Error::InvalidGraph` if any edge endpoint is `>= num_nodes`.
+    pub fn try_from_edges(
+        num_nodes: usize,
+        edges: &mut [Edge],
+        rebuild_limit: usize,
+    ) -> Result<Self, Error> {
+        Self::try_from_edges_with_fee(num_nodes, edges, rebuild_limit, 0.0)
+    }
+
+    /// Checked [`GraphCSR::from_edges_with_fee`].
+    ///
+    /// # Errors
+    /// `Error::InvalidGraph` if any edge endpoint is `>= num_nodes`, or if `fee_bps`
+    /// is outside `0..10_000`.
+    pub fn try_from_edges_with_fee(
+        num_nodes: usize,
+        edges: &mut [Edge],
+        rebuild_limit: usize,
+        fee_bps: f64,
+    ) -> Result<Self, Error> {
+        if edges
+            .iter()
+            .any(|&(u, v, _)| u >= num_nodes || v >= num_nodes)
+        {
+            return Err(Error::InvalidGraph);
+        }
+        Ok(Self::build(
+            num_nodes,
+            edges,
+            rebuild_limit,
+            fee_multiplier(fee_bps)?,
+            Transform::NegLnRate,
+        ))
+    }
+
+    /// Generates a random graph with exactly `num_nodes` nodes and `num_edges` edges.
+    ///
+    /// Sources and targets are drawn uniformly (never a self-loop; duplicate pairs are
+    /// possible) and rates uniformly from `rate_range`. The same `seed` always yields
+    /// the same graph, which makes this handy for benchmarks and property tests that
+    /// exercise the solver without the streaming pipeline. `rebuild_limit` is set to
+    /// `num_edges` so the graph is never rebuilt implicitly.
+    ///
+    /// # Panics
+    /// If `num_edges > 0` and `num_nodes < 2`, or if `rate_range` is empty.
+    #[cfg(any(test, feature = "random"))]
+    pub fn random(num_nodes: usize, num_edges: usize, rate_range: Range<f64>, seed: u64) -> Self {
+        assert!(
+            num_edges == 0 || num_nodes >= 2,
+            "a graph with edges but no self-loops needs at least two nodes"
+        );
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut edges: Vec<Edge> = (0..num_edges)
+            .map(|_| {
+                let src = rng.random_range(0..num_nodes);
+                // Skip `src` by drawing from the other num_nodes - 1 ids.
+                let dst = (src + rng.random_range(1..num_nodes)) % num_nodes;
+                (src, dst, rng.random_range(rate_range.clone()))
+            })
+            .collect();
+
+        Self::from_edges(num_nodes, &mut edges, num_edges.max(1))
+    }
+
+    /// Creates a new CSR graph whose weights are net of a global trading fee.
+    ///
+    /// Each weight is stored as `-ln(rate * (1 - fee))` where `fee = fee_bps / 10_000`,
+    /// so the solver directly detects cycles that remain profitable after fees.
+    /// Rates reported on reconstructed cycles are therefore net rates.
+    ///
+    /// # Arguments
+    /// - `num_nodes`: total number of nodes (graph indices: 0..num_nodes-1)
+    /// - `edges`: slice of gross `(src, dst, rate)` tuples
+    /// - `rebuild_limit`: number of pending updates before triggering rebuild
+    /// - `fee_bps`: fee charged per hop in basis points, in `0..10_000`
+    ///
+    /// # Panics
+    /// If `fee_bps` is outside `0..10_000` (a fee of 100% or more leaves no rate to
+    /// take the log of), or on the same input as [`GraphCSR::from_edges`]. Use
+    /// [`GraphCSR::try_from_edges_with_fee`] for untrusted input.
+    pub fn from_edges_with_fee(
+        num_nodes: usize,
+        edges: &mut [Edge],
+        rebuild_limit: usize,
+        fee_bps: f64,
+    ) -> Self {
+        let fee_multiplier = fee_multiplier(fee_bps)
+            .unwrap_or_else(|_| panic!("fee_bps must be in 0..10_000, got {}", fee_bps));
+        Self::build(
+            num_nodes,
+            edges,
+            rebuild_limit,
+            fee_multiplier,
+            Transform::NegLnRate,
+        )
+    }
+
+    /// Creates a new CSR graph whose edge values are mapped to weights by `transform`.
+    ///
+    /// With `Transform::Linear` the edge values are additive spreads stored
+    /// verbatim, and the solver looks for cycles whose values sum below zero.
+    /// Any [`WeightTransform`] can be plugged in via `Transform::custom`.
+    pub fn from_edges_with_transform(
+        num_nodes: usize,
+        edges: &mut [Edge],
+        rebuild_limit: usize,
+        transform: Transform,
+    ) -> Self {
+        Self::build(num_nodes, edges, rebuild_limit, 1.0, transform)
+    }
+
+    fn build(
+        num_nodes: usize,
+        edges: &mut [Edge],
+        rebuild_limit: usize,
+        fee_multiplier: f64,
+        weight_transform: Transform,
+    ) -> Self {
         edges.sort_by_key(|(src, _, _)| *src);
 
         let (node_pointers, edge_targets, edge_weights, edge_source_by_index) =
-            Self::build_csr_from_edges(num_nodes, edges);
-
-        Self {
+            Self::build_csr_from_edges(
+                num_nodes,
+                edges,
+                &|_, _| fee_multiplier,
+                &weight_transform,
+                None,
+            );
+
+        let mut graph = Self {
             num_nodes,
             node_pointers,
             edge_targets,
@@ -57,25 +527,209 @@ impl GraphCSR {
             edge_source_by_index,
             rebuild_limit,
             pending_updates: Vec::new(),
+            fee_multiplier,
+            edge_fee_multipliers: HashMap::new(),
+            pinned_edges: HashSet::new(),
+            weight_transform,
+            dedup_policy: DedupPolicy::default(),
+            dedup_key: DedupKey::default(),
+            drop_unit_rates: false,
+            max_out_degree: None,
+            rate_clamp: None,
+            max_pending: None,
+            on_rebuild: None,
+            rebuild_policy: SharedRebuildPolicy::default(),
+            #[cfg(feature = "packed-edges")]
+            packed: Vec::new(),
+        };
+        graph.repack();
+        graph
+    }
+
+    /// Sets how duplicate `(src, dst)` quotes are resolved on subsequent rebuilds.
+    pub fn with_dedup_policy(mut self, dedup_policy: DedupPolicy) -> Self {
+        self.dedup_policy = dedup_policy;
+        self
+    }
+
+    /// Sets whether reverse quotes of a pair are deduplicated as one market on
+    /// subsequent rebuilds (see [`DedupKey`]).
+    pub fn with_dedup_key(mut self, dedup_key: DedupKey) -> Self {
+        self.dedup_key = dedup_key;
+        self
+    }
+
+    /// Sets when buffered updates trigger a rebuild (default: [`PendingLimit`]).
+    pub fn with_rebuild_policy(mut self, policy: impl RebuildPolicy + 'static) -> Self {
+        self.set_rebuild_policy(policy);
+        self
+    }
+
+    /// Replaces the rebuild policy in place, e.g. on a graph loaded from a snapshot.
+    pub fn set_rebuild_policy(&mut self, policy: impl RebuildPolicy + 'static) {
+        self.rebuild_policy = SharedRebuildPolicy(Arc::new(policy));
+    }
+
+    /// Returns true if the rebuild policy asks for the pending updates to be committed,
+    /// or if they have reached the `max_pending` ceiling.
+    pub fn needs_rebuild(&self) -> bool {
+        self.max_pending
+            .is_some_and(|cap| self.pending_len() >= cap)
+            || self.rebuild_policy.0.should_rebuild(self)
+    }
+
+    /// Forces a rebuild once `max_pending` updates are buffered (`None` removes the
+    /// ceiling), whatever the rebuild policy says.
+    ///
+    /// With a large `rebuild_limit`, or a policy that rarely fires, the buffer would
+    /// otherwise grow without bound. Takes effect on the next add.
+    pub fn with_max_pending(mut self, max_pending: Option<usize>) -> Self {
+        self.max_pending = max_pending;
+        self
+    }
+
+    /// Drops (or, with `false`, keeps) edges whose gross rate is exactly 1.0.
+    ///
+    /// Such edges weigh zero: they never make a loop profitable, yet they occupy the
+    /// graph and let break-even (zero-sum) loops form, which detection has to wade
+    /// through. Enabling this removes committed 1.0 edges now; later rebuilds drop any
+    /// pair whose resolved quote is 1.0, and `try_update_weight` declines 1.0 so the
+    /// pair is removed by the next rebuild.
+    pub fn with_drop_unit_rates(mut self, drop_unit_rates: bool) -> Self {
+        self.drop_unit_rates = drop_unit_rates;
+        if drop_unit_rates {
+            let edges = self.to_edges();
+            if edges.iter().any(|&(_, _, rate)| rate == 1.0) {
+                let kept: Vec<Edge> = edges
+                    .into_iter()
+                    .filter(|&(_, _, rate)| rate != 1.0)
+                    .collect();
+                self.replace_edges(&kept);
+            }
+        }
+        self
+    }
+
+    /// Caps every node at `max_out_degree` outgoing edges (`None` removes the cap).
+    ///
+    /// Guards against a corrupt or adversarial feed fanning one node out to thousands
+    /// of pairs. Each rebuild keeps pinned edges first, then the lowest-weight (best
+    /// net rate) edges after duplicates are resolved per `dedup_policy`. Already
+    /// committed edges are trimmed on the next rebuild.
+    pub fn with_max_out_degree(mut self, max_out_degree: Option<usize>) -> Self {
+        self.max_out_degree = max_out_degree;
+        self
+    }
+
+    /// Clamps every gross rate into `rate_clamp`'s `[min_r, max_r]` before fees and the
+    /// weight transform (`None` removes the clamp).
+    ///
+    /// A fat-finger quote such as 1e9 would otherwise produce a huge negative weight
+    /// and a spurious, massively profitable cycle. Committed edges are re-weighted now;
+    /// their stored rates become the clamped ones.
+    pub fn with_rate_clamp(mut self, rate_clamp: Option<KernelParams>) -> Self {
+        self.rate_clamp = rate_clamp;
+        if rate_clamp.is_some() {
+            let edges = self.to_edges();
+            self.replace_edges(&edges);
+        }
+        self
+    }
+
+    /// Charges `fee_bps` on the `src -> dst` pair instead of the global fee.
+    ///
+    /// Committed `src -> dst` edges are re-weighted in place (no rebuild), and later
+    /// rebuilds keep applying the override.
+    ///
+    /// # Errors
+    /// `Error::InvalidGraph` if `fee_bps` is outside `0..10_000`; the graph is unchanged.
+    pub fn set_edge_fee_bps(&mut self, src: usize, dst: usize, fee_bps: f64) -> Result<(), Error> {
+        let new_multiplier = fee_multiplier(fee_bps)?;
+        let old_multiplier = self.fee_multiplier_for(src, dst);
+        self.edge_fee_multipliers.insert((src, dst), new_multiplier);
+
+        if src >= self.num_nodes {
+            return Ok(());
+        }
+        for i in self.node_pointers[src]..self.node_pointers[src + 1] {
+            if self.edge_targets[i] == dst {
+                let transform = &self.weight_transform;
+                let gross =
+                    transform.remove_fee(transform.inverse(self.edge_weights[i]), old_multiplier);
+                let weight = transform.forward(transform.apply_fee(gross, new_multiplier));
+                self.set_edge_weight(i, weight);
+            }
         }
+        Ok(())
+    }
+
+    /// Net multiplier (`1 - fee`) applied to `src -> dst` rates: the per-pair override
+    /// if one is set, otherwise the global `fee_multiplier`.
+    pub fn fee_multiplier_for(&self, src: usize, dst: usize) -> f64 {
+        self.edge_fee_multipliers
+            .get(&(src, dst))
+            .copied()
+            .unwrap_or(self.fee_multiplier)
+    }
+
+    /// Weight of a `src -> dst` quote at gross `rate`, net of the pair's fee where the
+    /// transform applies fees.
+    fn net_weight(&self, src: usize, dst: usize, rate: f64) -> f64 {
+        let transform = &self.weight_transform;
+        transform.forward(transform.apply_fee(rate, self.fee_multiplier_for(src, dst)))
+    }
+
+    /// Gross rate behind the stored `src -> dst` `weight`; undoes [`GraphCSR::net_weight`].
+    fn gross_value(&self, src: usize, dst: usize, weight: f64) -> f64 {
+        let transform = &self.weight_transform;
+        transform.remove_fee(transform.inverse(weight), self.fee_multiplier_for(src, dst))
+    }
+
+    /// Gross (pre-fee) rate of the committed `src -> dst` edge, or `None` if there is
+    /// none. Pending updates are not consulted; with parallel edges the first one in
+    /// CSR order is used.
+    pub fn gross_rate(&self, src: usize, dst: usize) -> Option<f64> {
+        if src >= self.num_nodes {
+            return None;
+        }
+        (self.node_pointers[src]..self.node_pointers[src + 1])
+            .find(|&i| self.edge_targets[i] == dst)
+            .map(|i| self.gross_value(src, dst, self.edge_weights[i]))
+    }
+
+    /// Registers `hook` to be called after every successful rebuild (e.g. to
+    /// invalidate caches or trigger a search).
+    ///
+    /// The hook runs synchronously inside `rebuild_with_edges`, so in the executor
+    /// it runs while the writer holds the graph's write lock; keep it cheap.
+    pub fn set_on_rebuild(&mut self, hook: impl Fn(&GraphCSR) + Send + Sync + 'static) {
+        self.on_rebuild = Some(RebuildHook::new(hook));
     }
 
     /// Internal helper to construct all necessary arrays for the Compressed Sparse Row (CSR) format.
     ///
     /// This function uses the efficient two-pass counting technique to build the CSR index
-    /// and applies a negative-log transformation to each exchange rate, preparing the graph
-    /// for shortest-path or arbitrage detection algorithms.
+    /// and applies `transform` (by default a negative-log transformation) to each edge value,
+    /// preparing the graph for shortest-path or arbitrage detection algorithms.
+    ///
+    /// Every source must be `< num_nodes`; callers validate untrusted input first (see
+    /// [`GraphCSR::try_from_edges`]). The prefix sums are bounded by `edges.len()`, so
+    /// they cannot overflow.
     ///
     /// # Arguments
     /// * `num_nodes`: The total number of vertices (|V|).
     /// * `edges`: A slice of raw edge tuples `(u, v, rate)`.
+    /// * `fee_multiplier`: Net multiplier (`1 - fee`) for each `(u, v)` rate, folded in
+    ///   by the transform's `apply_fee` (a no-op for `Linear`).
+    /// * `transform`: How each raw value is mapped to a weight.
+    /// * `rate_clamp`: Bounds each rate is clamped to before the fee, if any.
     ///
     /// # Returns
     /// A tuple containing the four core arrays:
     /// 1. `node_pointers`: Stores the starting index of each node’s outgoing edges
     ///    in the flattened edge arrays (size |V| + 1).
     /// 2. `edge_targets`: Stores the destination node `v` for each edge.
-    /// 3. `edge_weights`: Stores the transformed edge weights `w = -ln(rate)` for use by the SPFA solver.
+    /// 3. `edge_weights`: Stores the transformed edge weights (e.g. `w = -ln(rate * fee_multiplier)`) for use by the SPFA solver.
     /// 4. `edge_source_by_index`: Maps each edge index back to its source node `u`.
     ///
     ///    This array enables **O(1) reverse lookups** from any edge index to its originating source node,
@@ -86,6 +740,9 @@ impl GraphCSR {
     fn build_csr_from_edges(
         num_nodes: usize,
         edges: &[Edge],
+        fee_multiplier: &dyn Fn(usize, usize) -> f64,
+        transform: &dyn WeightTransform,
+        rate_clamp: Option<KernelParams>,
     ) -> (Vec<usize>, Vec<usize>, Vec<f64>, Vec<usize>) {
         let m = edges.len();
         let mut node_pointers = vec![0; num_nodes + 1];
@@ -106,7 +763,8 @@ impl GraphCSR {
 
         for &(u, v, rate) in edges {
             let pos = cursor[u]; // Get the next available position for node 'u'
-            edge_weights[pos] = -rate.ln();
+            let rate = rate_clamp.map_or(rate, |bounds| bounds.clamp(rate));
+            edge_weights[pos] = transform.forward(transform.apply_fee(rate, fee_multiplier(u, v)));
             edge_targets[pos] = v;
             edge_source_by_index[pos] = u;
 
@@ -122,6 +780,128 @@ impl GraphCSR {
         )
     }
 
+    /// `edge_targets` and `edge_weights` interleaved into one array with the same indices.
+    ///
+    /// With the `packed-edges` feature the graph keeps this array up to date and it is
+    /// borrowed; otherwise, or before the first rebuild of a deserialized graph, it is
+    /// built in O(E).
+    pub fn packed_edges(&self) -> Cow<'_, [PackedEdge]> {
+        #[cfg(feature = "packed-edges")]
+        if self.packed.len() == self.edge_targets.len() {
+            return Cow::Borrowed(&self.packed);
+        }
+        Cow::Owned(interleave(&self.edge_targets, &self.edge_weights))
+    }
+
+    /// Refreshes the packed edge array after the CSR arrays were replaced.
+    fn repack(&mut self) {
+        #[cfg(feature = "packed-edges")]
+        {
+            self.packed = interleave(&self.edge_targets, &self.edge_weights);
+        }
+    }
+
+    /// Sets the weight of CSR edge `i`.
+    ///
+    /// Goes through here rather than writing `edge_weights[i]` so that, with the
+    /// `packed-edges` feature, the packed edge array stays in sync.
+    pub fn set_edge_weight(&mut self, i: usize, weight: f64) {
+        self.edge_weights[i] = weight;
+        #[cfg(feature = "packed-edges")]
+        if let Some(edge) = self.packed.get_mut(i) {
+            edge.weight = weight;
+        }
+    }
+
+    /// Extracts the committed CSR edges as gross `(src, dst, rate)` tuples, in CSR order.
+    ///
+    /// Rates are recovered from the stored weights with the fee undone, so feeding
+    /// the result back into `from_edges_with_fee` reproduces the same graph.
+    /// Pending (not yet rebuilt) updates are not included.
+    pub fn to_edges(&self) -> Vec<Edge> {
+        self.to_edges_with_capacity(0)
+    }
+
+    fn to_edges_with_capacity(&self, extra: usize) -> Vec<Edge> {
+        let mut edges: Vec<Edge> = Vec::with_capacity(self.edge_targets.len() + extra);
+
+        for src in 0..self.num_nodes {
+            let start = self.node_pointers[src];
+            let end = self.node_pointers[src + 1];
+            for j in start..end {
+                let dst = self.edge_targets[j];
+                // Undo the fee so it is not compounded on every rebuild.
+                edges.push((src, dst, self.gross_value(src, dst, self.edge_weights[j])));
+            }
+        }
+
+        edges
+    }
+
+    /// Sums the stored weights along the path `nodes[0] -> nodes[1] -> ... -> nodes[k]`.
+    ///
+    /// For the default transform this is the log of the path's inverse product rate,
+    /// so a closed path (first node repeated at the end) with a negative result is a
+    /// profitable cycle. If several edges connect a pair, the first one in CSR order
+    /// is used. A path with fewer than two nodes has weight 0.
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidGraph` if a node is out of bounds or a consecutive pair
+    /// has no edge.
+    pub fn path_log_weight(&self, nodes: &[usize]) -> Result<f64, Error> {
+        nodes.windows(2).try_fold(0.0, |sum, hop| {
+            let (u, v) = (hop[0], hop[1]);
+            if u >= self.num_nodes {
+                return Err(Error::InvalidGraph);
+            }
+
+            (self.node_pointers[u]..self.node_pointers[u + 1])
+                .find(|&i| self.edge_targets[i] == v)
+                .map(|i| sum + self.edge_weights[i])
+                .ok_or(Error::InvalidGraph)
+        })
+    }
+
+    /// Lists the committed edges added, removed, and re-rated going from `self` to `other`.
+    ///
+    /// Edges are matched by `(src, dst)`; if a graph has parallel edges for a pair,
+    /// the last one in CSR order is compared. Rates within a relative `1e-12` of each
+    /// other count as unchanged, absorbing the round-off of recovering rates from
+    /// weights across rebuilds. Pending updates are ignored.
+    pub fn diff(&self, other: &GraphCSR) -> GraphDiff {
+        let old: HashMap<(usize, usize), f64> = self
+            .to_edges()
+            .into_iter()
+            .map(|(u, v, rate)| ((u, v), rate))
+            .collect();
+        let new: HashMap<(usize, usize), f64> = other
+            .to_edges()
+            .into_iter()
+            .map(|(u, v, rate)| ((u, v), rate))
+            .collect();
+
+        let mut diff = GraphDiff::default();
+        for (&(u, v), &new_rate) in &new {
+            match old.get(&(u, v)) {
+                None => diff.added.push((u, v, new_rate)),
+                Some(&old_rate) if (old_rate - new_rate).abs() > 1e-12 * old_rate.abs() => {
+                    diff.changed.push((u, v, old_rate, new_rate))
+                }
+                Some(_) => {}
+            }
+        }
+        for (&(u, v), &old_rate) in &old {
+            if !new.contains_key(&(u, v)) {
+                diff.removed.push((u, v, old_rate));
+            }
+        }
+
+        diff.added.sort_by_key(|&(u, v, _)| (u, v));
+        diff.removed.sort_by_key(|&(u, v, _)| (u, v));
+        diff.changed.sort_by_key(|&(u, v, _, _)| (u, v));
+        diff
+    }
+
     /// O(1) lookup for the source node of a given edge index.
     ///
     /// # Errors
@@ -133,12 +913,171 @@ impl GraphCSR {
             .ok_or(Error::InvalidGraph)
     }
 
+    /// Extracts the subgraph induced by `nodes`, relabelled so that `nodes[i]` becomes node `i`.
+    ///
+    /// Only edges with both endpoints in `nodes` are kept. Weights are copied verbatim
+    /// and the fee multipliers (relabelled) and weight transform are inherited, so
+    /// cycles found in the subgraph have exactly the same weights as in `self`.
+    pub fn induced_subgraph(&self, nodes: &[usize]) -> GraphCSR {
+        self.induced_subgraph_masked(nodes, |_, _| false)
+    }
+
+    /// [`GraphCSR::induced_subgraph`] that also drops every edge for which
+    /// `skip(from, to)` holds, with `from` and `to` as node ids of `self`.
+    ///
+    /// Masks edges for a single search without copying the full graph first.
+    pub fn induced_subgraph_masked(
+        &self,
+        nodes: &[usize],
+        skip: impl Fn(usize, usize) -> bool,
+    ) -> GraphCSR {
+        let local: HashMap<usize, usize> = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, &node)| (node, i))
+            .collect();
+
+        let mut node_pointers = Vec::with_capacity(nodes.len() + 1);
+        let mut edge_targets = Vec::new();
+        let mut edge_weights = Vec::new();
+        let mut edge_source_by_index = Vec::new();
+        node_pointers.push(0);
+
+        for (i, &u) in nodes.iter().enumerate() {
+            for e in self.node_pointers[u]..self.node_pointers[u + 1] {
+                if let Some(&v) = local.get(&self.edge_targets[e])
+                    && !skip(u, self.edge_targets[e])
+                {
+                    edge_targets.push(v);
+                    edge_weights.push(self.edge_weights[e]);
+                    edge_source_by_index.push(i);
+                }
+            }
+            node_pointers.push(edge_targets.len());
+        }
+
+        let mut sub = GraphCSR {
+            num_nodes: nodes.len(),
+            node_pointers,
+            edge_targets,
+            edge_weights,
+            edge_source_by_index,
+            rebuild_limit: self.rebuild_limit,
+            pending_updates: Vec::new(),
+            fee_multiplier: self.fee_multiplier,
+            edge_fee_multipliers: self
+                .edge_fee_multipliers
+                .iter()
+                .filter_map(|(&(u, v), &multiplier)| {
+                    Some(((*local.get(&u)?, *local.get(&v)?), multiplier))
+                })
+                .collect(),
+            pinned_edges: self
+                .pinned_edges
+                .iter()
+                .filter_map(|&(u, v)| Some((*local.get(&u)?, *local.get(&v)?)))
+                .collect(),
+            weight_transform: self.weight_transform.clone(),
+            dedup_policy: self.dedup_policy,
+            dedup_key: self.dedup_key,
+            drop_unit_rates: self.drop_unit_rates,
+            max_out_degree: self.max_out_degree,
+            rate_clamp: self.rate_clamp,
+            max_pending: self.max_pending,
+            on_rebuild: None,
+            rebuild_policy: self.rebuild_policy.clone(),
+            #[cfg(feature = "packed-edges")]
+            packed: Vec::new(),
+        };
+        sub.repack();
+        sub
+    }
+
+    /// Returns true if a `src -> dst` quote at `rate` maps to a finite weight under the
+    /// pair's fee (per-pair override or global) and this graph's transform.
+    pub fn is_valid_rate(&self, src: usize, dst: usize, rate: f64) -> bool {
+        self.net_weight(src, dst, rate).is_finite()
+    }
+
+    /// Net value (rate after fees, or raw spread) of the edge at `edge_idx`.
+    pub fn edge_value(&self, edge_idx: usize) -> f64 {
+        self.weight_transform.inverse(self.edge_weights[edge_idx])
+    }
+
+    /// Finds the most profitable triangular (3-hop) cycle `i -> j -> k -> i`.
+    ///
+    /// Triangular arbitrage is by far the most common shape and can be found by
+    /// brute force over the CSR adjacency, without running a full SPFA pass.
+    /// Each triangle is only visited from its smallest node id, so rotations of
+    /// the same loop are not evaluated repeatedly.
+    ///
+    /// # Complexity
+    /// For every edge `i -> j`, all edges `j -> k` are enumerated and `k`'s
+    /// adjacency is scanned for a closing edge `k -> i`. This is
+    /// **O(E · d_max²)** in the worst case (roughly O(V · E) on dense graphs),
+    /// so it is intended for sparse market graphs.
+    ///
+    /// # Returns
+    /// `Some(cycle)` with the lowest `log_rate_sum` among profitable triangles,
+    /// or `None` if no triangle has a product rate above 1.0.
+    pub fn best_triangle(&self) -> Option<WeightedCycle> {
+        let mut best: Option<([usize; 3], f64)> = None;
+
+        for i in 0..self.num_nodes {
+            for ij in self.node_pointers[i]..self.node_pointers[i + 1] {
+                let j = self.edge_targets[ij];
+                if j <= i {
+                    continue;
+                }
+
+                for jk in self.node_pointers[j]..self.node_pointers[j + 1] {
+                    let k = self.edge_targets[jk];
+                    if k <= i || k == j {
+                        continue;
+                    }
+
+                    for ki in self.node_pointers[k]..self.node_pointers[k + 1] {
+                        if self.edge_targets[ki] != i {
+                            continue;
+                        }
+
+                        let sum =
+                            self.edge_weights[ij] + self.edge_weights[jk] + self.edge_weights[ki];
+                        if sum < 0.0 && best.is_none_or(|(_, best_sum)| sum < best_sum) {
+                            best = Some(([ij, jk, ki], sum));
+                        }
+                    }
+                }
+            }
+        }
+
+        best.map(|(edge_indices, log_rate_sum)| {
+            let path: Vec<Edge> = edge_indices
+                .iter()
+                .map(|&idx| {
+                    (
+                        self.edge_source_by_index[idx],
+                        self.edge_targets[idx],
+                        self.edge_value(idx),
+                    )
+                })
+                .collect();
+            let rates = path.iter().map(|&(_, _, rate)| rate).collect();
+
+            WeightedCycle {
+                path,
+                rates,
+                log_rate_sum,
+            }
+        })
+    }
+
     /// Adds multiple edges to the graph in a single batch update.
     ///
     /// Instead of immediately rebuilding the CSR structure on every edge insertion,
-    /// new edges are first accumulated in `pending_updates`. Once the number of
-    /// pending edges reaches the configured `rebuild_limit`, the graph is rebuilt
-    /// in one pass for efficiency.
+    /// new edges are first accumulated in `pending_updates`. Once the rebuild policy
+    /// asks for it (by default, when the number of pending edges reaches the configured
+    /// `rebuild_limit`), the graph is rebuilt in one pass for efficiency.
     ///
     /// # Why batching?
     /// Building the CSR structure (`rebuild()`) requires sorting and recomputing
@@ -153,29 +1092,168 @@ impl GraphCSR {
     ///
     /// This design is ideal when edges are added in bursts and immediate consistency is not required.
     #[allow(dead_code)]
-    fn add_edges(&mut self, edges: Vec<Edge>) {
+    fn add_edges(&mut self, edges: Vec<Edge>) -> Result<(), Error> {
         let size = edges.len();
         self.pending_updates.extend(edges);
         println!("{} edges added to graph pending buffer", size);
 
-        if self.pending_updates.len() >= self.rebuild_limit {
+        if self.needs_rebuild() {
             println!("Graph rebuild limit reached. Re-building CSR");
-            self.rebuild();
+            self.rebuild()?;
+        }
+        Ok(())
+    }
+
+    /// Attempts to add a batch of new edges to the internal buffer.
+    /// If the rebuild policy fires, it atomically extracts (via O(1) swap)
+    /// the full accumulated edge list and signals that a rebuild is required.
+    pub fn add_edges_and_extract_data(&mut self, edges: Vec<Edge>) -> AddEdgeResult {
+        self.pending_updates.extend(edges);
+
+        if self.needs_rebuild() {
+            let edges_to_rebuild = std::mem::take(&mut self.pending_updates);
+
+            return AddEdgeResult::RebuildNeeded(edges_to_rebuild);
+        }
+        AddEdgeResult::Success
+    }
+
+    /// Removes and returns every buffered update, whatever the rebuild policy, e.g. so
+    /// the writer can commit them with `rebuild_with_edges` before shutting down.
+    pub fn take_pending(&mut self) -> Vec<Edge> {
+        std::mem::take(&mut self.pending_updates)
+    }
+
+    /// Number of committed edges (pending updates excluded).
+    pub fn num_edges(&self) -> usize {
+        self.edge_targets.len()
+    }
+
+    /// Number of buffered updates not yet committed by a rebuild.
+    pub fn pending_len(&self) -> usize {
+        self.pending_updates.len()
+    }
+
+    /// Buffered updates not yet committed by a rebuild, in arrival order.
+    ///
+    /// Read-only view for tests and diagnostics; prefer it to the field, whose
+    /// representation may change.
+    pub fn pending_updates(&self) -> &[Edge] {
+        &self.pending_updates
+    }
+
+    /// Resets the graph to the empty state, e.g. on a feed reset.
+    ///
+    /// All nodes, committed edges and pending updates are dropped. Settings are kept:
+    /// `rebuild_limit`, fees (global and per-pair), pins, the weight transform, dedup,
+    /// unit-rate and out-degree policies, the pending ceiling, the rebuild policy and the
+    /// `on_rebuild` hook.
+    pub fn clear(&mut self) {
+        self.num_nodes = 0;
+        self.node_pointers = vec![0];
+        self.edge_targets.clear();
+        self.edge_weights.clear();
+        self.edge_source_by_index.clear();
+        self.pending_updates.clear();
+        self.repack();
+    }
+
+    /// Grows the graph to at least `num_nodes` nodes without a rebuild.
+    ///
+    /// New nodes are appended with no outgoing edges, which only extends
+    /// `node_pointers` (O(new nodes)); existing edges are untouched. Does nothing if
+    /// the graph is already large enough.
+    pub fn grow_nodes(&mut self, num_nodes: usize) {
+        if num_nodes <= self.num_nodes {
+            return;
+        }
+
+        let end = self.node_pointers.last().copied().unwrap_or(0);
+        self.node_pointers.resize(num_nodes + 1, end);
+        self.num_nodes = num_nodes;
+    }
+
+    /// Re-rates the committed `src -> dst` edge in place, without a rebuild (O(out-degree)).
+    ///
+    /// Returns false, leaving the graph unchanged, if the pair has no committed edge,
+    /// `rate` would produce a non-finite weight, or `rate` is 1.0 with
+    /// `drop_unit_rates` set; such updates must go through `rebuild_with_edges`.
+    /// Parallel `src -> dst` edges are all re-rated. With `DedupKey::Undirected`, the
+    /// `dst -> src` side is re-rated to `1 / rate` too.
+    pub fn try_update_weight(&mut self, src: usize, dst: usize, rate: f64) -> bool {
+        if self.dedup_key == DedupKey::Undirected && src != dst && dst < self.num_nodes {
+            let updated = self.update_directed_weight(src, dst, rate);
+            return self.update_directed_weight(dst, src, 1.0 / rate) | updated;
+        }
+        self.update_directed_weight(src, dst, rate)
+    }
+
+    fn update_directed_weight(&mut self, src: usize, dst: usize, rate: f64) -> bool {
+        if src >= self.num_nodes
+            || !self.is_valid_rate(src, dst, rate)
+            || (self.drop_unit_rates && rate == 1.0)
+        {
+            return false;
+        }
+
+        let rate = self.rate_clamp.map_or(rate, |bounds| bounds.clamp(rate));
+        let weight = self.net_weight(src, dst, rate);
+        let mut updated = false;
+        for i in self.node_pointers[src]..self.node_pointers[src + 1] {
+            if self.edge_targets[i] == dst {
+                self.set_edge_weight(i, weight);
+                updated = true;
+            }
         }
+        updated
     }
 
-    /// Attempts to add a batch of new edges to the internal buffer.
-    /// If the buffer limit is reached, it atomically extracts (via O(1) swap)
-    /// the full accumulated edge list and signals that a rebuild is required.
-    pub fn add_edges_and_extract_data(&mut self, edges: Vec<Edge>) -> AddEdgeResult {
-        self.pending_updates.extend(edges);
+    /// Protects every `src -> dst` edge (present or future) from [`GraphCSR::evict_edges`].
+    pub fn pin_edge(&mut self, src: usize, dst: usize) {
+        self.pinned_edges.insert((src, dst));
+    }
 
-        if self.pending_updates.len() >= self.rebuild_limit {
-            let edges_to_rebuild = std::mem::take(&mut self.pending_updates);
+    /// Makes `src -> dst` evictable again. Returns false if it was not pinned.
+    pub fn unpin_edge(&mut self, src: usize, dst: usize) -> bool {
+        self.pinned_edges.remove(&(src, dst))
+    }
 
-            return AddEdgeResult::RebuildNeeded(edges_to_rebuild);
+    pub fn is_pinned(&self, src: usize, dst: usize) -> bool {
+        self.pinned_edges.contains(&(src, dst))
+    }
+
+    /// Removes every committed, unpinned edge for which `should_evict(src, dst)` holds,
+    /// e.g. edges whose last quote is older than a staleness cutoff.
+    ///
+    /// The CSR arrays are recomputed (O(E)); the node count and pending updates are
+    /// unchanged. Returns the evicted edges as gross `(src, dst, rate)` tuples.
+    pub fn evict_edges(&mut self, should_evict: impl Fn(usize, usize) -> bool) -> Vec<Edge> {
+        let (evicted, kept): (Vec<Edge>, Vec<Edge>) = self
+            .to_edges()
+            .into_iter()
+            .partition(|&(u, v, _)| !self.is_pinned(u, v) && should_evict(u, v));
+        if !evicted.is_empty() {
+            self.replace_edges(&kept);
         }
-        AddEdgeResult::Success
+        evicted
+    }
+
+    /// Recomputes the CSR arrays from `edges` (gross rates, sorted by source) in
+    /// O(E), keeping the node count.
+    fn replace_edges(&mut self, edges: &[Edge]) {
+        let (node_pointers, edge_targets, edge_weights, edge_source_by_index) =
+            Self::build_csr_from_edges(
+                self.num_nodes,
+                edges,
+                &|u, v| self.fee_multiplier_for(u, v),
+                &self.weight_transform,
+                self.rate_clamp,
+            );
+        self.node_pointers = node_pointers;
+        self.edge_targets = edge_targets;
+        self.edge_weights = edge_weights;
+        self.edge_source_by_index = edge_source_by_index;
+        self.repack();
     }
 
     /// Initiates a full, in-place CSR rebuild using the *pending updates* buffer.
@@ -184,7 +1262,7 @@ impl GraphCSR {
     /// concurrency model, the external Writer should call `rebuild_with_edges`
     /// instead to prevent excessive lock times.
     #[allow(dead_code)]
-    fn rebuild(&mut self) {
+    fn rebuild(&mut self) -> Result<(), Error> {
         let new_edges = std::mem::take(&mut self.pending_updates);
 
         self.rebuild_with_edges(new_edges)
@@ -194,45 +1272,214 @@ impl GraphCSR {
     ///
     /// This is the **public interface** for the Writer's Phase 2 commit.
     /// Steps involve extracting existing CSR edges, merging them with `new_edges`,
-    /// sorting/deduplicating, recomputing the node count, and committing the
-    /// new CSR arrays. The cost is high (O(E log E)).
-    pub fn rebuild_with_edges(&mut self, new_edges: Vec<Edge>) {
-        let mut edges: Vec<(usize, usize, f64)> =
-            Vec::with_capacity(self.edge_targets.len() + new_edges.len());
+    /// sorting/deduplicating (per `dedup_policy`), recomputing the node count, dropping
+    /// 1.0-rate pairs if `drop_unit_rates` is set, trimming each node to `max_out_degree`
+    /// edges, and committing the new CSR arrays.
+    /// The cost is high (O(E log E)).
+    ///
+    /// The node count never shrinks: nodes added via `grow_nodes` (or present at
+    /// construction) are kept even if they have no edges.
+    ///
+    /// # Errors
+    /// - `Error::NonFiniteWeight(i)` if `new_edges[i]` has a rate whose weight is NaN
+    ///   or infinite (zero, negative, or non-finite rates).
+    /// - `Error::InvalidGraph` if a node id is `usize::MAX`, since the node
+    ///   count (`max_id + 1`) would overflow.
+    ///
+    /// On error the graph is left unchanged and `on_rebuild` is not invoked.
+    pub fn rebuild_with_edges(&mut self, new_edges: Vec<Edge>) -> Result<(), Error> {
+        if let Some(idx) = new_edges
+            .iter()
+            .position(|&(u, v, rate)| !self.is_valid_rate(u, v, rate))
+        {
+            return Err(Error::NonFiniteWeight(idx));
+        }
 
         // Extract existing edges
-        for src in 0..self.num_nodes {
-            let start = self.node_pointers[src];
-            let end = self.node_pointers[src + 1];
-            for j in start..end {
-                let dst = self.edge_targets[j];
-                let rate = (-self.edge_weights[j]).exp();
-                edges.push((src, dst, rate));
+        let mut edges = self.to_edges_with_capacity(new_edges.len());
+
+        let undirected = self.dedup_key == DedupKey::Undirected;
+        let new_edges = if undirected {
+            edges = Self::orient_committed(edges);
+            Self::orient(new_edges)
+        } else {
+            new_edges
+        };
+
+        let mut new_edges = match self.dedup_policy {
+            DedupPolicy::WeightedAverage => Self::average_quotes(new_edges),
+            _ => new_edges,
+        };
+        edges.append(&mut new_edges);
+
+        // Sort by (src, dst), breaking ties by insertion sequence so the preferred
+        // quote comes first in each run and survives the dedup below.
+        let mut sequenced: Vec<(usize, Edge)> = edges.into_iter().enumerate().collect();
+        match self.dedup_policy {
+            DedupPolicy::KeepEarliest => {
+                sequenced.sort_unstable_by_key(|&(seq, (src, dst, _))| (src, dst, seq))
             }
+            _ => sequenced
+                .sort_unstable_by_key(|&(seq, (src, dst, _))| (src, dst, std::cmp::Reverse(seq))),
+        }
+        let mut edges: Vec<Edge> = sequenced.into_iter().map(|(_, edge)| edge).collect();
+
+        match self.dedup_policy {
+            DedupPolicy::KeepMax => edges.dedup_by(|later, kept| {
+                let same = (later.0, later.1) == (kept.0, kept.1);
+                if same {
+                    kept.2 = kept.2.max(later.2);
+                }
+                same
+            }),
+            _ => edges.dedup_by_key(|(src, dst, _)| (*src, *dst)),
         }
 
-        let mut new_edges = new_edges;
-        edges.append(&mut new_edges);
+        if undirected {
+            edges = Self::mirror(edges);
+        }
 
-        //Sort and deduplicate by (src, dst)
-        edges.sort_by_key(|&(src, dst, _)| (src, dst));
-        edges.reverse();
-        edges.dedup_by_key(|(src, dst, _)| (*src, *dst));
+        let num_nodes = match edges.iter().flat_map(|&(u, v, _)| [u, v]).max() {
+            Some(max_id) => max_id.checked_add(1).ok_or(Error::InvalidGraph)?,
+            None => 0,
+        }
+        .max(self.num_nodes);
 
-        let num_nodes = edges
-            .iter()
-            .flat_map(|&(u, v, _)| [u, v])
-            .max()
-            .map_or(0, |max_id| max_id + 1);
+        // After dedup, so a 1.0 quote removes its pair instead of leaving the old rate.
+        if self.drop_unit_rates {
+            edges.retain(|&(_, _, rate)| rate != 1.0);
+        }
+
+        if let Some(cap) = self.max_out_degree {
+            edges = self.cap_out_degree(edges, cap);
+        }
 
         let (node_pointers, edge_targets, edge_weights, edge_source_by_index) =
-            Self::build_csr_from_edges(num_nodes, &edges);
+            Self::build_csr_from_edges(
+                num_nodes,
+                &edges,
+                &|u, v| self.fee_multiplier_for(u, v),
+                &self.weight_transform,
+                self.rate_clamp,
+            );
 
         self.num_nodes = num_nodes;
         self.node_pointers = node_pointers;
         self.edge_targets = edge_targets;
         self.edge_weights = edge_weights;
         self.edge_source_by_index = edge_source_by_index;
+        self.repack();
+
+        if let Some(RebuildHook(hook)) = &self.on_rebuild {
+            hook(self);
+        }
+
+        Ok(())
+    }
+
+    /// Keeps at most `cap` edges per source from `edges` (sorted by `(src, dst)`):
+    /// pinned edges first, then the lowest weights. The `(src, dst)` order is preserved.
+    fn cap_out_degree(&self, edges: Vec<Edge>, cap: usize) -> Vec<Edge> {
+        let mut kept = Vec::with_capacity(edges.len());
+        for block in edges.chunk_by(|a, b| a.0 == b.0) {
+            if block.len() <= cap {
+                kept.extend_from_slice(block);
+                continue;
+            }
+
+            let weight = |&(u, v, rate): &Edge| self.net_weight(u, v, rate);
+            let mut ranked = block.to_vec();
+            ranked.sort_by(|a, b| {
+                let unpinned = |e: &Edge| !self.pinned_edges.contains(&(e.0, e.1));
+                unpinned(a)
+                    .cmp(&unpinned(b))
+                    .then(weight(a).total_cmp(&weight(b)))
+            });
+            ranked.truncate(cap);
+            ranked.sort_by_key(|&(_, dst, _)| dst);
+            kept.append(&mut ranked);
+        }
+        kept
+    }
+
+    /// Rewrites every `b -> a` quote with `a < b` as the equivalent `a -> b` quote.
+    fn orient(edges: Vec<Edge>) -> Vec<Edge> {
+        edges
+            .into_iter()
+            .map(|(u, v, rate)| {
+                if u > v {
+                    (v, u, 1.0 / rate)
+                } else {
+                    (u, v, rate)
+                }
+            })
+            .collect()
+    }
+
+    /// [`GraphCSR::orient`] for committed edges, which are usually already mirrored:
+    /// a `b -> a` edge is only used when its `a -> b` side is missing (e.g. committed
+    /// before `DedupKey::Undirected` was set), so re-inverting never adds rounding drift.
+    fn orient_committed(edges: Vec<Edge>) -> Vec<Edge> {
+        let forward: HashSet<(usize, usize)> = edges
+            .iter()
+            .filter(|&&(u, v, _)| u < v)
+            .map(|&(u, v, _)| (u, v))
+            .collect();
+        let edges = edges
+            .into_iter()
+            .filter(|&(u, v, _)| u <= v || !forward.contains(&(v, u)))
+            .collect();
+        Self::orient(edges)
+    }
+
+    /// Adds the inverse-rate `b -> a` side of every `a -> b` market, keeping the
+    /// result sorted by `(src, dst)`.
+    fn mirror(mut edges: Vec<Edge>) -> Vec<Edge> {
+        let reverse: Vec<Edge> = edges
+            .iter()
+            .filter(|&&(u, v, _)| u != v)
+            .map(|&(u, v, rate)| (v, u, 1.0 / rate))
+            .collect();
+        edges.extend(reverse);
+        edges.sort_unstable_by_key(|&(src, dst, _)| (src, dst));
+        edges
+    }
+
+    /// Collapses quotes for the same `(src, dst)` into their arithmetic mean rate.
+    fn average_quotes(mut quotes: Vec<Edge>) -> Vec<Edge> {
+        quotes.sort_by_key(|&(src, dst, _)| (src, dst));
+        quotes
+            .chunk_by(|a, b| (a.0, a.1) == (b.0, b.1))
+            .map(|group| {
+                let mean = group.iter().map(|&(_, _, rate)| rate).sum::<f64>() / group.len() as f64;
+                (group[0].0, group[0].1, mean)
+            })
+            .collect()
+    }
+}
+
+/// JSON maps need string keys, so per-pair fees are (de)serialized as `[src, dst, multiplier]` triples.
+#[cfg(feature = "serde")]
+mod edge_fee_serde {
+    use std::collections::HashMap;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        fees: &HashMap<(usize, usize), f64>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut triples: Vec<(usize, usize, f64)> =
+            fees.iter().map(|(&(u, v), &m)| (u, v, m)).collect();
+        triples.sort_by_key(|&(u, v, _)| (u, v));
+        triples.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<(usize, usize), f64>, D::Error> {
+        let triples = Vec::<(usize, usize, f64)>::deserialize(deserializer)?;
+        Ok(triples.into_iter().map(|(u, v, m)| ((u, v), m)).collect())
     }
 }
 
@@ -252,7 +1499,7 @@ mod tests {
         let expected_weights: Vec<f64> = edges.iter().map(|&(_, _, r)| -r.ln()).collect();
         assert_eq!(csr.edge_weights, expected_weights);
         assert_eq!(csr.num_nodes, 3);
-        assert!(csr.pending_updates.is_empty());
+        assert!(csr.pending_updates().is_empty());
         assert_eq!(csr.rebuild_limit, 3);
     }
 
@@ -307,25 +1554,44 @@ mod tests {
         let mut csr = GraphCSR::from_edges(3, &mut [(0, 1, 1.0), (1, 2, 1.5)], 2);
 
         csr.pending_updates = vec![(2, 0, 2.0)];
-        csr.rebuild();
+        csr.rebuild().unwrap();
 
         assert_eq!(csr.edge_targets.len(), 3);
         assert_eq!(csr.edge_targets.iter().sum::<usize>(), 1 + 2);
 
         // The pending buffer must be empty after rebuild() runs.
-        assert!(csr.pending_updates.is_empty());
+        assert!(csr.pending_updates().is_empty());
     }
 
     #[test]
     fn rebuild_deduplicates_by_keeping_latest() {
         let mut csr = GraphCSR::from_edges(2, &mut [(0, 1, 1.0)], 2);
         csr.pending_updates = vec![(0, 1, 2.0)];
-        csr.rebuild();
+        csr.rebuild().unwrap();
 
         assert_eq!(csr.edge_targets, vec![1]);
         assert_eq!(csr.edge_weights, vec![-2.0f64.ln()]);
 
-        assert!(csr.pending_updates.is_empty());
+        assert!(csr.pending_updates().is_empty());
+    }
+
+    #[test]
+    fn rebuild_reconciles_edge_count_across_existing_and_new_duplicates() {
+        let mut csr = GraphCSR::from_edges(3, &mut [(0, 1, 1.0), (0, 2, 1.5)], 2);
+
+        csr.rebuild_with_edges(vec![(0, 1, 2.0), (1, 0, 0.5)])
+            .unwrap();
+
+        assert_eq!(csr.edge_targets.len(), 3);
+        assert_eq!(csr.node_pointers, vec![0, 2, 3, 3]);
+
+        let mut edges = csr.to_edges();
+        edges.sort_by_key(|&(src, dst, _)| (src, dst));
+        let expected = [(0, 1, 2.0), (0, 2, 1.5), (1, 0, 0.5)];
+        for (&(u, v, rate), &(eu, ev, expected_rate)) in edges.iter().zip(&expected) {
+            assert_eq!((u, v), (eu, ev));
+            assert!((rate - expected_rate).abs() < 1e-12);
+        }
     }
 
     #[test]
@@ -333,7 +1599,7 @@ mod tests {
         let csr_original = GraphCSR::from_edges(2, &mut [(0, 1, 1.0)], 2);
         let mut csr = csr_original.clone();
 
-        csr.rebuild();
+        csr.rebuild().unwrap();
         assert_eq!(csr.node_pointers, csr_original.node_pointers);
         assert_eq!(csr.edge_targets, csr_original.edge_targets);
         assert_eq!(csr.edge_weights, csr_original.edge_weights);
@@ -343,7 +1609,7 @@ mod tests {
     fn rebuild_on_empty_graph() {
         let mut csr = GraphCSR::from_edges(0, &mut [], 1);
         csr.pending_updates = vec![(0, 1, 1.0)];
-        csr.rebuild();
+        csr.rebuild().unwrap();
 
         assert_eq!(csr.num_nodes, 2);
         assert_eq!(csr.edge_targets, vec![1]);
@@ -356,7 +1622,7 @@ mod tests {
         let mut csr = GraphCSR::from_edges(1000, &mut edges, 1000);
 
         csr.pending_updates = (0..1000).map(|i| (i, (i + 2) % 1000, 1.2)).collect();
-        csr.rebuild();
+        csr.rebuild().unwrap();
 
         assert_eq!(csr.num_nodes, 1000);
         assert_eq!(csr.edge_targets.len(), 2000);
@@ -367,39 +1633,773 @@ mod tests {
         let mut edges = vec![(0, 1, 1.0)];
         let mut csr = GraphCSR::from_edges(2, &mut edges, 3);
 
-        csr.add_edges(vec![(1, 0, 2.0)]);
+        csr.add_edges(vec![(1, 0, 2.0)]).unwrap();
 
-        assert_eq!(csr.pending_updates.len(), 1);
+        assert_eq!(csr.pending_len(), 1);
         assert_eq!(csr.edge_targets.len(), 1); // CSR arrays should be unchanged
     }
 
+    #[test]
+    fn custom_rebuild_policy_overrides_rebuild_limit() {
+        struct Never;
+        impl RebuildPolicy for Never {
+            fn should_rebuild(&self, _: &GraphCSR) -> bool {
+                false
+            }
+        }
+        struct Always;
+        impl RebuildPolicy for Always {
+            fn should_rebuild(&self, _: &GraphCSR) -> bool {
+                true
+            }
+        }
+
+        let mut never = GraphCSR::from_edges(2, &mut [(0, 1, 1.0)], 1).with_rebuild_policy(Never);
+        for _ in 0..5 {
+            assert!(matches!(
+                never.add_edges_and_extract_data(vec![(1, 0, 2.0)]),
+                AddEdgeResult::Success
+            ));
+        }
+        assert_eq!(never.pending_len(), 5);
+        assert_eq!(never.edge_targets.len(), 1);
+
+        let mut always =
+            GraphCSR::from_edges(2, &mut [(0, 1, 1.0)], 100).with_rebuild_policy(Always);
+        match always.add_edges_and_extract_data(vec![(1, 0, 2.0)]) {
+            AddEdgeResult::RebuildNeeded(edges) => assert_eq!(edges, vec![(1, 0, 2.0)]),
+            AddEdgeResult::Success => panic!("Always policy should request a rebuild"),
+        }
+        assert!(always.pending_updates().is_empty());
+    }
+
+    #[test]
+    fn adaptive_limit_rises_under_heavy_flow_and_falls_when_sparse() {
+        let policy = AdaptiveLimit::new(10, 1_000, Duration::from_millis(100));
+        assert_eq!(policy.effective_limit(), 10);
+
+        // 100 updates every millisecond: 100k/s wants 10k per 100ms, capped at 1k.
+        let start = Instant::now();
+        for ms in 0..20 {
+            policy.observe(100, start + Duration::from_millis(ms));
+        }
+        let busy = policy.effective_limit();
+        assert_eq!(busy, 1_000);
+
+        // One update per second: 0.1 per 100ms, floored at 10.
+        let quiet_start = start + Duration::from_millis(20);
+        for s in 1..=20 {
+            policy.observe(1, quiet_start + Duration::from_secs(s));
+        }
+        let quiet = policy.effective_limit();
+        assert!(quiet < busy);
+        assert_eq!(quiet, 10);
+
+        // Installed on a graph (limit pinned to 3), it rebuilds at its limit and counts
+        // afresh after every rebuild.
+        let mut csr = GraphCSR::from_edges(2, &mut [(0, 1, 1.0)], usize::MAX)
+            .with_rebuild_policy(AdaptiveLimit::new(3, 3, Duration::from_millis(100)));
+        for _ in 0..2 {
+            for _ in 0..2 {
+                assert!(matches!(
+                    csr.add_edges_and_extract_data(vec![(1, 0, 2.0)]),
+                    AddEdgeResult::Success
+                ));
+            }
+            assert!(matches!(
+                csr.add_edges_and_extract_data(vec![(1, 0, 2.0)]),
+                AddEdgeResult::RebuildNeeded(_)
+            ));
+        }
+    }
+
+    #[test]
+    fn edge_ratio_policy_scales_with_committed_edges() {
+        let mut edges: Vec<_> = (0..10).map(|i| (i, (i + 1) % 10, 1.0)).collect();
+        let mut csr = GraphCSR::from_edges(10, &mut edges, 1).with_rebuild_policy(EdgeRatio(0.5));
+
+        for i in 0..4 {
+            csr.add_edges_and_extract_data(vec![(i, (i + 2) % 10, 1.0)]);
+            assert!(!csr.needs_rebuild());
+        }
+        assert!(matches!(
+            csr.add_edges_and_extract_data(vec![(4, 6, 1.0)]),
+            AddEdgeResult::RebuildNeeded(_)
+        ));
+    }
+
+    #[test]
+    fn try_from_edges_rejects_out_of_range_source() {
+        let mut edges = vec![(0, 1, 1.0), (2, 0, 1.0)];
+        assert!(matches!(
+            GraphCSR::try_from_edges(2, &mut edges, 10),
+            Err(Error::InvalidGraph)
+        ));
+
+        let mut edges = vec![(0, 1, 1.0), (1, 0, 1.0)];
+        let csr = GraphCSR::try_from_edges(2, &mut edges, 10).unwrap();
+        assert_eq!(csr.node_pointers, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn try_from_edges_with_fee_rejects_fees_outside_the_bps_range() {
+        for fee_bps in [-1.0, 10_000.0, 20_000.0, f64::NAN] {
+            assert!(matches!(
+                GraphCSR::try_from_edges_with_fee(2, &mut [(0, 1, 1.0)], 10, fee_bps),
+                Err(Error::InvalidGraph)
+            ));
+        }
+
+        let csr = GraphCSR::try_from_edges_with_fee(2, &mut [(0, 1, 1.0)], 10, 9_999.0).unwrap();
+        assert!(csr.edge_weights[0].is_finite());
+    }
+
+    #[test]
+    fn packed_edges_follow_rebuilds_and_in_place_weight_changes() {
+        let mut csr = GraphCSR::from_edges(3, &mut [(0, 1, 1.1), (1, 2, 1.2)], 10);
+        let in_sync = |csr: &GraphCSR| {
+            let packed = csr.packed_edges();
+            assert_eq!(
+                matches!(packed, Cow::Borrowed(_)),
+                cfg!(feature = "packed-edges")
+            );
+            assert_eq!(*packed, *interleave(&csr.edge_targets, &csr.edge_weights));
+        };
+        in_sync(&csr);
+
+        csr.rebuild_with_edges(vec![(2, 0, 0.9)]).unwrap();
+        in_sync(&csr);
+
+        assert!(csr.try_update_weight(0, 1, 1.5));
+        csr.set_edge_fee_bps(1, 2, 30.0).unwrap();
+        csr.set_edge_weight(2, f64::INFINITY);
+        in_sync(&csr);
+
+        csr.clear();
+        in_sync(&csr);
+    }
+
+    #[test]
+    fn try_update_weight_rerates_committed_edges_only() {
+        let mut csr = GraphCSR::from_edges_with_fee(3, &mut [(0, 1, 1.0), (1, 2, 2.0)], 10, 30.0);
+        let expected = GraphCSR::from_edges_with_fee(3, &mut [(0, 1, 1.5), (1, 2, 2.0)], 10, 30.0);
+
+        assert!(csr.try_update_weight(0, 1, 1.5));
+        assert_eq!(csr.edge_weights, expected.edge_weights);
+
+        assert!(!csr.try_update_weight(2, 0, 1.5));
+        assert!(!csr.try_update_weight(0, 1, 0.0));
+        assert!(!csr.try_update_weight(7, 1, 1.5));
+        assert_eq!(csr.edge_weights, expected.edge_weights);
+    }
+
+    #[test]
+    fn unit_rate_edges_are_dropped_only_when_enabled() {
+        let mut edges = vec![(0, 1, 1.0), (1, 2, 1.1), (2, 0, 0.9)];
+        let kept = GraphCSR::from_edges(3, &mut edges.clone(), 10);
+        assert_eq!(kept.edge_targets.len(), 3);
+
+        let mut csr = GraphCSR::from_edges(3, &mut edges, 10).with_drop_unit_rates(true);
+        let pairs: Vec<_> = csr.to_edges().iter().map(|&(u, v, _)| (u, v)).collect();
+        assert_eq!(pairs, vec![(1, 2), (2, 0)]);
+        assert_eq!(csr.num_nodes, 3);
+
+        // A 1.0 quote on a committed pair removes it rather than re-rating it.
+        assert!(!csr.try_update_weight(1, 2, 1.0));
+        csr.rebuild_with_edges(vec![(1, 2, 1.0), (0, 1, 1.0), (0, 2, 1.2)])
+            .unwrap();
+        let pairs: Vec<_> = csr.to_edges().iter().map(|&(u, v, _)| (u, v)).collect();
+        assert_eq!(pairs, vec![(0, 2), (2, 0)]);
+    }
+
+    #[test]
+    fn clear_empties_the_graph_but_keeps_settings() {
+        let mut csr = GraphCSR::from_edges_with_fee(3, &mut [(0, 1, 1.1), (1, 2, 0.9)], 7, 30.0)
+            .with_dedup_policy(DedupPolicy::KeepMax);
+        csr.pending_updates.push((2, 0, 1.2));
+
+        csr.clear();
+        assert_eq!(csr.num_edges(), 0);
+        assert_eq!(csr.num_nodes, 0);
+        assert_eq!(csr.node_pointers, vec![0]);
+        assert!(csr.pending_updates().is_empty());
+        assert_eq!(csr.rebuild_limit, 7);
+        assert_eq!(csr.dedup_policy, DedupPolicy::KeepMax);
+
+        csr.rebuild_with_edges(vec![(0, 1, 1.3), (0, 1, 1.2), (1, 0, 0.8)])
+            .unwrap();
+        let expected = GraphCSR::from_edges_with_fee(2, &mut [(0, 1, 1.3), (1, 0, 0.8)], 7, 30.0);
+        assert_eq!(csr.num_nodes, 2);
+        assert_eq!(csr.node_pointers, expected.node_pointers);
+        assert_eq!(csr.edge_targets, expected.edge_targets);
+        assert_eq!(csr.edge_weights, expected.edge_weights);
+    }
+
+    #[test]
+    fn undirected_key_treats_reverse_quotes_as_one_market() {
+        let mut csr = GraphCSR::from_edges(2, &mut [], 10).with_dedup_key(DedupKey::Undirected);
+
+        csr.rebuild_with_edges(vec![(0, 1, 2.0), (1, 0, 0.5)])
+            .unwrap();
+        assert_eq!(csr.to_edges(), vec![(0, 1, 2.0), (1, 0, 0.5)]);
+        // One consistent market: the round trip is break-even, not a 2-edge "cycle".
+        assert!(csr.path_log_weight(&[0, 1, 0]).unwrap().abs() < 1e-12);
+
+        // A later reverse quote reprices both sides.
+        csr.rebuild_with_edges(vec![(1, 0, 0.4)]).unwrap();
+        let edges = csr.to_edges();
+        assert_eq!(edges.len(), 2);
+        assert!((edges[0].2 - 2.5).abs() < 1e-12);
+        assert!((edges[1].2 - 0.4).abs() < 1e-12);
+
+        assert!(csr.try_update_weight(0, 1, 4.0));
+        let edges = csr.to_edges();
+        assert!((edges[1].2 - 0.25).abs() < 1e-12);
+
+        // Directed (the default) keeps both quotes as independent pairs.
+        let mut directed = GraphCSR::from_edges(2, &mut [], 10);
+        directed
+            .rebuild_with_edges(vec![(0, 1, 2.0), (1, 0, 0.6)])
+            .unwrap();
+        assert_eq!(directed.to_edges(), vec![(0, 1, 2.0), (1, 0, 0.6)]);
+    }
+
+    #[test]
+    fn rate_above_clamp_is_stored_as_clamped_weight() {
+        let bounds = KernelParams::new(0.5, 2.0);
+        let mut csr = GraphCSR::from_edges(3, &mut [(0, 1, 1.0)], 10).with_rate_clamp(Some(bounds));
+
+        // A fat-finger 1e9 quote would otherwise make 0 -> 1 -> 2 -> 0 wildly profitable.
+        csr.rebuild_with_edges(vec![(1, 2, 1e9), (2, 0, 0.5)])
+            .unwrap();
+
+        let edges = csr.to_edges();
+        assert_eq!(edges[1], (1, 2, 2.0));
+        let idx = csr.node_pointers[1];
+        assert_eq!(csr.edge_weights[idx], -2.0f64.ln());
+        assert!(csr.path_log_weight(&[0, 1, 2, 0]).unwrap() >= 0.0);
+
+        assert!(csr.try_update_weight(1, 2, 1e9));
+        assert_eq!(csr.edge_weights[idx], -2.0f64.ln());
+    }
+
+    #[test]
+    fn rebuild_caps_out_degree_keeping_best_rates() {
+        let mut csr = GraphCSR::from_edges(2, &mut [(1, 0, 1.1)], 10).with_max_out_degree(Some(10));
+
+        // 50 quotes from node 0; the best rates go to the highest-numbered targets.
+        let fan_out: Vec<Edge> = (1..=50)
+            .map(|dst| (0, dst, 1.0 + dst as f64 / 100.0))
+            .collect();
+        csr.rebuild_with_edges(fan_out).unwrap();
+
+        let from_zero: Vec<usize> = csr
+            .to_edges()
+            .iter()
+            .filter(|e| e.0 == 0)
+            .map(|e| e.1)
+            .collect();
+        assert_eq!(from_zero, (41..=50).collect::<Vec<_>>());
+        assert_eq!(csr.num_nodes, 51);
+        assert_eq!(csr.edge_targets.len(), 11);
+
+        // A pinned pair survives the cap even with the worst rate.
+        csr.pin_edge(0, 3);
+        csr.rebuild_with_edges(vec![(0, 3, 1.001)]).unwrap();
+        let from_zero: Vec<usize> = csr
+            .to_edges()
+            .iter()
+            .filter(|e| e.0 == 0)
+            .map(|e| e.1)
+            .collect();
+        assert_eq!(
+            from_zero,
+            [3].into_iter().chain(42..=50).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn pinned_edge_survives_staleness_eviction() {
+        let mut edges = vec![(0, 1, 1.0), (1, 2, 1.1), (2, 0, 0.95), (2, 1, 0.9)];
+        let mut csr = GraphCSR::from_edges(3, &mut edges, 10);
+        csr.pin_edge(1, 2);
+
+        // Every edge except 2 -> 1 was last quoted at tick 5; the cutoff is tick 10.
+        let last_quote: HashMap<(usize, usize), u64> =
+            HashMap::from([((0, 1), 5), ((1, 2), 5), ((2, 0), 5), ((2, 1), 20)]);
+        let mut evicted = csr.evict_edges(|u, v| last_quote[&(u, v)] < 10);
+        evicted.sort_by_key(|&(u, v, _)| (u, v));
+
+        assert_eq!(
+            evicted.iter().map(|&(u, v, _)| (u, v)).collect::<Vec<_>>(),
+            vec![(0, 1), (2, 0)]
+        );
+        let kept: Vec<_> = csr.to_edges().iter().map(|&(u, v, _)| (u, v)).collect();
+        assert_eq!(kept, vec![(1, 2), (2, 1)]);
+        assert_eq!(csr.num_nodes, 3);
+
+        assert!(csr.unpin_edge(1, 2));
+        assert_eq!(csr.evict_edges(|u, v| last_quote[&(u, v)] < 10).len(), 1);
+    }
+
+    #[test]
+    fn random_graph_is_deterministic_per_seed() {
+        let graph = GraphCSR::random(50, 400, 0.9..1.1, 7);
+        let same = GraphCSR::random(50, 400, 0.9..1.1, 7);
+        let other = GraphCSR::random(50, 400, 0.9..1.1, 8);
+
+        assert_eq!(graph.num_nodes, 50);
+        assert_eq!(graph.node_pointers.len(), 51);
+        assert_eq!(graph.edge_targets.len(), 400);
+        assert_eq!(graph.edge_targets, same.edge_targets);
+        assert_eq!(graph.edge_weights, same.edge_weights);
+        assert_ne!(graph.edge_weights, other.edge_weights);
+
+        for (u, v, rate) in graph.to_edges() {
+            assert_ne!(u, v);
+            assert!((0.9..1.1).contains(&rate));
+        }
+    }
+
     #[test]
     fn add_edges_triggers_rebuild_when_limit_exceeded() {
         let mut edges = vec![(0, 1, 1.0)];
         let mut csr = GraphCSR::from_edges(2, &mut edges, 1);
 
-        csr.add_edges(vec![(1, 0, 2.0)]);
+        csr.add_edges(vec![(1, 0, 2.0)]).unwrap();
+
+        assert!(csr.pending_updates().is_empty()); // Buffer cleared after internal rebuild
+        assert_eq!(csr.edge_targets.len(), 2);
+    }
+
+    #[test]
+    fn grow_nodes_adds_isolated_nodes_and_survives_rebuild() {
+        let mut csr = GraphCSR::from_edges(2, &mut [(0, 1, 1.0)], 2);
+
+        csr.grow_nodes(5);
+        assert_eq!(csr.num_nodes, 5);
+        assert_eq!(csr.node_pointers, vec![0, 1, 1, 1, 1, 1]);
+
+        csr.grow_nodes(3);
+        assert_eq!(csr.num_nodes, 5);
 
-        assert!(csr.pending_updates.is_empty()); // Buffer cleared after internal rebuild
+        csr.rebuild_with_edges(vec![(1, 0, 2.0)]).unwrap();
+        assert_eq!(csr.num_nodes, 5);
         assert_eq!(csr.edge_targets.len(), 2);
     }
 
+    #[test]
+    fn rebuild_with_edges_rejects_max_node_id() {
+        let mut csr = GraphCSR::from_edges(2, &mut [(0, 1, 1.0)], 2);
+
+        let result = csr.rebuild_with_edges(vec![(0, usize::MAX, 1.5)]);
+
+        assert!(matches!(result, Err(Error::InvalidGraph)));
+        // The existing graph must survive the failed rebuild.
+        assert_eq!(csr.num_nodes, 2);
+        assert_eq!(csr.edge_targets, vec![1]);
+    }
+
+    #[test]
+    fn rebuild_with_edges_rejects_non_finite_weights() {
+        let mut csr = GraphCSR::from_edges(2, &mut [(0, 1, 1.0)], 2);
+
+        for bad_rate in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let result = csr.rebuild_with_edges(vec![(1, 0, 1.1), (1, 2, bad_rate)]);
+            assert!(matches!(result, Err(Error::NonFiniteWeight(1))));
+        }
+
+        assert_eq!(csr.num_nodes, 2);
+        assert_eq!(csr.edge_targets, vec![1]);
+    }
+
     #[test]
     fn rebuild_with_edges_does_not_touch_pending_buffer() {
         let mut csr = GraphCSR::from_edges(2, &mut [(0, 1, 1.0)], 2);
 
         csr.pending_updates = vec![(1, 0, 0.5)];
-        let pending_len_before = csr.pending_updates.len();
+        let pending_len_before = csr.pending_len();
 
         let rebuild_data = vec![(0, 1, 2.0)];
 
-        csr.rebuild_with_edges(rebuild_data);
+        csr.rebuild_with_edges(rebuild_data).unwrap();
 
         assert_eq!(csr.edge_weights.len(), 1);
         assert_eq!(csr.edge_weights[0], -2.0f64.ln());
 
-        assert_eq!(csr.pending_updates.len(), pending_len_before);
-        assert_eq!(csr.pending_updates, vec![(1, 0, 0.5)]);
+        assert_eq!(csr.pending_len(), pending_len_before);
+        assert_eq!(csr.pending_updates(), [(1, 0, 0.5)]);
+    }
+
+    #[test]
+    fn best_triangle_finds_most_profitable_triangle() {
+        let mut edges = vec![
+            // Profitable triangle: 1.1 * 1.0 * 1.0 = 1.1
+            (0, 1, 1.1),
+            (1, 2, 1.0),
+            (2, 0, 1.0),
+            // Weaker profitable triangle: 1.02
+            (3, 4, 1.02),
+            (4, 5, 1.0),
+            (5, 3, 1.0),
+            // Decoys: a profitable 2-cycle, a losing triangle and dangling edges
+            (1, 0, 1.5),
+            (2, 4, 0.5),
+            (4, 2, 0.5),
+            (2, 3, 0.9),
+            (3, 1, 0.9),
+            (5, 6, 3.0),
+        ];
+        let csr = GraphCSR::from_edges(7, &mut edges, 10);
+
+        let cycle = csr.best_triangle().expect("Expected a profitable triangle");
+
+        assert_eq!(cycle.path, vec![(0, 1, 1.1), (1, 2, 1.0), (2, 0, 1.0)]);
+        assert_eq!(cycle.rates, vec![1.1, 1.0, 1.0]);
+        assert!((cycle.product_rate() - 1.1).abs() < 1e-12);
+    }
+
+    #[test]
+    fn path_log_weight_sums_edges_and_rejects_missing_edges() {
+        let mut edges = vec![(0, 1, 0.5), (1, 2, 2.0), (2, 0, 1.5), (2, 3, 1.0)];
+        let csr = GraphCSR::from_edges(4, &mut edges, 10);
+
+        let weight = csr.path_log_weight(&[0, 1, 2, 0]).unwrap();
+        assert!((weight + (0.5f64 * 2.0 * 1.5).ln()).abs() < 1e-12);
+        assert_eq!(csr.path_log_weight(&[3]).unwrap(), 0.0);
+
+        assert!(matches!(
+            csr.path_log_weight(&[0, 1, 3]),
+            Err(Error::InvalidGraph)
+        ));
+        assert!(matches!(
+            csr.path_log_weight(&[7, 0]),
+            Err(Error::InvalidGraph)
+        ));
+    }
+
+    #[test]
+    fn best_triangle_returns_none_without_profit() {
+        let mut edges = vec![(0, 1, 1.0), (1, 2, 0.99), (2, 0, 1.0), (0, 2, 1.5)];
+        let csr = GraphCSR::from_edges(3, &mut edges, 10);
+
+        assert!(csr.best_triangle().is_none());
+    }
+
+    #[test]
+    fn fee_is_applied_to_weights_and_not_compounded_on_rebuild() {
+        let mut csr = GraphCSR::from_edges_with_fee(2, &mut [(0, 1, 2.0)], 2, 30.0);
+        let expected = -(2.0f64 * 0.997).ln();
+        assert!((csr.edge_weights[0] - expected).abs() < 1e-12);
+
+        csr.rebuild_with_edges(vec![(1, 0, 0.5)]).unwrap();
+        csr.rebuild_with_edges(vec![]).unwrap();
+
+        assert!((csr.edge_weights[0] - expected).abs() < 1e-12);
+        assert!((csr.edge_weights[1] + (0.5f64 * 0.997).ln()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn linear_transform_stores_raw_weights_and_finds_negative_sum_cycle() {
+        use crate::solver::SPFASolver;
+        use crate::traits::GraphSolver;
+
+        // Spreads: 0 -> 1 -> 2 -> 0 sums to -0.5; 2 -> 3 is a dangling cost.
+        let mut edges = vec![(0, 1, 1.0), (1, 2, -2.0), (2, 0, 0.5), (2, 3, 4.0)];
+        let mut csr = GraphCSR::from_edges_with_transform(4, &mut edges, 10, Transform::Linear);
+        assert_eq!(csr.edge_weights, vec![1.0, -2.0, 0.5, 4.0]);
+
+        // Negative values are valid spreads and survive a rebuild unchanged.
+        csr.rebuild_with_edges(vec![(3, 2, -1.0)]).unwrap();
+        assert_eq!(csr.to_edges()[0], (0, 1, 1.0));
+
+        let cycle = SPFASolver
+            .find_profitable_cycle(&csr, 0, csr.num_nodes + 1)
+            .unwrap()
+            .expect("Negative-sum cycle expected");
+
+        let total: f64 = cycle.rates.iter().sum();
+        assert!(total < 0.0);
+        assert!((cycle.log_rate_sum - total).abs() < 1e-12);
+        assert!(cycle.is_profitable());
+    }
+
+    #[test]
+    fn edge_fee_outside_the_bps_range_is_rejected_without_changes() {
+        let mut csr = GraphCSR::from_edges(2, &mut [(0, 1, 2.0), (1, 0, 0.6)], 10);
+        let weights = csr.edge_weights.clone();
+
+        for fee_bps in [-5.0, 10_000.0, f64::INFINITY] {
+            assert!(matches!(
+                csr.set_edge_fee_bps(0, 1, fee_bps),
+                Err(Error::InvalidGraph)
+            ));
+        }
+        assert_eq!(csr.edge_weights, weights);
+        assert!(csr.edge_fee_multipliers.is_empty());
+
+        // Validity follows the pair's own fee: a 50% fee halves the smallest
+        // subnormal rate to zero on that pair only.
+        csr.set_edge_fee_bps(0, 1, 5_000.0).unwrap();
+        assert!(!csr.is_valid_rate(0, 1, 5e-324));
+        assert!(csr.is_valid_rate(1, 0, 5e-324));
+    }
+
+    #[test]
+    fn fees_apply_to_rates_but_not_to_linear_spreads() {
+        let mut edges = vec![(0, 1, 2.0), (1, 0, -1.0)];
+        let mut spreads = GraphCSR::from_edges_with_transform(2, &mut edges, 10, Transform::Linear);
+        spreads.fee_multiplier = 0.997;
+        spreads.set_edge_fee_bps(0, 1, 100.0).unwrap();
+        assert_eq!(spreads.edge_weights, vec![2.0, -1.0]);
+
+        spreads.rebuild_with_edges(vec![(1, 0, -0.5)]).unwrap();
+        assert_eq!(spreads.edge_weights, vec![2.0, -0.5]);
+        assert_eq!(spreads.gross_rate(0, 1), Some(2.0));
+
+        // The same fee does charge multiplicative rates.
+        let mut rates = GraphCSR::from_edges(2, &mut [(0, 1, 2.0)], 10);
+        rates.set_edge_fee_bps(0, 1, 100.0).unwrap();
+        assert!((rates.edge_value(0) - 2.0 * 0.99).abs() < 1e-12);
+        assert!((rates.gross_rate(0, 1).unwrap() - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn induced_subgraph_relabels_nodes_and_keeps_internal_edges() {
+        let mut edges = vec![(0, 1, 1.5), (1, 3, 2.0), (3, 1, 0.5), (3, 2, 4.0)];
+        let csr = GraphCSR::from_edges_with_fee(4, &mut edges, 10, 30.0);
+
+        let sub = csr.induced_subgraph(&[1, 3]);
+
+        assert_eq!(sub.num_nodes, 2);
+        assert_eq!(sub.node_pointers, vec![0, 1, 2]);
+        assert_eq!(sub.edge_targets, vec![1, 0]);
+        assert_eq!(sub.edge_source_by_index, vec![0, 1]);
+        assert_eq!(
+            sub.edge_weights,
+            vec![csr.edge_weights[1], csr.edge_weights[2]]
+        );
+        assert_eq!(sub.fee_multiplier, csr.fee_multiplier);
+    }
+
+    #[test]
+    fn masked_subgraph_drops_skipped_edges_by_original_ids() {
+        let mut edges = vec![(0, 1, 1.5), (1, 3, 2.0), (3, 1, 0.5), (3, 2, 4.0)];
+        let csr = GraphCSR::from_edges(4, &mut edges, 10);
+
+        let sub = csr.induced_subgraph_masked(&[1, 3], |u, v| (u, v) == (3, 1));
+
+        assert_eq!(sub.num_nodes, 2);
+        assert_eq!(sub.node_pointers, vec![0, 1, 1]);
+        assert_eq!(sub.edge_targets, vec![1]);
+        assert_eq!(sub.edge_weights, vec![csr.edge_weights[1]]);
+        // The full graph is left as it was.
+        assert_eq!(csr.edge_targets.len(), 4);
+    }
+
+    #[test]
+    fn weighted_average_policy_stores_mean_of_quotes() {
+        let mut csr = GraphCSR::from_edges(2, &mut [(0, 1, 0.5), (1, 0, 2.0)], 10)
+            .with_dedup_policy(DedupPolicy::WeightedAverage);
+
+        // Three venues quote 0 -> 1; the committed 0.5 is superseded, not averaged in.
+        csr.rebuild_with_edges(vec![(0, 1, 1.0), (0, 1, 1.1), (0, 1, 1.3)])
+            .unwrap();
+
+        let expected = (1.0 + 1.1 + 1.3) / 3.0;
+        assert_eq!(csr.edge_targets, vec![1, 0]);
+        assert!((csr.edge_weights[0] + f64::ln(expected)).abs() < 1e-12);
+        assert!((csr.edge_weights[1] + 2.0f64.ln()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn diff_reports_single_added_edge() {
+        let mut edges = vec![(0, 1, 1.1), (1, 2, 0.9), (2, 0, 1.05)];
+        let csr = GraphCSR::from_edges(3, &mut edges, 10);
+
+        let mut grown = csr.clone();
+        grown.rebuild_with_edges(vec![(2, 1, 1.3)]).unwrap();
+
+        assert!(csr.diff(&csr).is_empty());
+        let diff = csr.diff(&grown);
+        assert_eq!(diff.added, vec![(2, 1, 1.3)]);
+        assert!(diff.removed.is_empty());
+        assert!(diff.changed.is_empty());
+    }
+
+    #[test]
+    fn diff_reports_removed_and_changed_edges() {
+        let old = GraphCSR::from_edges(3, &mut [(0, 1, 1.1), (1, 2, 0.9)], 10);
+        let new = GraphCSR::from_edges(3, &mut [(0, 1, 1.2)], 10);
+
+        let diff = old.diff(&new);
+        assert!(diff.added.is_empty());
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!((diff.removed[0].0, diff.removed[0].1), (1, 2));
+        assert_eq!(diff.changed.len(), 1);
+        let (u, v, before, after) = diff.changed[0];
+        assert_eq!((u, v), (0, 1));
+        assert!((before - 1.1).abs() < 1e-12 && (after - 1.2).abs() < 1e-12);
+    }
+
+    #[test]
+    fn interleaved_batches_keep_genuinely_latest_update() {
+        let mut csr = GraphCSR::from_edges(3, &mut [(0, 1, 1.0)], 10);
+
+        // Three batches, each touching 0 -> 1, interleaved with other pairs.
+        for batch in [
+            vec![(0, 1, 1.1), (1, 2, 0.9)],
+            vec![(2, 0, 1.3), (0, 1, 1.2)],
+            vec![(0, 1, 1.05), (1, 2, 0.8)],
+        ] {
+            assert!(matches!(
+                csr.add_edges_and_extract_data(batch),
+                AddEdgeResult::Success
+            ));
+        }
+        let pending = std::mem::take(&mut csr.pending_updates);
+        csr.rebuild_with_edges(pending).unwrap();
+
+        let edges = csr.to_edges();
+        let rate = |u, v| edges.iter().find(|e| (e.0, e.1) == (u, v)).unwrap().2;
+        assert!((rate(0, 1) - 1.05).abs() < 1e-12);
+        assert!((rate(1, 2) - 0.8).abs() < 1e-12);
+        assert!((rate(2, 0) - 1.3).abs() < 1e-12);
+    }
+
+    #[test]
+    fn keep_earliest_policy_ignores_later_quotes() {
+        let mut csr = GraphCSR::from_edges(2, &mut [(0, 1, 1.2)], 10)
+            .with_dedup_policy(DedupPolicy::KeepEarliest);
+
+        csr.rebuild_with_edges(vec![(1, 0, 0.9), (0, 1, 1.5), (1, 0, 0.7)])
+            .unwrap();
+
+        let edges = csr.to_edges();
+        assert_eq!(edges.len(), 2);
+        assert!(
+            edges
+                .iter()
+                .any(|e| (e.0, e.1) == (0, 1) && (e.2 - 1.2).abs() < 1e-12)
+        );
+        assert!(
+            edges
+                .iter()
+                .any(|e| (e.0, e.1) == (1, 0) && (e.2 - 0.9).abs() < 1e-12)
+        );
+    }
+
+    #[test]
+    fn keep_max_policy_keeps_best_rate() {
+        let mut csr =
+            GraphCSR::from_edges(2, &mut [(0, 1, 1.2)], 10).with_dedup_policy(DedupPolicy::KeepMax);
+
+        csr.rebuild_with_edges(vec![(0, 1, 1.1), (0, 1, 0.9)])
+            .unwrap();
+        assert!((csr.edge_weights[0] + 1.2f64.ln()).abs() < 1e-12);
+
+        csr.rebuild_with_edges(vec![(0, 1, 1.5)]).unwrap();
+        assert!((csr.edge_weights[0] + 1.5f64.ln()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn on_rebuild_hook_fires_once_per_successful_rebuild() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let last_edge_count = Arc::new(AtomicUsize::new(0));
+
+        let mut csr = GraphCSR::from_edges(2, &mut [(0, 1, 1.0)], 2);
+        let (c, n) = (calls.clone(), last_edge_count.clone());
+        csr.set_on_rebuild(move |graph| {
+            c.fetch_add(1, Ordering::SeqCst);
+            n.store(graph.edge_targets.len(), Ordering::SeqCst);
+        });
+
+        csr.rebuild_with_edges(vec![(1, 0, 2.0)]).unwrap();
+        csr.rebuild_with_edges(vec![(1, 2, 1.5)]).unwrap();
+        assert!(csr.rebuild_with_edges(vec![(0, 2, f64::NAN)]).is_err());
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(last_edge_count.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn custom_identity_transform_stores_rates_and_inverts() {
+        struct Identity;
+
+        impl WeightTransform for Identity {
+            fn forward(&self, rate: f64) -> f64 {
+                rate
+            }
+
+            fn inverse(&self, weight: f64) -> f64 {
+                weight
+            }
+        }
+
+        let mut edges = vec![(0, 1, 0.25), (1, 0, -0.5), (1, 2, 3.0)];
+        let mut csr =
+            GraphCSR::from_edges_with_transform(3, &mut edges, 10, Transform::custom(Identity));
+        assert_eq!(csr.edge_weights, vec![0.25, -0.5, 3.0]);
+
+        csr.rebuild_with_edges(vec![(2, 0, 1.5)]).unwrap();
+        let mut rebuilt = csr.to_edges();
+        rebuilt.sort_by_key(|&(src, dst, _)| (src, dst));
+        assert_eq!(
+            rebuilt,
+            vec![(0, 1, 0.25), (1, 0, -0.5), (1, 2, 3.0), (2, 0, 1.5)]
+        );
+        assert_eq!(csr.edge_value(0), 0.25);
+    }
+
+    #[test]
+    fn pending_len_counts_buffered_but_unflushed_edges() {
+        let mut csr = GraphCSR::from_edges(2, &mut [(0, 1, 1.0)], 4);
+        assert_eq!(csr.pending_len(), 0);
+
+        csr.add_edges_and_extract_data(vec![(1, 0, 1.1), (1, 2, 0.9)]);
+        csr.add_edges_and_extract_data(vec![(2, 0, 1.2)]);
+        assert_eq!(csr.pending_len(), 3);
+        assert_eq!(
+            csr.pending_updates(),
+            [(1, 0, 1.1), (1, 2, 0.9), (2, 0, 1.2)]
+        );
+        assert_eq!(csr.num_edges(), 1);
+
+        // The fourth update reaches the limit and hands the whole buffer out.
+        let AddEdgeResult::RebuildNeeded(edges) = csr.add_edges_and_extract_data(vec![(2, 1, 1.0)])
+        else {
+            panic!("Expected RebuildNeeded result");
+        };
+        assert_eq!(edges.len(), 4);
+        assert_eq!(csr.pending_len(), 0);
+
+        // Below the limit, the buffer can still be taken out explicitly.
+        csr.add_edges_and_extract_data(vec![(0, 2, 1.0)]);
+        assert_eq!(csr.take_pending(), vec![(0, 2, 1.0)]);
+        assert_eq!(csr.pending_len(), 0);
+        assert_eq!(csr.num_edges(), 1);
+    }
+
+    #[test]
+    fn pending_buffer_never_exceeds_the_ceiling() {
+        let mut csr =
+            GraphCSR::from_edges(2, &mut [(0, 1, 1.0)], usize::MAX).with_max_pending(Some(8));
+
+        let mut rebuilds = 0;
+        for i in 0..1_000 {
+            let batch = vec![(0, 1, 1.0 + i as f64 / 1e4), (1, 0, 1.0)];
+            if let AddEdgeResult::RebuildNeeded(edges) = csr.add_edges_and_extract_data(batch) {
+                rebuilds += 1;
+                csr.rebuild_with_edges(edges).unwrap();
+            }
+            assert!(csr.pending_len() <= 8);
+        }
+
+        // 2_000 updates, forced out every 8 despite the unreachable rebuild limit.
+        assert_eq!(rebuilds, 250);
+        assert!(csr.pending_updates().is_empty());
     }
 
     #[test]
@@ -409,14 +2409,14 @@ mod tests {
 
         let result = csr.add_edges_and_extract_data(updates);
 
-        assert!(csr.pending_updates.is_empty());
+        assert!(csr.pending_updates().is_empty());
 
         let extracted_edges = match result {
             AddEdgeResult::RebuildNeeded(edges) => edges,
             _ => panic!("Expected RebuildNeeded result"),
         };
 
-        csr.rebuild_with_edges(extracted_edges);
+        csr.rebuild_with_edges(extracted_edges).unwrap();
 
         assert_eq!(csr.edge_targets.len(), 2);
     }
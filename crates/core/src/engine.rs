@@ -0,0 +1,136 @@
+use super::csr::GraphCSR;
+use super::solver::SPFASolver;
+use super::traits::GraphSolver;
+use common::{
+    error::Error,
+    types::{Edge, NodeId, Rate, WeightedCycle, edge},
+};
+
+/// Synchronous facade over a [`GraphCSR`] and a solver, for embedders that push
+/// updates and query cycles on demand without an async runtime.
+///
+/// Unlike the executor's writer, updates are not buffered: every
+/// [`ArbEngine::apply_updates`] call rebuilds the graph, so the next search sees them.
+#[derive(Debug)]
+pub struct ArbEngine<S = SPFASolver> {
+    graph: GraphCSR,
+    solver: S,
+}
+
+impl ArbEngine {
+    /// Wraps `graph` with the default [`SPFASolver`].
+    pub fn new(graph: GraphCSR) -> Self {
+        Self::with_solver(graph, SPFASolver)
+    }
+}
+
+impl Default for ArbEngine {
+    fn default() -> Self {
+        Self::new(GraphCSR::from_edges(0, &mut [], 0))
+    }
+}
+
+impl<S: GraphSolver> ArbEngine<S> {
+    /// Wraps `graph` with a custom solver.
+    pub fn with_solver(graph: GraphCSR, solver: S) -> Self {
+        Self { graph, solver }
+    }
+
+    /// Applies `edges` as `(src, dst, rate)` quotes, growing the graph for new node ids.
+    ///
+    /// Duplicates of existing pairs are resolved by the graph's dedup policy.
+    ///
+    /// # Errors
+    /// Same as [`GraphCSR::rebuild_with_edges`]; on error the graph is unchanged.
+    pub fn apply_updates(&mut self, edges: &[Edge]) -> Result<(), Error> {
+        if edges.is_empty() {
+            return Ok(());
+        }
+        self.graph.rebuild_with_edges(edges.to_vec())
+    }
+
+    /// Applies a single `from -> to` quote; the typed arguments cannot be swapped.
+    ///
+    /// # Errors
+    /// Same as [`ArbEngine::apply_updates`].
+    pub fn update_rate(&mut self, from: NodeId, to: NodeId, rate: Rate) -> Result<(), Error> {
+        self.apply_updates(&[edge(from, to, rate)])
+    }
+
+    /// Searches the whole graph for a profitable cycle.
+    ///
+    /// An empty graph has no cycle and returns `Ok(None)`.
+    pub fn search(&self) -> Result<Option<WeightedCycle>, Error> {
+        if self.graph.num_nodes == 0 {
+            return Ok(None);
+        }
+        self.solver
+            .find_profitable_cycle(&self.graph, 0, self.graph.num_nodes + 1)
+    }
+
+    /// The current graph.
+    pub fn graph(&self) -> &GraphCSR {
+        &self.graph
+    }
+
+    /// Unwraps the facade, returning the graph.
+    pub fn into_graph(self) -> GraphCSR {
+        self.graph
+    }
+}
+
+/// Finds a profitable cycle in a raw `(src, dst, rate)` edge list in one call.
+///
+/// The node count is inferred from the largest id, and the graph is searched with the
+/// default [`SPFASolver`] and settings. Invalid input (e.g. a rate that maps to a
+/// non-finite weight) yields `None`; use [`ArbEngine`] to see the error instead.
+pub fn detect_arbitrage(edges: &[Edge]) -> Option<WeightedCycle> {
+    let mut engine = ArbEngine::default();
+    engine.apply_updates(edges).ok()?;
+    engine.search().ok().flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_arbitrage_from_a_raw_edge_list() {
+        let cycle = detect_arbitrage(&[(0, 1, 1.1), (1, 2, 0.95), (2, 0, 0.98)])
+            .expect("Expected the 0 -> 1 -> 2 -> 0 loop");
+        assert_eq!(cycle.canonicalize().nodes(), vec![0, 1, 2]);
+        assert!(cycle.is_profitable());
+
+        assert!(detect_arbitrage(&[(0, 1, 1.1), (1, 2, 0.95), (2, 0, 0.9)]).is_none());
+        assert!(detect_arbitrage(&[]).is_none());
+    }
+
+    #[test]
+    fn applies_updates_and_finds_cycle_without_a_runtime() {
+        let mut engine = ArbEngine::default();
+        assert!(engine.search().unwrap().is_none());
+
+        engine
+            .apply_updates(&[(0, 1, 1.1), (1, 2, 0.95), (2, 3, 1.0)])
+            .unwrap();
+        assert_eq!(engine.graph().num_nodes, 4);
+        assert!(engine.search().unwrap().is_none());
+
+        engine.apply_updates(&[(2, 0, 0.98)]).unwrap();
+        let cycle = engine
+            .search()
+            .unwrap()
+            .expect("Expected the 0 -> 1 -> 2 -> 0 loop");
+        assert_eq!(cycle.canonicalize().nodes(), vec![0, 1, 2]);
+        assert!(cycle.is_profitable());
+
+        // Re-quoting a hop through the typed API closes the loop at a loss.
+        engine.update_rate(NodeId(2), NodeId(0), Rate(0.9)).unwrap();
+        assert!(engine.search().unwrap().is_none());
+        assert_eq!(engine.graph().num_edges(), 4);
+
+        // A rejected batch leaves the graph as it was.
+        assert!(engine.apply_updates(&[(3, 0, f64::NAN)]).is_err());
+        assert_eq!(engine.graph().num_edges(), 4);
+    }
+}
@@ -0,0 +1,143 @@
+//! Fixed-point negative-cycle search.
+//!
+//! `-ln`/`exp` and f64 accumulation can differ in the last bits across platforms and
+//! compilers, so two machines may disagree on whether a near-break-even cycle is
+//! profitable. Here every stored weight is quantized once to an `i64` number of
+//! `1 / scale` log-units, and SPFA then runs on exact integer sums and comparisons.
+//! Given the same quantized weights, every platform reaches the same verdict.
+
+use std::collections::VecDeque;
+
+use common::error::Error;
+use common::types::WeightedCycle;
+
+use super::csr::GraphCSR;
+use super::solver::SPFASolver;
+use super::traits::GraphSolver;
+
+/// Default quantization: one unit is `1e-9` in log space (~1e-7 % of a rate).
+pub const DEFAULT_SCALE: f64 = 1e9;
+
+/// Quantizes `weights` to `round(weight * scale)`.
+///
+/// # Errors
+/// Returns `Error::NonFiniteWeight(i)` if `weights[i]` is not finite or does not fit
+/// in an `i64` after scaling.
+pub fn quantize_weights(weights: &[f64], scale: f64) -> Result<Vec<i64>, Error> {
+    weights
+        .iter()
+        .enumerate()
+        .map(|(i, &weight)| {
+            let scaled = (weight * scale).round();
+            if scaled.is_finite() && scaled.abs() < i64::MAX as f64 {
+                Ok(scaled as i64)
+            } else {
+                Err(Error::NonFiniteWeight(i))
+            }
+        })
+        .collect()
+}
+
+/// SPFA over fixed-point (`i64`) weights with exact integer comparisons.
+///
+/// Seeds every node at distance 0, like [`SPFASolver`]. The reported cycle is
+/// reconstructed from the graph as usual, so its rates and `log_rate_sum` are f64.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedPointSPFASolver {
+    pub scale: f64,
+}
+
+impl Default for FixedPointSPFASolver {
+    fn default() -> Self {
+        Self {
+            scale: DEFAULT_SCALE,
+        }
+    }
+}
+
+impl GraphSolver for FixedPointSPFASolver {
+    fn find_profitable_cycle(
+        &self,
+        graph: &GraphCSR,
+        source: usize,
+        hop_cap: usize,
+    ) -> Result<Option<WeightedCycle>, Error> {
+        let num_nodes = graph.num_nodes;
+        if num_nodes == 0 {
+            return Err(Error::EmptyGraph);
+        }
+        if source >= num_nodes {
+            return Err(Error::NodeIndexOutOfBounds(source));
+        }
+
+        let weights = quantize_weights(&graph.edge_weights, self.scale)?;
+
+        let mut distance = vec![0i64; num_nodes];
+        let mut count = vec![0; num_nodes];
+        let mut in_queue = vec![true; num_nodes];
+        let mut pred_edge_idx = vec![None; num_nodes];
+        let mut queue: VecDeque<usize> = (0..num_nodes).collect();
+
+        while let Some(u) = queue.pop_front() {
+            in_queue[u] = false;
+
+            let (start, end) = (graph.node_pointers[u], graph.node_pointers[u + 1]);
+            for (i, &weight) in (start..end).zip(&weights[start..end]) {
+                let v = graph.edge_targets[i];
+                let candidate = distance[u].saturating_add(weight);
+                if candidate < distance[v] {
+                    distance[v] = candidate;
+                    pred_edge_idx[v] = Some(i);
+
+                    count[v] += 1;
+                    if count[v] >= hop_cap {
+                        match SPFASolver.confirm_cycle(v, &pred_edge_idx, graph)? {
+                            Some(cycle) => return Ok(Some(cycle)),
+                            None => count[v] = 0,
+                        }
+                    }
+
+                    if !in_queue[v] {
+                        queue.push_back(v);
+                        in_queue[v] = true;
+                    }
+                }
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantize_rounds_and_rejects_out_of_range() {
+        assert_eq!(
+            quantize_weights(&[0.25, -1.0000000004, 0.0], DEFAULT_SCALE).unwrap(),
+            vec![250_000_000, -1_000_000_000, 0]
+        );
+        assert!(matches!(
+            quantize_weights(&[0.0, f64::INFINITY], DEFAULT_SCALE),
+            Err(Error::NonFiniteWeight(1))
+        ));
+        assert!(matches!(
+            quantize_weights(&[1e12], DEFAULT_SCALE),
+            Err(Error::NonFiniteWeight(0))
+        ));
+    }
+
+    #[test]
+    fn fixed_point_finds_cycle_in_disconnected_graph() {
+        let mut edges = vec![(0, 1, 0.9), (1, 0, 1.0), (2, 3, 1.1), (3, 2, 0.95)];
+        let graph = GraphCSR::from_edges(4, &mut edges, 10);
+
+        let cycle = FixedPointSPFASolver::default()
+            .find_profitable_cycle(&graph, 0, 5)
+            .unwrap()
+            .expect("Cycle expected");
+        assert_eq!(cycle.canonical_key(), vec![2, 3]);
+    }
+}
@@ -1,5 +1,12 @@
 pub mod csr;
+pub mod engine;
+pub mod fixed_point;
+pub mod scc;
 pub mod solver;
+#[cfg(test)]
+pub(crate) mod test_fixtures;
 pub mod traits;
+pub mod transform;
 
 pub use csr::GraphCSR;
+pub use engine::{ArbEngine, detect_arbitrage};
@@ -0,0 +1,383 @@
+use std::collections::{HashMap, HashSet};
+
+use super::csr::GraphCSR;
+
+/// Strongly connected component (SCC) partition of a graph with incremental maintenance.
+///
+/// A profitable cycle can only exist inside a single SCC, so the partition can be used
+/// to prune nodes that are not part of any cycle before running the solver.
+///
+/// Alongside the `node -> component` mapping, the index keeps the **condensation DAG**
+/// (edges between components, with multiplicities) so that edge updates can be applied
+/// without re-running Tarjan over the whole graph:
+///
+/// - **Edge addition** `u -> v` across components only changes the partition if `u`'s
+///   component was already reachable from `v`'s. In that case every component lying on a
+///   path `comp(v) ~> comp(u)` collapses into one. Cost: a forward and a backward search
+///   over the condensation DAG, **O(C + D)** (components + DAG edges) in the worst case,
+///   usually far smaller than Tarjan's **O(V + E)** over the full graph.
+/// - **Edge removal** across components only decrements a DAG edge count (O(1)). Removing
+///   an edge *inside* a component may split it, which cannot be decided locally, so the
+///   index falls back to a full Tarjan recomputation.
+///
+/// Component ids are stable across additions but are not dense: ids absorbed by a merge
+/// are left empty. Use [`SccIndex::canonical_labels`] to compare partitions.
+#[derive(Debug, Clone, Default)]
+pub struct SccIndex {
+    component_of: Vec<usize>,
+    members: Vec<Vec<usize>>,
+    dag_out: Vec<HashMap<usize, usize>>,
+    dag_in: Vec<HashMap<usize, usize>>,
+}
+
+impl SccIndex {
+    /// Computes the SCC partition of `graph` from scratch using Tarjan's algorithm.
+    pub fn from_graph(graph: &GraphCSR) -> Self {
+        let component_of = Self::tarjan(graph);
+        let num_components = component_of.iter().max().map_or(0, |&max| max + 1);
+
+        let mut index = Self {
+            component_of,
+            members: vec![Vec::new(); num_components],
+            dag_out: vec![HashMap::new(); num_components],
+            dag_in: vec![HashMap::new(); num_components],
+        };
+
+        for (node, &comp) in index.component_of.iter().enumerate() {
+            index.members[comp].push(node);
+        }
+
+        for u in 0..graph.num_nodes {
+            for i in graph.node_pointers[u]..graph.node_pointers[u + 1] {
+                let (cu, cv) = (
+                    index.component_of[u],
+                    index.component_of[graph.edge_targets[i]],
+                );
+                if cu != cv {
+                    *index.dag_out[cu].entry(cv).or_insert(0) += 1;
+                    *index.dag_in[cv].entry(cu).or_insert(0) += 1;
+                }
+            }
+        }
+
+        index
+    }
+
+    /// Iterative Tarjan's algorithm returning the component id of every node.
+    fn tarjan(graph: &GraphCSR) -> Vec<usize> {
+        const UNVISITED: usize = usize::MAX;
+
+        let n = graph.num_nodes;
+        let mut index = vec![UNVISITED; n];
+        let mut lowlink = vec![0; n];
+        let mut on_stack = vec![false; n];
+        let mut component_of = vec![0; n];
+        let mut stack: Vec<usize> = Vec::new();
+        // Explicit DFS stack of (node, next CSR edge index to explore).
+        let mut call_stack: Vec<(usize, usize)> = Vec::new();
+        let mut next_index = 0;
+        let mut next_component = 0;
+
+        for root in 0..n {
+            if index[root] != UNVISITED {
+                continue;
+            }
+
+            index[root] = next_index;
+            lowlink[root] = next_index;
+            next_index += 1;
+            stack.push(root);
+            on_stack[root] = true;
+            call_stack.push((root, graph.node_pointers[root]));
+
+            while let Some(frame) = call_stack.last_mut() {
+                let u = frame.0;
+
+                if frame.1 < graph.node_pointers[u + 1] {
+                    let v = graph.edge_targets[frame.1];
+                    frame.1 += 1;
+
+                    if index[v] == UNVISITED {
+                        index[v] = next_index;
+                        lowlink[v] = next_index;
+                        next_index += 1;
+                        stack.push(v);
+                        on_stack[v] = true;
+                        call_stack.push((v, graph.node_pointers[v]));
+                    } else if on_stack[v] {
+                        lowlink[u] = lowlink[u].min(index[v]);
+                    }
+                    continue;
+                }
+
+                call_stack.pop();
+                if let Some(&(parent, _)) = call_stack.last() {
+                    lowlink[parent] = lowlink[parent].min(lowlink[u]);
+                }
+
+                if lowlink[u] == index[u] {
+                    while let Some(w) = stack.pop() {
+                        on_stack[w] = false;
+                        component_of[w] = next_component;
+                        if w == u {
+                            break;
+                        }
+                    }
+                    next_component += 1;
+                }
+            }
+        }
+
+        component_of
+    }
+
+    /// Returns the component id of `node`, or `None` if the node is unknown.
+    pub fn component_of(&self, node: usize) -> Option<usize> {
+        self.component_of.get(node).copied()
+    }
+
+    /// Returns true if `u` and `v` are known and belong to the same component.
+    pub fn same_component(&self, u: usize, v: usize) -> bool {
+        matches!(
+            (self.component_of(u), self.component_of(v)),
+            (Some(cu), Some(cv)) if cu == cv
+        )
+    }
+
+    /// Number of (non-empty) strongly connected components.
+    pub fn component_count(&self) -> usize {
+        self.members.iter().filter(|m| !m.is_empty()).count()
+    }
+
+    /// Iterates over the members of every non-empty component.
+    pub fn components(&self) -> impl Iterator<Item = &[usize]> {
+        self.members
+            .iter()
+            .filter(|m| !m.is_empty())
+            .map(|m| m.as_slice())
+    }
+
+    /// Labels every node with the smallest node id in its component.
+    ///
+    /// Two indices describe the same partition iff their canonical labels are equal,
+    /// regardless of how component ids were assigned.
+    pub fn canonical_labels(&self) -> Vec<usize> {
+        let min_member: Vec<usize> = self
+            .members
+            .iter()
+            .map(|m| m.iter().copied().min().unwrap_or(usize::MAX))
+            .collect();
+
+        self.component_of.iter().map(|&c| min_member[c]).collect()
+    }
+
+    /// Registers singleton components for any node up to and including `node`.
+    fn ensure_node(&mut self, node: usize) {
+        while self.component_of.len() <= node {
+            let id = self.members.len();
+            self.component_of.push(id);
+            self.members.push(vec![self.component_of.len() - 1]);
+            self.dag_out.push(HashMap::new());
+            self.dag_in.push(HashMap::new());
+        }
+    }
+
+    /// Incrementally applies the insertion of edge `u -> v`.
+    ///
+    /// Unknown nodes are added as singleton components. Each call counts as one edge
+    /// in the condensation DAG, so the same edge should not be reported twice.
+    pub fn add_edge(&mut self, u: usize, v: usize) {
+        self.ensure_node(u.max(v));
+
+        let (cu, cv) = (self.component_of[u], self.component_of[v]);
+        if cu == cv {
+            return;
+        }
+
+        *self.dag_out[cu].entry(cv).or_insert(0) += 1;
+        *self.dag_in[cv].entry(cu).or_insert(0) += 1;
+
+        // A new cycle appears only if comp(u) was already reachable from comp(v).
+        let forward = Self::reachable(cv, &self.dag_out);
+        if !forward.contains(&cu) {
+            return;
+        }
+
+        let backward = Self::reachable(cu, &self.dag_in);
+        let merged: HashSet<usize> = forward.intersection(&backward).copied().collect();
+        self.merge(&merged);
+    }
+
+    /// Applies the removal of edge `u -> v`.
+    ///
+    /// `graph` must already reflect the removal; it is only consulted when the edge was
+    /// internal to a component and a full recomputation is required.
+    ///
+    /// # Returns
+    /// `true` if the index fell back to a full Tarjan recomputation.
+    pub fn remove_edge(&mut self, u: usize, v: usize, graph: &GraphCSR) -> bool {
+        let (Some(cu), Some(cv)) = (self.component_of(u), self.component_of(v)) else {
+            return false;
+        };
+
+        if cu != cv {
+            Self::decrement(&mut self.dag_out[cu], cv);
+            Self::decrement(&mut self.dag_in[cv], cu);
+            return false;
+        }
+
+        *self = Self::from_graph(graph);
+        true
+    }
+
+    fn decrement(edges: &mut HashMap<usize, usize>, key: usize) {
+        if let Some(count) = edges.get_mut(&key) {
+            *count -= 1;
+            if *count == 0 {
+                edges.remove(&key);
+            }
+        }
+    }
+
+    /// Collects all components reachable from `start` following `adjacency`.
+    fn reachable(start: usize, adjacency: &[HashMap<usize, usize>]) -> HashSet<usize> {
+        let mut seen = HashSet::from([start]);
+        let mut stack = vec![start];
+
+        while let Some(c) = stack.pop() {
+            for &next in adjacency[c].keys() {
+                if seen.insert(next) {
+                    stack.push(next);
+                }
+            }
+        }
+
+        seen
+    }
+
+    /// Collapses all `components` into the one with the smallest id.
+    fn merge(&mut self, components: &HashSet<usize>) {
+        let Some(&target) = components.iter().min() else {
+            return;
+        };
+
+        for &c in components {
+            if c == target {
+                continue;
+            }
+
+            let nodes = std::mem::take(&mut self.members[c]);
+            for &node in &nodes {
+                self.component_of[node] = target;
+            }
+            self.members[target].extend(nodes);
+
+            for (d, count) in std::mem::take(&mut self.dag_out[c]) {
+                self.dag_in[d].remove(&c);
+                if !components.contains(&d) {
+                    *self.dag_out[target].entry(d).or_insert(0) += count;
+                    *self.dag_in[d].entry(target).or_insert(0) += count;
+                }
+            }
+
+            for (d, count) in std::mem::take(&mut self.dag_in[c]) {
+                self.dag_out[d].remove(&c);
+                if !components.contains(&d) {
+                    *self.dag_in[target].entry(d).or_insert(0) += count;
+                    *self.dag_out[d].entry(target).or_insert(0) += count;
+                }
+            }
+        }
+
+        // Edges between merged components are now internal to `target`.
+        self.dag_out[target].retain(|d, _| !components.contains(d));
+        self.dag_in[target].retain(|d, _| !components.contains(d));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::types::Edge;
+
+    fn scratch(edges: &[Edge]) -> SccIndex {
+        let num_nodes = edges
+            .iter()
+            .flat_map(|&(u, v, _)| [u, v])
+            .max()
+            .map_or(0, |m| m + 1);
+        SccIndex::from_graph(&GraphCSR::from_edges(
+            num_nodes,
+            &mut edges.to_vec(),
+            edges.len(),
+        ))
+    }
+
+    #[test]
+    fn tarjan_finds_components() {
+        let index = scratch(&[
+            (0, 1, 1.0),
+            (1, 2, 1.0),
+            (2, 0, 1.0),
+            (2, 3, 1.0),
+            (3, 4, 1.0),
+            (4, 3, 1.0),
+            (4, 5, 1.0),
+        ]);
+
+        assert_eq!(index.component_count(), 3);
+        assert_eq!(index.canonical_labels(), vec![0, 0, 0, 3, 3, 5]);
+        assert!(index.same_component(0, 2));
+        assert!(!index.same_component(2, 3));
+    }
+
+    #[test]
+    fn incremental_additions_match_recomputation() {
+        let sequence: Vec<Edge> = vec![
+            (0, 1, 1.0),
+            (1, 2, 1.0),
+            (3, 4, 1.0),
+            (2, 3, 1.0),
+            (4, 5, 1.0),
+            (5, 3, 1.0),
+            (6, 0, 1.0),
+            (4, 1, 1.0),
+            (2, 0, 1.0),
+            (7, 7, 1.0),
+            (5, 6, 1.0),
+        ];
+
+        let mut index = SccIndex::default();
+        for (step, &(u, v, _)) in sequence.iter().enumerate() {
+            index.add_edge(u, v);
+
+            let expected = scratch(&sequence[..=step]);
+            assert_eq!(
+                index.canonical_labels(),
+                expected.canonical_labels(),
+                "Partition diverged after adding edge #{step} ({u} -> {v})"
+            );
+        }
+
+        assert_eq!(index.component_count(), 2);
+    }
+
+    #[test]
+    fn removing_internal_edge_falls_back_to_recomputation() {
+        let mut edges = vec![(0, 1, 1.0), (1, 0, 1.0), (1, 2, 1.0)];
+        let mut index = scratch(&edges);
+        assert!(index.same_component(0, 1));
+
+        // Cross-component removal is handled locally.
+        edges.retain(|&(u, v, _)| (u, v) != (1, 2));
+        let graph = GraphCSR::from_edges(3, &mut edges.clone(), 3);
+        assert!(!index.remove_edge(1, 2, &graph));
+
+        // Internal removal splits the component and needs a full pass.
+        edges.retain(|&(u, v, _)| (u, v) != (1, 0));
+        let graph = GraphCSR::from_edges(3, &mut edges.clone(), 3);
+        assert!(index.remove_edge(1, 0, &graph));
+        assert!(!index.same_component(0, 1));
+        assert_eq!(index.canonical_labels(), vec![0, 1, 2]);
+    }
+}
@@ -1,14 +1,24 @@
-use super::csr::GraphCSR;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use super::csr::{EdgeLayout, GraphCSR, PackedEdge};
+use super::scc::SccIndex;
 use super::traits::GraphSolver;
+use super::transform::WeightTransform;
 use common::{
     error::Error,
     types::{Edge, WeightedCycle},
 };
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
 use std::f64;
 
+/// Relative gap in product rate within which [`SPFASolver::find_best_cycle`] treats two
+/// cycles as equally profitable, absorbing rounding from different hop orders.
+const PROFIT_TIE_EPS: f64 = 1e-9;
+
 /// Solver implementing the Shortest Path Faster Algorithm (SPFA) for single-source shortest paths
 /// and negative cycle detection.
+#[derive(Debug, Clone, Copy, Default)]
 pub struct SPFASolver;
 
 impl SPFASolver {
@@ -38,49 +48,125 @@ impl SPFASolver {
         start: usize,
         pred_edge_idx: &[Option<usize>],
         graph: &GraphCSR,
+    ) -> Result<WeightedCycle, Error> {
+        self.reconstruct_cycle_with_scratch(
+            start,
+            pred_edge_idx,
+            graph,
+            &mut ReconstructScratch::default(),
+        )
+    }
+
+    /// Same as [`SPFASolver::reconstruct_cycle`], but traces the predecessors in the
+    /// caller-provided `scratch` buffers instead of fresh ones.
+    ///
+    /// Reusing `scratch` across calls (e.g. when extracting many cycles in a loop)
+    /// amortizes its allocations, including the per-node array. Only the returned
+    /// cycle's own `path` and `rates` are allocated, each with exact capacity.
+    pub fn reconstruct_cycle_with_scratch(
+        &self,
+        start: usize,
+        pred_edge_idx: &[Option<usize>],
+        graph: &GraphCSR,
+        scratch: &mut ReconstructScratch,
     ) -> Result<WeightedCycle, Error> {
         let num_nodes = graph.num_nodes;
         if start >= num_nodes {
             return Err(Error::InvalidGraph);
         }
 
-        // Trace backwards up by `num_nodes` steps to ensure we reach a node
-        // inside the negative cycle.
+        // Trace predecessors back from `start` in a single pass, remembering the step at
+        // which each node was reached; the first node reached twice is the cycle entry.
+        // `walk[i]` is the predecessor edge taken at step `i`; `seen_at[u]` is one past
+        // the step `u` was reached at. Only the entries the last call set are reset.
+        let ReconstructScratch {
+            walk,
+            seen_at,
+            visited,
+        } = scratch;
+        for node in visited.drain(..) {
+            seen_at[node] = 0;
+        }
+        seen_at.resize(num_nodes, 0);
+        walk.clear();
         let mut trace_node = start;
-        for _ in 0..num_nodes {
+        let entry_step = loop {
+            if seen_at[trace_node] != 0 {
+                break seen_at[trace_node] - 1;
+            }
+            seen_at[trace_node] = walk.len() + 1;
+            visited.push(trace_node);
+
             let edge_idx = pred_edge_idx[trace_node].ok_or(Error::CycleReconstructionFailed)?;
+            walk.push(edge_idx);
             trace_node = graph.get_edge_source_node(edge_idx)?;
+        };
+
+        // Keep only the loop, rotated to start where a `num_nodes`-step trace would
+        // have landed, so results match the classic two-pass reconstruction exactly.
+        walk.drain(..entry_step);
+        let cycle_len = walk.len();
+        walk.rotate_left((num_nodes - entry_step) % cycle_len);
+        walk.reverse();
+
+        Self::cycle_from_edge_indices(walk, graph)
+    }
+
+    /// The classic two-pass reconstruction: `num_nodes` predecessor steps from `start`
+    /// to be sure to stand on the loop, then a second walk collecting it.
+    ///
+    /// Returns the same cycle as [`SPFASolver::reconstruct_cycle`], which stops at the
+    /// first node reached twice instead; kept as the reference it is checked and
+    /// benchmarked against.
+    ///
+    /// # Errors
+    /// As [`SPFASolver::reconstruct_cycle`].
+    pub fn reconstruct_cycle_two_pass(
+        &self,
+        start: usize,
+        pred_edge_idx: &[Option<usize>],
+        graph: &GraphCSR,
+    ) -> Result<WeightedCycle, Error> {
+        if start >= graph.num_nodes {
+            return Err(Error::InvalidGraph);
         }
 
-        let cycle_start_node = trace_node;
-        let mut cycle_edge_indices: Vec<usize> = Vec::new();
-        let mut current_node = cycle_start_node;
+        let mut trace_node = start;
+        for _ in 0..graph.num_nodes {
+            let edge_idx = pred_edge_idx[trace_node].ok_or(Error::CycleReconstructionFailed)?;
+            trace_node = graph.get_edge_source_node(edge_idx)?;
+        }
 
+        let mut edge_indices = Vec::new();
+        let mut current_node = trace_node;
         loop {
             let edge_idx = pred_edge_idx[current_node].ok_or(Error::CycleReconstructionFailed)?;
-            cycle_edge_indices.push(edge_idx);
-
-            let source_node = graph.get_edge_source_node(edge_idx)?;
-            current_node = source_node;
-
-            if current_node == cycle_start_node {
+            edge_indices.push(edge_idx);
+            current_node = graph.get_edge_source_node(edge_idx)?;
+            if current_node == trace_node {
                 break;
             }
         }
+        edge_indices.reverse();
+        Self::cycle_from_edge_indices(&edge_indices, graph)
+    }
 
-        cycle_edge_indices.reverse();
-
-        let len = cycle_edge_indices.len();
+    /// Builds the cycle made of the CSR edges `edge_indices`, in order.
+    pub(crate) fn cycle_from_edge_indices(
+        edge_indices: &[usize],
+        graph: &GraphCSR,
+    ) -> Result<WeightedCycle, Error> {
+        let len = edge_indices.len();
         let mut path: Vec<Edge> = Vec::with_capacity(len);
         let mut rates: Vec<f64> = Vec::with_capacity(len);
         let mut log_rate_sum = 0.0f64;
 
-        for &edge_idx in &cycle_edge_indices {
+        for &edge_idx in edge_indices {
             let weight = graph.edge_weights[edge_idx];
             let v = graph.edge_targets[edge_idx];
             let u = graph.get_edge_source_node(edge_idx)?;
 
-            let rate = (-weight).exp();
+            let rate = graph.edge_value(edge_idx);
             path.push((u, v, rate));
             rates.push(rate);
             log_rate_sum += weight;
@@ -94,55 +180,135 @@ impl SPFASolver {
     }
 }
 
-impl GraphSolver for SPFASolver {
-    /// Finds the shortest path from `source` and detects the first reachable negative cycle (SPFA).
+impl SPFASolver {
+    /// Reconstructs the cycle flagged at `v`, treating a broken predecessor chain as a
+    /// false alarm rather than an error.
     ///
-    /// # Parameters
-    /// - `graph`: The CSR data structure for fast edge traversal.
-    /// - `source`: Starting node ID.
-    /// - `hop_cap`: Max relaxations per node (typically N).
+    /// The relaxation count can reach `hop_cap` without the predecessor graph containing
+    /// a cycle yet (e.g. a profitable self-loop alternating with a longer incoming path).
+    /// Returns `Ok(None)` in that case so the caller can reset the count and keep relaxing.
+    pub(crate) fn confirm_cycle(
+        &self,
+        v: usize,
+        pred_edge_idx: &[Option<usize>],
+        graph: &GraphCSR,
+    ) -> Result<Option<WeightedCycle>, Error> {
+        match self.reconstruct_cycle(v, pred_edge_idx, graph) {
+            Ok(cycle) => Ok(Some(cycle)),
+            Err(Error::CycleReconstructionFailed) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Core SPFA loop shared by the full and the localized searches.
     ///
-    /// # Returns
-    /// - `Ok(Some(cycle))` → Profitable cycle found.
-    /// - `Ok(None)` → No negative cycle found.
-    /// - `Err(e)` → Error occurred.
-    fn find_profitable_cycle(
+    /// Only the `seeds` start at distance 0 and are enqueued (in the given order);
+    /// all other nodes start at infinity and are only explored once a seed's
+    /// relaxations reach them. See [`Seeding`] for the two standard seed sets.
+    fn run_spfa(
         &self,
         graph: &GraphCSR,
-        source: usize,
+        seeds: impl IntoIterator<Item = usize>,
         hop_cap: usize,
+        stats: &mut SearchStats,
     ) -> Result<Option<WeightedCycle>, Error> {
-        if source >= graph.num_nodes {
-            return Err(Error::NodeIndexOutOfBounds(source));
+        self.run_spfa_with_state(
+            graph,
+            seeds,
+            hop_cap,
+            stats,
+            &mut SearchState::default(),
+            EdgeLayout::default(),
+        )
+    }
+
+    /// [`SPFASolver::run_spfa`], leaving the final arrays in `state` for inspection and
+    /// reading edges from `layout`.
+    fn run_spfa_with_state(
+        &self,
+        graph: &GraphCSR,
+        seeds: impl IntoIterator<Item = usize>,
+        hop_cap: usize,
+        stats: &mut SearchStats,
+        state: &mut SearchState,
+        layout: EdgeLayout,
+    ) -> Result<Option<WeightedCycle>, Error> {
+        match layout {
+            EdgeLayout::Split => self.run_spfa_over(graph, seeds, hop_cap, stats, state, |i| {
+                (graph.edge_targets[i], graph.edge_weights[i])
+            }),
+            EdgeLayout::Packed => {
+                let packed = graph.packed_edges();
+                let packed = &*packed;
+                self.run_spfa_over(graph, seeds, hop_cap, stats, state, |i| {
+                    let PackedEdge { target, weight } = packed[i];
+                    (target, weight)
+                })
+            }
+        }
+    }
+
+    /// Distances after a single relaxation round from `seeding`'s initial distances.
+    ///
+    /// Each edge is relaxed once against the initial distances (not against distances
+    /// improved earlier in the same round), i.e. one Bellman-Ford pass. Useful to
+    /// inspect how a seeding strategy starts the search.
+    ///
+    /// # Errors
+    /// Returns `Error::NodeIndexOutOfBounds` if a `Seeding::Source` is not a node.
+    pub fn relax_round(&self, graph: &GraphCSR, seeding: Seeding) -> Result<Vec<f64>, Error> {
+        let initial = seeding.initial_distances(graph.num_nodes)?;
+        let mut distance = initial.clone();
+        for (u, &base) in initial.iter().enumerate() {
+            for i in graph.node_pointers[u]..graph.node_pointers[u + 1] {
+                let v = graph.edge_targets[i];
+                distance[v] = distance[v].min(base + graph.edge_weights[i]);
+            }
         }
+        Ok(distance)
+    }
 
+    /// The SPFA loop proper; `edge(i)` yields the target and weight of CSR edge `i`.
+    fn run_spfa_over(
+        &self,
+        graph: &GraphCSR,
+        seeds: impl IntoIterator<Item = usize>,
+        hop_cap: usize,
+        stats: &mut SearchStats,
+        state: &mut SearchState,
+        edge: impl Fn(usize) -> (usize, f64),
+    ) -> Result<Option<WeightedCycle>, Error> {
         let num_nodes = graph.num_nodes;
-        let mut distance = vec![f64::INFINITY; num_nodes];
-        let mut count = vec![0; num_nodes]; // Tracks relaxations/hops
+        *state = SearchState {
+            distance: vec![f64::INFINITY; num_nodes],
+            count: vec![0; num_nodes], // Tracks relaxations/hops
+            // Stores the CSR index of the predecessor edge.
+            pred_edge_idx: vec![None; num_nodes],
+        };
+        let SearchState {
+            distance,
+            count,
+            pred_edge_idx,
+        } = state;
         let mut in_queue = vec![false; num_nodes];
 
-        // Stores the CSR index of the predecessor edge.
-        let mut pred_edge_idx = vec![None; num_nodes];
-
         let mut queue = VecDeque::with_capacity(num_nodes);
 
-        // distance[source] = 0.0;
-        // queue.push_back(source);
-        // in_queue[source] = true;
-
-        // To guarantee detection of any negative cycle in the entire graph, regardless of
-        // whether the arbitrary 'source' node can reach it (i.e., handling disconnected components),
-        // we initialize all nodes to a distance of 0.0 and add them to the queue.
-        // This simulates connecting a virtual zero-weight source to every node.
-        for i in 0..num_nodes {
-            distance[i] = 0.0;
-            queue.push_back(i);
-            in_queue[i] = true;
+        for seed in seeds {
+            if seed >= num_nodes {
+                return Err(Error::NodeIndexOutOfBounds(seed));
+            }
+            if !in_queue[seed] {
+                distance[seed] = 0.0;
+                queue.push_back(seed);
+                in_queue[seed] = true;
+            }
         }
 
         // SPFA Loop: Propagate distances while the queue is not empty.
         while let Some(u) = queue.pop_front() {
             in_queue[u] = false;
+            stats.dequeues += 1;
 
             let start = graph.node_pointers[u];
             let end = graph.node_pointers[u + 1];
@@ -150,16 +316,18 @@ impl GraphSolver for SPFASolver {
             // Traverse edges u -> v
             // 'i' is the CSR index of the edge (u,v)
             for i in start..end {
-                let v = graph.edge_targets[i];
-                let weight = graph.edge_weights[i];
+                let (v, weight) = edge(i);
                 if distance[u] + weight < distance[v] {
                     distance[v] = distance[u] + weight;
                     pred_edge_idx[v] = Some(i);
+                    stats.relaxations += 1;
 
                     count[v] += 1;
                     if count[v] >= hop_cap {
-                        let cycle = self.reconstruct_cycle(v, &pred_edge_idx, graph)?;
-                        return Ok(Some(cycle));
+                        match self.confirm_cycle(v, pred_edge_idx, graph)? {
+                            Some(cycle) => return Ok(Some(cycle)),
+                            None => count[v] = 0,
+                        }
                     }
 
                     if !in_queue[v] {
@@ -172,123 +340,1818 @@ impl GraphSolver for SPFASolver {
 
         Ok(None)
     }
-}
 
-#[cfg(test)]
-mod spfa_tests {
-    use super::*;
-    use common::types::Edge;
+    /// Same as [`GraphSolver::find_profitable_cycle`], additionally returning search statistics.
+    pub fn find_profitable_cycle_with_stats(
+        &self,
+        graph: &GraphCSR,
+        source: usize,
+        hop_cap: usize,
+    ) -> Result<(Option<WeightedCycle>, SearchStats), Error> {
+        if graph.num_nodes == 0 {
+            return Err(Error::EmptyGraph);
+        }
+        if source >= graph.num_nodes {
+            return Err(Error::NodeIndexOutOfBounds(source));
+        }
 
-    fn build_graph(edges: &mut [Edge], num_nodes: usize) -> GraphCSR {
-        GraphCSR::from_edges(num_nodes, edges, edges.len())
+        // To guarantee detection of any negative cycle in the entire graph, regardless of
+        // whether the arbitrary 'source' node can reach it (i.e., handling disconnected components),
+        // we initialize all nodes to a distance of 0.0 and add them to the queue.
+        let mut stats = SearchStats::default();
+        let cycle = self.run_spfa(
+            graph,
+            Seeding::AllZero.seeds(graph.num_nodes)?,
+            hop_cap,
+            &mut stats,
+        )?;
+        Ok((cycle, stats))
     }
 
-    #[test]
-    fn reconstruct_cycle_small_graph() {
-        let mut edges = vec![(0, 1, 1.0), (1, 2, 0.5), (2, 0, 0.5)];
-        let graph = build_graph(&mut edges, 3);
-
-        let pred_edge_idx = vec![Some(2), Some(0), Some(1)];
-        let solver = SPFASolver;
-        let cycle = solver.reconstruct_cycle(0, &pred_edge_idx, &graph).unwrap();
+    /// Same as [`GraphSolver::find_profitable_cycle`], but when no cycle exists the final
+    /// distances are returned as a [`NoArbitrageCertificate`] instead of `None`.
+    ///
+    /// SPFA only stops without a cycle once no edge can be relaxed any more, so its
+    /// distances from the all-zero virtual source already form a feasible potential.
+    ///
+    /// # Errors
+    /// Same as [`SPFASolver::find_profitable_cycle_with_stats`].
+    pub fn find_profitable_cycle_or_certificate(
+        &self,
+        graph: &GraphCSR,
+        source: usize,
+        hop_cap: usize,
+    ) -> Result<SearchOutcome, Error> {
+        if graph.num_nodes == 0 {
+            return Err(Error::EmptyGraph);
+        }
+        if source >= graph.num_nodes {
+            return Err(Error::NodeIndexOutOfBounds(source));
+        }
 
-        assert_eq!(cycle.path.len(), 3);
-        assert_eq!(cycle.path[0], (0, 1, 1.0));
+        let mut state = SearchState::default();
+        let cycle = self.run_spfa_with_state(
+            graph,
+            Seeding::AllZero.seeds(graph.num_nodes)?,
+            hop_cap,
+            &mut SearchStats::default(),
+            &mut state,
+            EdgeLayout::default(),
+        )?;
+        Ok(match cycle {
+            Some(cycle) => SearchOutcome::Cycle(cycle),
+            None => SearchOutcome::NoArbitrage(NoArbitrageCertificate {
+                potentials: state.distance,
+            }),
+        })
     }
 
-    #[test]
-    fn spfa_detects_simple_negative_cycle() {
-        let mut edges = vec![(1, 0, 2.0), (0, 1, 2.0)];
-        let graph = build_graph(&mut edges, 2);
-
-        let solver = SPFASolver;
+    /// Best conversion rate from `from` to `to`: the highest product of rates over any
+    /// path, found as the shortest path in weight space from `from` alone.
+    ///
+    /// Returns `Ok(None)` if `to` is unreachable and `Some(1.0)` for `from == to`. Rates
+    /// are net of fees, like the graph's weights.
+    ///
+    /// # Errors
+    /// - `Error::EmptyGraph` / `Error::NodeIndexOutOfBounds` for bad inputs.
+    /// - `Error::UnboundedRate` if a profitable cycle is reachable from `from`: looping
+    ///   it before continuing would make any rate achievable. This is conservative; the
+    ///   cycle need not lead to `to`.
+    pub fn best_rate(
+        &self,
+        graph: &GraphCSR,
+        from: usize,
+        to: usize,
+    ) -> Result<Option<f64>, Error> {
+        if graph.num_nodes == 0 {
+            return Err(Error::EmptyGraph);
+        }
+        if to >= graph.num_nodes {
+            return Err(Error::NodeIndexOutOfBounds(to));
+        }
 
-        let cycle = solver.find_profitable_cycle(&graph, 0, 2).unwrap();
-        assert!(cycle.is_some());
+        let mut state = SearchState::default();
+        let cycle = self.run_spfa_with_state(
+            graph,
+            Seeding::Source(from).seeds(graph.num_nodes)?,
+            graph.num_nodes + 1,
+            &mut SearchStats::default(),
+            &mut state,
+            EdgeLayout::default(),
+        )?;
+        if cycle.is_some() {
+            return Err(Error::UnboundedRate);
+        }
 
-        let cycle = cycle.unwrap();
-        assert_eq!(cycle.path, vec![(1, 0, 2.0), (0, 1, 2.0)]);
-        assert!(cycle.log_rate_sum < 0.0);
+        let distance = state.distance[to];
+        Ok(distance
+            .is_finite()
+            .then(|| graph.weight_transform.inverse(distance)))
     }
 
-    #[test]
-    fn spfa_no_negative_cycle_returns_none() {
-        let mut edges = vec![(0, 1, 1.0), (1, 2, 1.2), (2, 3, 1.2)];
-        let graph = build_graph(&mut edges, 4);
-        let solver = SPFASolver;
+    /// Same search as [`GraphSolver::find_profitable_cycle`], also returning the solver's
+    /// final arrays for debugging unexpected results.
+    ///
+    /// When a cycle is found the arrays are captured at the moment of detection, so the
+    /// flagged node's `count` is at least `hop_cap`.
+    ///
+    /// # Errors
+    /// Same as [`SPFASolver::find_profitable_cycle_with_stats`].
+    pub fn find_profitable_cycle_debug(
+        &self,
+        graph: &GraphCSR,
+        source: usize,
+        hop_cap: usize,
+    ) -> Result<(Option<WeightedCycle>, SearchState), Error> {
+        if graph.num_nodes == 0 {
+            return Err(Error::EmptyGraph);
+        }
+        if source >= graph.num_nodes {
+            return Err(Error::NodeIndexOutOfBounds(source));
+        }
 
-        let cycle = solver.find_profitable_cycle(&graph, 0, 4).unwrap();
-        assert!(cycle.is_none());
+        let mut state = SearchState::default();
+        let cycle = self.run_spfa_with_state(
+            graph,
+            Seeding::AllZero.seeds(graph.num_nodes)?,
+            hop_cap,
+            &mut SearchStats::default(),
+            &mut state,
+            EdgeLayout::default(),
+        )?;
+        Ok((cycle, state))
     }
 
-    #[test]
-    fn spfa_single_node_graph() {
-        let graph = build_graph(&mut [], 1);
-        let solver = SPFASolver;
+    /// [`SPFASolver::find_profitable_cycle_with_stats`] reading edges from `layout`
+    /// instead of the build's default, e.g. to benchmark the layouts against each other.
+    /// Both layouts visit edges in the same order, so results and stats are identical.
+    ///
+    /// # Errors
+    /// Same as [`SPFASolver::find_profitable_cycle_with_stats`].
+    pub fn find_profitable_cycle_with_layout(
+        &self,
+        graph: &GraphCSR,
+        source: usize,
+        hop_cap: usize,
+        layout: EdgeLayout,
+    ) -> Result<(Option<WeightedCycle>, SearchStats), Error> {
+        if graph.num_nodes == 0 {
+            return Err(Error::EmptyGraph);
+        }
+        if source >= graph.num_nodes {
+            return Err(Error::NodeIndexOutOfBounds(source));
+        }
 
-        let cycle = solver.find_profitable_cycle(&graph, 0, 1).unwrap();
-        assert!(cycle.is_none());
+        let mut stats = SearchStats::default();
+        let cycle = self.run_spfa_with_state(
+            graph,
+            Seeding::AllZero.seeds(graph.num_nodes)?,
+            hop_cap,
+            &mut stats,
+            &mut SearchState::default(),
+            layout,
+        )?;
+        Ok((cycle, stats))
     }
 
-    #[test]
-    fn spfa_empty_graph_returns_error() {
-        let graph = build_graph(&mut [], 0);
-        let solver = SPFASolver;
+    /// Searches for a negative cycle starting from recently changed nodes.
+    ///
+    /// When updates are localized, a newly created cycle must pass through one of the
+    /// changed edges' endpoints, so seeding SPFA only from `changed_nodes` finds it
+    /// without relaxing the untouched remainder of the graph.
+    ///
+    /// Correctness is preserved: if the localized pass finds nothing (or
+    /// `changed_nodes` is empty), the solver falls back to a full search and
+    /// `SearchStats::fell_back` is set. Stats accumulate across both passes.
+    ///
+    /// # Errors
+    /// - `Error::EmptyGraph` if the graph has no nodes.
+    /// - `Error::NodeIndexOutOfBounds` if a changed node is outside the graph.
+    pub fn find_cycle_near(
+        &self,
+        graph: &GraphCSR,
+        changed_nodes: &[usize],
+        hop_cap: usize,
+    ) -> Result<(Option<WeightedCycle>, SearchStats), Error> {
+        if graph.num_nodes == 0 {
+            return Err(Error::EmptyGraph);
+        }
 
-        let result = solver.find_profitable_cycle(&graph, 0, 1);
-        assert!(result.is_err());
+        let mut stats = SearchStats::default();
+
+        if !changed_nodes.is_empty() {
+            let cycle = self.run_spfa(graph, changed_nodes.iter().copied(), hop_cap, &mut stats)?;
+            if cycle.is_some() {
+                return Ok((cycle, stats));
+            }
+        }
+
+        stats.fell_back = true;
+        let cycle = self.run_spfa(
+            graph,
+            Seeding::AllZero.seeds(graph.num_nodes)?,
+            hop_cap,
+            &mut stats,
+        )?;
+        Ok((cycle, stats))
     }
+}
 
-    // ----------------------------
-    // Stress and edge-case tests
-    // ----------------------------
+impl SPFASolver {
+    /// Negative-cycle search that shares solver time fairly across strongly connected components.
+    ///
+    /// With the virtual all-node seeding, a large component can keep the single FIFO queue
+    /// busy for many rounds, delaying detection of a cycle in a small component. Here each
+    /// SCC gets its own queue and the solver processes one node per component in
+    /// round-robin order, so every component progresses at the same rate.
+    ///
+    /// Edges between different SCCs can never lie on a cycle, so they are skipped; this
+    /// keeps components independent without affecting which cycles can be found.
+    ///
+    /// # Complexity
+    /// Adds an O(V + E) Tarjan pass per search on top of the usual SPFA cost.
+    ///
+    /// # Errors
+    /// Returns `Error::EmptyGraph` if the graph has no nodes.
+    pub fn find_profitable_cycle_fair(
+        &self,
+        graph: &GraphCSR,
+        hop_cap: usize,
+    ) -> Result<(Option<WeightedCycle>, SearchStats), Error> {
+        let num_nodes = graph.num_nodes;
+        if num_nodes == 0 {
+            return Err(Error::EmptyGraph);
+        }
 
-    #[test]
-    fn spfa_large_linear_graph_no_cycle() {
-        let n = 1000;
-        let mut edges: Vec<Edge> = (0..n - 1).map(|i| (i, i + 1, 1.0)).collect();
-        let graph = build_graph(&mut edges, n);
-        let solver = SPFASolver;
+        let scc = SccIndex::from_graph(graph);
+        let component: Vec<usize> = (0..num_nodes)
+            .map(|node| scc.component_of(node).ok_or(Error::InvalidGraph))
+            .collect::<Result<_, _>>()?;
 
-        let cycle = solver.find_profitable_cycle(&graph, 0, n).unwrap();
-        assert!(cycle.is_none());
-    }
+        let mut stats = SearchStats::default();
+        let mut distance = vec![0.0f64; num_nodes];
+        let mut count = vec![0; num_nodes];
+        let mut in_queue = vec![true; num_nodes];
+        let mut pred_edge_idx = vec![None; num_nodes];
 
-    #[test]
-    fn spfa_large_circular_graph_negative_cycle() {
-        let n = 1000;
-        let mut edges: Vec<(usize, usize, f64)> = (0..n)
-            .map(|i| {
-                let next = (i + 1) % n;
-                let rate = 1.001;
-                (i, next, rate)
-            })
-            .collect();
+        // One queue per component, seeded with all of its nodes.
+        let mut queues: Vec<VecDeque<usize>> = vec![VecDeque::new(); scc.component_count()];
+        for node in 0..num_nodes {
+            queues[component[node]].push_back(node);
+        }
+        let mut active: VecDeque<usize> = (0..queues.len()).collect();
 
-        let graph = build_graph(&mut edges, n);
+        while let Some(c) = active.pop_front() {
+            let Some(u) = queues[c].pop_front() else {
+                continue;
+            };
+            in_queue[u] = false;
+            stats.dequeues += 1;
 
-        let solver = SPFASolver;
-        let cycle = solver.find_profitable_cycle(&graph, 0, n + 1).unwrap();
-        assert!(cycle.is_some());
-        let cycle = cycle.unwrap();
-        assert!(cycle.log_rate_sum < 0.0);
-        assert!(cycle.path.len() <= n);
-    }
+            for i in graph.node_pointers[u]..graph.node_pointers[u + 1] {
+                let v = graph.edge_targets[i];
+                if component[v] != c {
+                    continue;
+                }
 
-    #[test]
-    fn spfa_detects_arbitrage_in_disconnected_component() {
-        let mut edges: Vec<Edge> = vec![
-            // Reachable from default source (Node 0), but unprofitable.
-            // Product: 1.0 * 0.5 * 0.5 = 0.25 (LOSS / Positive Cycle)
-            (0, 1, 1.0),
-            (1, 2, 0.5),
-            (2, 0, 0.5),
-            // Disconnected from source 0, but profitable.
-            // Product: 1.0 * 1.1 = 1.1 (PROFIT / Negative Cycle)
-            (3, 4, 1.0),
-            (4, 3, 1.1),
-        ];
-        let graph = build_graph(&mut edges, 5);
+                let weight = graph.edge_weights[i];
+                if distance[u] + weight < distance[v] {
+                    distance[v] = distance[u] + weight;
+                    pred_edge_idx[v] = Some(i);
+                    stats.relaxations += 1;
+
+                    count[v] += 1;
+                    if count[v] >= hop_cap {
+                        match self.confirm_cycle(v, &pred_edge_idx, graph)? {
+                            Some(cycle) => return Ok((Some(cycle), stats)),
+                            None => count[v] = 0,
+                        }
+                    }
+
+                    if !in_queue[v] {
+                        queues[c].push_back(v);
+                        in_queue[v] = true;
+                    }
+                }
+            }
+
+            if !queues[c].is_empty() {
+                active.push_back(c);
+            }
+        }
+
+        Ok((None, stats))
+    }
+}
+
+impl SPFASolver {
+    /// Finds the profitable cycles of every SCC, most profitable first.
+    ///
+    /// Cycles cannot span components, so each non-trivial SCC is searched on its own
+    /// induced subgraph. SPFA reports one negative cycle per run; after each detection
+    /// the cycle's highest-rate edge (the one contributing most to the profit) is
+    /// disabled and the search repeated until the component has no profitable cycle
+    /// left. Every run disables an edge, so at most `E` searches are performed.
+    ///
+    /// This is not an enumeration of every simple cycle: of several loops sharing a
+    /// disabled edge, only the first one found is reported.
+    ///
+    /// Results are sorted by `log_rate_sum`, then by canonical key, and are returned
+    /// in canonical rotation.
+    pub fn find_all_negative_cycles(&self, graph: &GraphCSR) -> Result<Vec<WeightedCycle>, Error> {
+        self.collect_negative_cycles(graph, graph.num_nodes + 1, usize::MAX)
+            .map(|(cycles, _)| cycles)
+    }
+
+    /// [`SPFASolver::find_all_negative_cycles`] that stops once `max_cycles` cycles have
+    /// been collected, bounding memory on graphs with very many cycles.
+    ///
+    /// Returns the cycles (ranked as usual) and whether enumeration was truncated, i.e.
+    /// more cycles would have been found. The kept cycles are the first ones found in
+    /// component order, not necessarily the most profitable.
+    pub fn find_all_negative_cycles_capped(
+        &self,
+        graph: &GraphCSR,
+        max_cycles: usize,
+    ) -> Result<(Vec<WeightedCycle>, bool), Error> {
+        self.collect_negative_cycles(graph, graph.num_nodes + 1, max_cycles)
+    }
+
+    /// Returns the profitable cycle with the fewest hops, breaking ties by profit.
+    ///
+    /// Shorter cycles are cheaper and less risky to execute. Candidates are the
+    /// cycles detected by [`SPFASolver::find_all_negative_cycles`], with `hop_cap`
+    /// used as the detection threshold for each component search.
+    ///
+    /// # Errors
+    /// Returns `Error::EmptyGraph` if the graph has no nodes.
+    pub fn find_shortest_profitable_cycle(
+        &self,
+        graph: &GraphCSR,
+        hop_cap: usize,
+    ) -> Result<Option<WeightedCycle>, Error> {
+        if graph.num_nodes == 0 {
+            return Err(Error::EmptyGraph);
+        }
+
+        let (cycles, _) = self.collect_negative_cycles(graph, hop_cap, usize::MAX)?;
+        Ok(cycles.into_iter().min_by(|a, b| {
+            a.path
+                .len()
+                .cmp(&b.path.len())
+                .then_with(|| a.log_rate_sum.total_cmp(&b.log_rate_sum))
+        }))
+    }
+
+    /// Returns the most profitable cycle, preferring deeper liquidity on equal profit.
+    ///
+    /// Profits within a relative `PROFIT_TIE_EPS` of the best count as equal.
+    ///
+    /// The graph carries no liquidity, so it is looked up per hop with
+    /// `liquidity(from, to)`; a cycle's depth is its bottleneck, the minimum over its
+    /// hops. Candidates are the cycles detected by
+    /// [`SPFASolver::find_all_negative_cycles`].
+    ///
+    /// # Errors
+    /// Returns `Error::EmptyGraph` if the graph has no nodes.
+    pub fn find_best_cycle(
+        &self,
+        graph: &GraphCSR,
+        liquidity: impl Fn(usize, usize) -> f64,
+    ) -> Result<Option<WeightedCycle>, Error> {
+        if graph.num_nodes == 0 {
+            return Err(Error::EmptyGraph);
+        }
+
+        let bottleneck = |cycle: &WeightedCycle| {
+            cycle
+                .path
+                .iter()
+                .map(|&(u, v, _)| liquidity(u, v))
+                .fold(f64::INFINITY, f64::min)
+        };
+
+        let (cycles, _) = self.collect_negative_cycles(graph, graph.num_nodes + 1, usize::MAX)?;
+        let Some(best_rate) = cycles
+            .iter()
+            .map(WeightedCycle::product_rate)
+            .max_by(f64::total_cmp)
+        else {
+            return Ok(None);
+        };
+        Ok(cycles
+            .into_iter()
+            .filter(|cycle| best_rate - cycle.product_rate() <= PROFIT_TIE_EPS * best_rate)
+            .max_by(|a, b| {
+                bottleneck(a)
+                    .total_cmp(&bottleneck(b))
+                    .then_with(|| a.product_rate().total_cmp(&b.product_rate()))
+            }))
+    }
+
+    /// [`SPFASolver::find_all_negative_cycles`] with every edge whose liquidity is below
+    /// `min_liquidity` treated as absent.
+    ///
+    /// Thin pools yield cycles that cannot be executed at meaningful size. As in
+    /// [`SPFASolver::find_best_cycle`], liquidity is looked up per edge with
+    /// `liquidity(from, to)`; excluded edges are left out of each component's subgraph,
+    /// so `graph` is neither modified nor copied.
+    pub fn find_all_negative_cycles_with_min_liquidity(
+        &self,
+        graph: &GraphCSR,
+        liquidity: impl Fn(usize, usize) -> f64,
+        min_liquidity: f64,
+    ) -> Result<Vec<WeightedCycle>, Error> {
+        let thin = |u, v| liquidity(u, v) < min_liquidity;
+        self.collect_negative_cycles_masked(graph, graph.num_nodes + 1, usize::MAX, &thin)
+            .map(|(cycles, _)| cycles)
+    }
+
+    /// Returns the CSR indices of every edge on a detected negative cycle.
+    ///
+    /// Meant for risk monitoring, where the set of "hot" edges matters rather than the
+    /// individual loops. Cycles come from the same per-component search as
+    /// [`SPFASolver::find_all_negative_cycles`] (with `hop_cap` as the detection
+    /// threshold), so the same caveat applies: an edge only on loops sharing an
+    /// already-disabled edge may be missed. Parallel edges of a cycle hop are all included.
+    ///
+    /// # Errors
+    /// Returns `Error::EmptyGraph` if the graph has no nodes.
+    pub fn arbitrage_edge_set(
+        &self,
+        graph: &GraphCSR,
+        hop_cap: usize,
+    ) -> Result<HashSet<usize>, Error> {
+        if graph.num_nodes == 0 {
+            return Err(Error::EmptyGraph);
+        }
+
+        let (cycles, _) = self.collect_negative_cycles(graph, hop_cap, usize::MAX)?;
+        Ok(cycles
+            .iter()
+            .flat_map(|cycle| &cycle.path)
+            .flat_map(|&(u, v, _)| {
+                (graph.node_pointers[u]..graph.node_pointers[u + 1])
+                    .filter(move |&i| graph.edge_targets[i] == v)
+            })
+            .collect())
+    }
+
+    /// Finds the guaranteed-loss cycles (product rate below 1), biggest loss first.
+    ///
+    /// Such loops are never traded, but usually point at bad data or a toxic pool, so
+    /// risk checks report them separately. They are the positive-weight cycles of the
+    /// graph, found by running the [`SPFASolver::find_all_negative_cycles`] search on a
+    /// copy with every weight negated; the same caveat about loops sharing a disabled
+    /// edge applies. Rates and `log_rate_sum` are mapped back to the original graph.
+    pub fn find_loss_cycles(&self, graph: &GraphCSR) -> Result<Vec<WeightedCycle>, Error> {
+        let mut negated = graph.clone();
+        for (i, &weight) in graph.edge_weights.iter().enumerate() {
+            negated.set_edge_weight(i, -weight);
+        }
+
+        let transform = &graph.weight_transform;
+        let original_rate = |rate: f64| transform.inverse(-transform.forward(rate));
+
+        let (cycles, _) =
+            self.collect_negative_cycles(&negated, graph.num_nodes + 1, usize::MAX)?;
+        Ok(cycles
+            .into_iter()
+            .map(|cycle| WeightedCycle {
+                path: cycle
+                    .path
+                    .iter()
+                    .map(|&(u, v, rate)| (u, v, original_rate(rate)))
+                    .collect(),
+                rates: cycle
+                    .rates
+                    .iter()
+                    .map(|&rate| original_rate(rate))
+                    .collect(),
+                log_rate_sum: -cycle.log_rate_sum,
+            })
+            .collect())
+    }
+
+    /// Collects up to `max_cycles` cycles; the flag is true if more exist.
+    fn collect_negative_cycles(
+        &self,
+        graph: &GraphCSR,
+        hop_cap: usize,
+        max_cycles: usize,
+    ) -> Result<(Vec<WeightedCycle>, bool), Error> {
+        self.collect_negative_cycles_masked(graph, hop_cap, max_cycles, &|_, _| false)
+    }
+
+    /// [`SPFASolver::collect_negative_cycles`] ignoring every edge for which `skip(from, to)` holds.
+    fn collect_negative_cycles_masked(
+        &self,
+        graph: &GraphCSR,
+        hop_cap: usize,
+        max_cycles: usize,
+        skip: &dyn Fn(usize, usize) -> bool,
+    ) -> Result<(Vec<WeightedCycle>, bool), Error> {
+        let scc = SccIndex::from_graph(graph);
+
+        // One cycle past the cap tells a truncated enumeration from an exact fit.
+        let limit = max_cycles.saturating_add(1);
+        let mut cycles = Vec::new();
+        for members in scc.components() {
+            if cycles.len() >= limit {
+                break;
+            }
+            let remaining = limit - cycles.len();
+            cycles.extend(self.component_cycles(graph, members, hop_cap, remaining, skip)?);
+        }
+
+        let truncated = cycles.len() > max_cycles;
+        cycles.truncate(max_cycles);
+        Self::rank_cycles(&mut cycles);
+        Ok((cycles, truncated))
+    }
+
+    /// Parallel version of [`SPFASolver::find_all_negative_cycles`].
+    ///
+    /// Components are independent, so each one is searched on a rayon worker and
+    /// the results are merged. The output is identical to the sequential version.
+    #[cfg(feature = "parallel")]
+    pub fn find_all_negative_cycles_par(
+        &self,
+        graph: &GraphCSR,
+    ) -> Result<Vec<WeightedCycle>, Error> {
+        let scc = SccIndex::from_graph(graph);
+        let components: Vec<&[usize]> = scc.components().collect();
+
+        let per_component = components
+            .par_iter()
+            .map(|members| {
+                self.component_cycles(graph, members, graph.num_nodes + 1, usize::MAX, |_, _| {
+                    false
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut cycles: Vec<WeightedCycle> = per_component.into_iter().flatten().collect();
+        Self::rank_cycles(&mut cycles);
+        Ok(cycles)
+    }
+
+    /// Repeatedly searches the subgraph induced by `members`, disabling one edge per cycle,
+    /// until no cycle is left or `limit` cycles were found. Edges for which `skip(from, to)`
+    /// holds are left out of the subgraph.
+    fn component_cycles(
+        &self,
+        graph: &GraphCSR,
+        members: &[usize],
+        hop_cap: usize,
+        limit: usize,
+        skip: impl Fn(usize, usize) -> bool,
+    ) -> Result<Vec<WeightedCycle>, Error> {
+        let mut sub = graph.induced_subgraph_masked(members, skip);
+        let mut cycles = Vec::new();
+        if sub.edge_targets.is_empty() {
+            return Ok(cycles);
+        }
+
+        while cycles.len() < limit
+            && let (Some(cycle), _) = self.find_profitable_cycle_with_stats(&sub, 0, hop_cap)?
+        {
+            let Some(&(u, v, _)) = cycle.path.iter().max_by(|a, b| a.2.total_cmp(&b.2)) else {
+                break;
+            };
+
+            // An infinite weight can never be relaxed, which disables the edge in place.
+            for i in sub.node_pointers[u]..sub.node_pointers[u + 1] {
+                if sub.edge_targets[i] == v {
+                    sub.set_edge_weight(i, f64::INFINITY);
+                }
+            }
+
+            let path: Vec<Edge> = cycle
+                .path
+                .iter()
+                .map(|&(u, v, rate)| (members[u], members[v], rate))
+                .collect();
+            cycles.push(WeightedCycle { path, ..cycle }.canonicalize());
+        }
+
+        Ok(cycles)
+    }
+
+    fn rank_cycles(cycles: &mut [WeightedCycle]) {
+        cycles.sort_by(|a, b| {
+            a.log_rate_sum
+                .total_cmp(&b.log_rate_sum)
+                .then_with(|| a.canonical_key().cmp(&b.canonical_key()))
+        });
+    }
+}
+
+impl SPFASolver {
+    /// Finds a profitable cycle that uses the `src -> dst` edge, if one exists.
+    ///
+    /// Runs a hop-bounded Bellman-Ford from `dst`: after `k` rounds, `distance[k][src]`
+    /// is the lightest walk `dst ~> src` of at most `k` edges, which stays well defined
+    /// even when other negative cycles exist. The first round whose walk closes a
+    /// negative loop through `src -> dst` and visits no node twice is returned, starting
+    /// with the queried edge. Cycles are limited to `hop_cap` edges.
+    ///
+    /// The search is sound but not exhaustive: if the lightest walk of every length
+    /// repeats a node, a simple profitable cycle through the edge can be missed.
+    /// Among parallel `src -> dst` edges the best-rate one is used.
+    ///
+    /// # Complexity
+    /// O(hop_cap * E) time, O(hop_cap * V) memory.
+    ///
+    /// # Errors
+    /// - `Error::EmptyGraph` if the graph has no nodes.
+    /// - `Error::NodeIndexOutOfBounds` if `src` or `dst` is not a node of the graph.
+    pub fn find_cycle_through_edge(
+        &self,
+        graph: &GraphCSR,
+        src: usize,
+        dst: usize,
+        hop_cap: usize,
+    ) -> Result<Option<WeightedCycle>, Error> {
+        let num_nodes = graph.num_nodes;
+        if num_nodes == 0 {
+            return Err(Error::EmptyGraph);
+        }
+        if let Some(&node) = [src, dst].iter().find(|&&node| node >= num_nodes) {
+            return Err(Error::NodeIndexOutOfBounds(node));
+        }
+
+        let Some(closing_edge) = (graph.node_pointers[src]..graph.node_pointers[src + 1])
+            .filter(|&i| graph.edge_targets[i] == dst)
+            .min_by(|&a, &b| graph.edge_weights[a].total_cmp(&graph.edge_weights[b]))
+        else {
+            return Ok(None);
+        };
+        let closing_weight = graph.edge_weights[closing_edge];
+
+        if src == dst {
+            return (closing_weight < 0.0)
+                .then(|| Self::cycle_from_edge_indices(&[closing_edge], graph))
+                .transpose();
+        }
+
+        // `distance[k][v]`: lightest walk dst ~> v of at most k edges. `pred_edge_idx[k][v]`
+        // is its last edge if round k improved v, `None` if the round-(k-1) walk stands.
+        let max_path_len = hop_cap.min(num_nodes).saturating_sub(1);
+        let mut distance = vec![vec![f64::INFINITY; num_nodes]];
+        let mut pred_edge_idx: Vec<Vec<Option<usize>>> = vec![vec![None; num_nodes]];
+        distance[0][dst] = 0.0;
+
+        for k in 1..=max_path_len {
+            let mut layer = distance[k - 1].clone();
+            let mut preds = vec![None; num_nodes];
+            for (u, &base) in distance[k - 1].iter().enumerate() {
+                if base == f64::INFINITY {
+                    continue;
+                }
+                for i in graph.node_pointers[u]..graph.node_pointers[u + 1] {
+                    let v = graph.edge_targets[i];
+                    if base + graph.edge_weights[i] < layer[v] {
+                        layer[v] = base + graph.edge_weights[i];
+                        preds[v] = Some(i);
+                    }
+                }
+            }
+            let improved_src = layer[src] < distance[k - 1][src];
+            distance.push(layer);
+            pred_edge_idx.push(preds);
+
+            if !improved_src || distance[k][src] + closing_weight >= 0.0 {
+                continue;
+            }
+            if let Some(path) = Self::simple_walk(k, src, dst, &pred_edge_idx, graph) {
+                let mut edge_indices = Vec::with_capacity(path.len() + 1);
+                edge_indices.push(closing_edge);
+                edge_indices.extend(path);
+                return Self::cycle_from_edge_indices(&edge_indices, graph).map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Traces the layered predecessors of `to` back to `from` (at most `k` edges),
+    /// returning the edge indices in forward order, or `None` if a node repeats.
+    fn simple_walk(
+        k: usize,
+        to: usize,
+        from: usize,
+        pred_edge_idx: &[Vec<Option<usize>>],
+        graph: &GraphCSR,
+    ) -> Option<Vec<usize>> {
+        let mut visited = vec![false; graph.num_nodes];
+        let mut edges = Vec::new();
+        let mut node = to;
+        let mut layer = k;
+        visited[node] = true;
+
+        while node != from {
+            // Skip rounds that left `node`'s walk unchanged.
+            while pred_edge_idx[layer][node].is_none() {
+                layer = layer.checked_sub(1)?;
+            }
+            let edge_idx = pred_edge_idx[layer][node]?;
+            node = graph.edge_source_by_index[edge_idx];
+            if visited[node] {
+                return None;
+            }
+            visited[node] = true;
+            edges.push(edge_idx);
+            layer -= 1;
+        }
+
+        edges.reverse();
+        Some(edges)
+    }
+}
+
+/// SPFA variant that schedules relaxations round-robin across SCCs.
+///
+/// See [`SPFASolver::find_profitable_cycle_fair`]. The `source` argument is only
+/// bounds-checked, since every node is seeded.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FairSPFASolver;
+
+impl GraphSolver for FairSPFASolver {
+    fn find_profitable_cycle(
+        &self,
+        graph: &GraphCSR,
+        source: usize,
+        hop_cap: usize,
+    ) -> Result<Option<WeightedCycle>, Error> {
+        if graph.num_nodes > 0 && source >= graph.num_nodes {
+            return Err(Error::NodeIndexOutOfBounds(source));
+        }
+
+        SPFASolver
+            .find_profitable_cycle_fair(graph, hop_cap)
+            .map(|(cycle, _)| cycle)
+    }
+}
+
+/// SPFA seeded from `source` alone instead of every node.
+///
+/// Only cycles reachable from `source` are found, but the search touches just that
+/// part of the graph. Rotating `source` across calls spreads detection over the graph;
+/// within a strongly connected component every node reaches every cycle, so any
+/// member is an equally good seed for it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SingleSourceSPFASolver;
+
+impl GraphSolver for SingleSourceSPFASolver {
+    fn find_profitable_cycle(
+        &self,
+        graph: &GraphCSR,
+        source: usize,
+        hop_cap: usize,
+    ) -> Result<Option<WeightedCycle>, Error> {
+        if graph.num_nodes == 0 {
+            return Err(Error::EmptyGraph);
+        }
+
+        SPFASolver.run_spfa(
+            graph,
+            Seeding::Source(source).seeds(graph.num_nodes)?,
+            hop_cap,
+            &mut SearchStats::default(),
+        )
+    }
+}
+
+/// Classic Bellman-Ford negative-cycle detection, used as a reference for SPFA.
+///
+/// Every node starts at distance 0 (a virtual source) and all edges are relaxed
+/// `|V|` times; a relaxation in the last pass proves a negative cycle, which is
+/// then traced back through the predecessors. Detection is exact and does not
+/// depend on `hop_cap`, at the cost of `O(V * E)` work. `source` is only
+/// bounds-checked, since every node is seeded.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BellmanFordSolver;
+
+impl GraphSolver for BellmanFordSolver {
+    fn find_profitable_cycle(
+        &self,
+        graph: &GraphCSR,
+        source: usize,
+        _hop_cap: usize,
+    ) -> Result<Option<WeightedCycle>, Error> {
+        let num_nodes = graph.num_nodes;
+        if num_nodes == 0 {
+            return Err(Error::EmptyGraph);
+        }
+        if source >= num_nodes {
+            return Err(Error::NodeIndexOutOfBounds(source));
+        }
+
+        let mut distance = vec![0.0f64; num_nodes];
+        let mut pred_edge_idx = vec![None; num_nodes];
+
+        let mut last_relaxed = None;
+        for _ in 0..num_nodes {
+            last_relaxed = None;
+            for u in 0..num_nodes {
+                for i in graph.node_pointers[u]..graph.node_pointers[u + 1] {
+                    let v = graph.edge_targets[i];
+                    let candidate = distance[u] + graph.edge_weights[i];
+                    if candidate < distance[v] {
+                        distance[v] = candidate;
+                        pred_edge_idx[v] = Some(i);
+                        last_relaxed = Some(v);
+                    }
+                }
+            }
+
+            if last_relaxed.is_none() {
+                return Ok(None);
+            }
+        }
+
+        // Still relaxing after |V| passes: `v` is on or downstream of a negative cycle.
+        match last_relaxed {
+            Some(v) => SPFASolver
+                .reconstruct_cycle(v, &pred_edge_idx, graph)
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Counters describing the work performed by a single SPFA search.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    /// Number of successful edge relaxations (distance improvements).
+    pub relaxations: usize,
+    /// Number of nodes popped from the work queue.
+    pub dequeues: usize,
+    /// True if a localized search had to fall back to a full-graph search.
+    pub fell_back: bool,
+}
+
+/// Reusable buffers for [`SPFASolver::reconstruct_cycle_with_scratch`].
+#[derive(Debug, Clone, Default)]
+pub struct ReconstructScratch {
+    /// Predecessor edge taken at each step of the trace.
+    walk: Vec<usize>,
+    /// One past the step each node was reached at; zero if not reached.
+    seen_at: Vec<usize>,
+    /// Nodes with a non-zero `seen_at`, reset at the start of the next call.
+    visited: Vec<usize>,
+}
+
+/// Final per-node arrays of an SPFA search, returned by
+/// [`SPFASolver::find_profitable_cycle_debug`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchState {
+    /// Shortest known distance (sum of `-ln` weights) from the virtual source.
+    pub distance: Vec<f64>,
+    /// Relaxations since the node's counter was last reset.
+    pub count: Vec<usize>,
+    /// CSR index of the edge that last relaxed each node.
+    pub pred_edge_idx: Vec<Option<usize>>,
+}
+
+/// Result of [`SPFASolver::find_profitable_cycle_or_certificate`].
+#[derive(Debug, Clone)]
+pub enum SearchOutcome {
+    Cycle(WeightedCycle),
+    NoArbitrage(NoArbitrageCertificate),
+}
+
+/// Proof that a graph has no profitable cycle: a potential per node such that
+/// `potentials[v] <= potentials[u] + weight` for every edge `u -> v`.
+///
+/// Summing the inequality around any cycle shows its weight sum is non-negative, i.e.
+/// no cycle has a product rate above 1. Consumers can check it with
+/// [`NoArbitrageCertificate::verify`] without trusting the solver.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoArbitrageCertificate {
+    pub potentials: Vec<f64>,
+}
+
+impl NoArbitrageCertificate {
+    /// Returns true if the potentials cover every node of `graph` and satisfy the
+    /// inequality on all of its edges.
+    pub fn verify(&self, graph: &GraphCSR) -> bool {
+        self.potentials.len() == graph.num_nodes
+            && (0..graph.num_nodes).all(|u| {
+                (graph.node_pointers[u]..graph.node_pointers[u + 1]).all(|i| {
+                    self.potentials[graph.edge_targets[i]]
+                        <= self.potentials[u] + graph.edge_weights[i]
+                })
+            })
+    }
+}
+
+/// How SPFA initializes distances before relaxing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Seeding {
+    /// Every node starts at 0 and is enqueued, as if a virtual zero-weight source
+    /// were connected to the whole graph. Detects negative cycles anywhere, but the
+    /// distances are not paths from any particular node.
+    AllZero,
+    /// Only the given node starts at 0; all others start at infinity. Distances are
+    /// shortest paths from that node and only cycles it reaches are detected.
+    Source(usize),
+}
+
+impl Seeding {
+    /// Nodes that start at distance 0, in enqueue order.
+    ///
+    /// # Errors
+    /// Returns `Error::NodeIndexOutOfBounds` if a `Seeding::Source` is not a node.
+    pub fn seeds(self, num_nodes: usize) -> Result<std::ops::Range<usize>, Error> {
+        match self {
+            Seeding::AllZero => Ok(0..num_nodes),
+            // Checked first, so `source + 1` cannot overflow.
+            Seeding::Source(source) if source >= num_nodes => {
+                Err(Error::NodeIndexOutOfBounds(source))
+            }
+            Seeding::Source(source) => Ok(source..source + 1),
+        }
+    }
+
+    /// Initial distance of every node.
+    ///
+    /// # Errors
+    /// Returns `Error::NodeIndexOutOfBounds` if a `Seeding::Source` is not a node.
+    pub fn initial_distances(self, num_nodes: usize) -> Result<Vec<f64>, Error> {
+        let seeds = self.seeds(num_nodes)?;
+        let mut distance = vec![f64::INFINITY; num_nodes];
+        for seed in seeds {
+            distance[seed] = 0.0;
+        }
+        Ok(distance)
+    }
+}
+
+impl GraphSolver for SPFASolver {
+    /// Finds the shortest path from `source` and detects the first reachable negative cycle (SPFA).
+    ///
+    /// # Parameters
+    /// - `graph`: The CSR data structure for fast edge traversal.
+    /// - `source`: Starting node ID.
+    /// - `hop_cap`: Max relaxations per node (typically N).
+    ///
+    /// # Returns
+    /// - `Ok(Some(cycle))` → Profitable cycle found.
+    /// - `Ok(None)` → No negative cycle found.
+    /// - `Err(Error::EmptyGraph)` → The graph has no nodes to search.
+    /// - `Err(Error::NodeIndexOutOfBounds(source))` → `source` is not a node of the graph.
+    fn find_profitable_cycle(
+        &self,
+        graph: &GraphCSR,
+        source: usize,
+        hop_cap: usize,
+    ) -> Result<Option<WeightedCycle>, Error> {
+        self.find_profitable_cycle_with_stats(graph, source, hop_cap)
+            .map(|(cycle, _)| cycle)
+    }
+}
+
+#[cfg(test)]
+mod spfa_tests {
+    use super::*;
+    use crate::test_fixtures;
+    use common::types::Edge;
+
+    fn build_graph(edges: &mut [Edge], num_nodes: usize) -> GraphCSR {
+        GraphCSR::from_edges(num_nodes, edges, edges.len())
+    }
+
+    #[test]
+    fn reconstruct_cycle_small_graph() {
+        let mut edges = vec![(0, 1, 1.0), (1, 2, 0.5), (2, 0, 0.5)];
+        let graph = build_graph(&mut edges, 3);
+
+        let pred_edge_idx = vec![Some(2), Some(0), Some(1)];
+        let solver = SPFASolver;
+        let cycle = solver.reconstruct_cycle(0, &pred_edge_idx, &graph).unwrap();
+
+        assert_eq!(cycle.path.len(), 3);
+        assert_eq!(cycle.path[0], (0, 1, 1.0));
+    }
+
+    #[test]
+    fn spfa_detects_simple_negative_cycle() {
+        let mut edges = vec![(1, 0, 2.0), (0, 1, 2.0)];
+        let graph = build_graph(&mut edges, 2);
+
+        let solver = SPFASolver;
+
+        let cycle = solver.find_profitable_cycle(&graph, 0, 2).unwrap();
+        assert!(cycle.is_some());
+
+        let cycle = cycle.unwrap();
+        assert_eq!(cycle.path, vec![(1, 0, 2.0), (0, 1, 2.0)]);
+        assert!(cycle.log_rate_sum < 0.0);
+    }
+
+    #[test]
+    fn spfa_recovers_from_self_loop_false_alarm() {
+        // Node 0's relaxation count hits the cap while its predecessor is `1 -> 0`,
+        // whose chain dead-ends at node 2; the real cycle is the self-loop.
+        let mut edges = vec![(0, 0, 1.009), (2, 1, 1.019), (1, 0, 1.019)];
+        let mut graph = GraphCSR::from_edges(3, &mut [], 1);
+        graph
+            .rebuild_with_edges(std::mem::take(&mut edges))
+            .unwrap();
+
+        let cycle = SPFASolver
+            .find_profitable_cycle(&graph, 0, 4)
+            .unwrap()
+            .expect("self-loop is profitable");
+        assert_eq!(cycle.path, vec![(0, 0, 1.009)]);
+    }
+
+    #[test]
+    fn bellman_ford_matches_spfa_on_small_graphs() {
+        let graph = test_fixtures::simple_triangle_profit();
+        let cycle = BellmanFordSolver
+            .find_profitable_cycle(&graph, 0, 4)
+            .unwrap()
+            .expect("cycle expected");
+        assert_eq!(cycle.canonical_key(), vec![0, 1, 2]);
+        assert!(cycle.log_rate_sum < 0.0);
+
+        assert!(
+            BellmanFordSolver
+                .find_profitable_cycle(&test_fixtures::break_even_loop(), 0, 3)
+                .unwrap()
+                .is_none()
+        );
+
+        let mut edges = vec![(0, 1, 1.0), (1, 2, 1.2), (2, 0, 0.5)];
+        let graph = build_graph(&mut edges, 3);
+        assert!(
+            BellmanFordSolver
+                .find_profitable_cycle(&graph, 0, 4)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn cycle_through_edge_is_found_only_for_edges_on_a_profitable_cycle() {
+        // Profitable triangle 0 -> 1 -> 2 -> 0 (product 1.1); 2 -> 3 -> 2 loses money.
+        let mut edges = vec![
+            (0, 1, 1.0),
+            (1, 2, 1.0),
+            (2, 0, 1.1),
+            (2, 3, 0.9),
+            (3, 2, 1.0),
+        ];
+        let graph = build_graph(&mut edges, 4);
+
+        let cycle = SPFASolver
+            .find_cycle_through_edge(&graph, 1, 2, 5)
+            .unwrap()
+            .expect("1 -> 2 lies on the profitable triangle");
+        assert_eq!(cycle.nodes(), vec![1, 2, 0]);
+        assert!(cycle.is_valid());
+        assert!(cycle.log_rate_sum < 0.0);
+
+        assert!(
+            SPFASolver
+                .find_cycle_through_edge(&graph, 2, 3, 5)
+                .unwrap()
+                .is_none()
+        );
+        // No such edge.
+        assert!(
+            SPFASolver
+                .find_cycle_through_edge(&graph, 0, 2, 5)
+                .unwrap()
+                .is_none()
+        );
+        // Too few hops allowed to close the triangle.
+        assert!(
+            SPFASolver
+                .find_cycle_through_edge(&graph, 1, 2, 2)
+                .unwrap()
+                .is_none()
+        );
+        assert!(matches!(
+            SPFASolver.find_cycle_through_edge(&graph, 0, 9, 5),
+            Err(Error::NodeIndexOutOfBounds(9))
+        ));
+    }
+
+    #[test]
+    fn debug_search_exposes_counts_at_detection() {
+        let mut edges = vec![(0, 1, 1.0), (1, 2, 1.0), (2, 0, 1.1), (2, 3, 0.9)];
+        let graph = build_graph(&mut edges, 4);
+        let hop_cap = graph.num_nodes + 1;
+
+        let (cycle, state) = SPFASolver
+            .find_profitable_cycle_debug(&graph, 0, hop_cap)
+            .unwrap();
+        let cycle = cycle.expect("Cycle expected");
+
+        assert_eq!(state.distance.len(), 4);
+        assert_eq!(state.pred_edge_idx.len(), 4);
+        assert!(
+            cycle
+                .nodes()
+                .iter()
+                .any(|&node| state.count[node] >= hop_cap),
+            "counts: {:?}",
+            state.count
+        );
+        let plain = SPFASolver
+            .find_profitable_cycle(&graph, 0, hop_cap)
+            .unwrap()
+            .expect("Cycle expected");
+        assert_eq!(cycle.path, plain.path);
+    }
+
+    #[test]
+    fn split_and_packed_layouts_give_identical_results() {
+        for seed in 0..20 {
+            let graph = GraphCSR::random(30, 120, 0.9..1.1, seed);
+            let hop_cap = graph.num_nodes + 1;
+
+            let (split_cycle, split_stats) = SPFASolver
+                .find_profitable_cycle_with_layout(&graph, 0, hop_cap, EdgeLayout::Split)
+                .unwrap();
+            let (packed_cycle, packed_stats) = SPFASolver
+                .find_profitable_cycle_with_layout(&graph, 0, hop_cap, EdgeLayout::Packed)
+                .unwrap();
+
+            assert_eq!(split_stats, packed_stats, "seed {seed}");
+            assert_eq!(
+                split_cycle.map(|cycle| cycle.path),
+                packed_cycle.map(|cycle| cycle.path),
+                "seed {seed}"
+            );
+        }
+    }
+
+    #[test]
+    fn single_source_search_only_finds_reachable_cycles() {
+        // 0 -> 1 feeds the loop 1 <-> 2; node 3 is a dead end.
+        let mut edges = vec![(0, 1, 1.0), (1, 2, 1.0), (2, 1, 1.1), (3, 0, 1.0)];
+        let graph = build_graph(&mut edges, 4);
+        let hop_cap = graph.num_nodes + 1;
+
+        for source in [0, 1, 2, 3] {
+            let cycle = SingleSourceSPFASolver
+                .find_profitable_cycle(&graph, source, hop_cap)
+                .unwrap()
+                .expect("Loop reachable from every node");
+            assert_eq!(cycle.canonical_key(), vec![1, 2]);
+        }
+
+        let mut edges = vec![(0, 1, 1.0), (1, 2, 1.0), (2, 1, 1.1)];
+        let graph = build_graph(&mut edges, 4);
+        assert!(
+            SingleSourceSPFASolver
+                .find_profitable_cycle(&graph, 3, hop_cap)
+                .unwrap()
+                .is_none()
+        );
+        assert!(matches!(
+            SingleSourceSPFASolver.find_profitable_cycle(&graph, 4, hop_cap),
+            Err(Error::NodeIndexOutOfBounds(4))
+        ));
+    }
+
+    #[test]
+    fn best_cycle_prefers_deeper_liquidity_on_equal_profit() {
+        // Two disjoint loops with identical rates; 2 -> 3 -> 2 has the deeper bottleneck.
+        let mut edges = vec![(0, 1, 1.1), (1, 0, 1.0), (2, 3, 1.1), (3, 2, 1.0)];
+        let graph = build_graph(&mut edges, 4);
+        let liquidity = |u: usize, _v: usize| match u {
+            0 => 500.0,
+            1 => 50.0,
+            _ => 100.0,
+        };
+
+        let cycle = SPFASolver
+            .find_best_cycle(&graph, liquidity)
+            .unwrap()
+            .expect("Expected a profitable cycle");
+        let mut nodes = cycle.nodes();
+        nodes.sort_unstable();
+        assert_eq!(nodes, vec![2, 3]);
+
+        // A strictly better rate still wins over liquidity.
+        let mut edges = vec![(0, 1, 1.2), (1, 0, 1.0), (2, 3, 1.1), (3, 2, 1.0)];
+        let graph = build_graph(&mut edges, 4);
+        let cycle = SPFASolver
+            .find_best_cycle(&graph, liquidity)
+            .unwrap()
+            .unwrap();
+        let mut nodes = cycle.nodes();
+        nodes.sort_unstable();
+        assert_eq!(nodes, vec![0, 1]);
+
+        // A rounding-level edge in rate does not override deeper liquidity.
+        let mut edges = vec![(0, 1, 1.1 + 1e-13), (1, 0, 1.0), (2, 3, 1.1), (3, 2, 1.0)];
+        let graph = build_graph(&mut edges, 4);
+        let cycle = SPFASolver
+            .find_best_cycle(&graph, liquidity)
+            .unwrap()
+            .unwrap();
+        let mut nodes = cycle.nodes();
+        nodes.sort_unstable();
+        assert_eq!(nodes, vec![2, 3]);
+    }
+
+    #[test]
+    fn cycles_through_a_thin_edge_are_dropped_below_min_liquidity() {
+        // 0 <-> 1 (+10%) runs through a thin 0 -> 1 pool; 2 <-> 3 (+5%) is deep throughout.
+        let mut edges = vec![(0, 1, 1.1), (1, 0, 1.0), (2, 3, 1.05), (3, 2, 1.0)];
+        let graph = build_graph(&mut edges, 4);
+        let liquidity = |u: usize, v: usize| if (u, v) == (0, 1) { 5.0 } else { 1_000.0 };
+
+        let all = SPFASolver
+            .find_all_negative_cycles_with_min_liquidity(&graph, liquidity, 0.0)
+            .unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].canonical_key(), vec![0, 1]);
+
+        let liquid = SPFASolver
+            .find_all_negative_cycles_with_min_liquidity(&graph, liquidity, 100.0)
+            .unwrap();
+        assert_eq!(liquid.len(), 1);
+        assert_eq!(liquid[0].canonical_key(), vec![2, 3]);
+        assert_eq!(graph.edge_weights, build_graph(&mut edges, 4).edge_weights);
+    }
+
+    #[test]
+    fn all_zero_seeding_relaxes_every_edge_in_one_round() {
+        // 0 -> 1 (2.0), 1 -> 2 (0.5), 2 -> 0 (1.5): weights -ln2, ln2, -ln1.5.
+        let mut edges = vec![(0, 1, 2.0), (1, 2, 0.5), (2, 0, 1.5)];
+        let graph = build_graph(&mut edges, 4);
+
+        assert_eq!(Seeding::AllZero.initial_distances(4).unwrap(), vec![0.0; 4]);
+        let distance = SPFASolver.relax_round(&graph, Seeding::AllZero).unwrap();
+        assert!((distance[0] + 1.5f64.ln()).abs() < 1e-12);
+        assert!((distance[1] + 2.0f64.ln()).abs() < 1e-12);
+        // A positive-weight edge cannot improve on the seed distance of 0.
+        assert_eq!(distance[2], 0.0);
+        assert_eq!(distance[3], 0.0);
+    }
+
+    #[test]
+    fn source_seeding_only_reaches_direct_neighbours_in_one_round() {
+        let mut edges = vec![(0, 1, 2.0), (1, 2, 0.5), (2, 0, 1.5), (0, 3, 1.0)];
+        let graph = build_graph(&mut edges, 4);
+
+        let initial = Seeding::Source(0).initial_distances(4).unwrap();
+        assert_eq!(
+            initial,
+            vec![0.0, f64::INFINITY, f64::INFINITY, f64::INFINITY]
+        );
+
+        let distance = SPFASolver.relax_round(&graph, Seeding::Source(0)).unwrap();
+        assert_eq!(distance[0], 0.0);
+        assert!((distance[1] + 2.0f64.ln()).abs() < 1e-12);
+        assert_eq!(distance[2], f64::INFINITY);
+        assert_eq!(distance[3], 0.0);
+
+        assert!(matches!(
+            SPFASolver.relax_round(&graph, Seeding::Source(7)),
+            Err(Error::NodeIndexOutOfBounds(7))
+        ));
+        assert!(matches!(
+            Seeding::Source(usize::MAX).seeds(4),
+            Err(Error::NodeIndexOutOfBounds(usize::MAX))
+        ));
+        assert!(matches!(
+            SingleSourceSPFASolver.find_profitable_cycle(&graph, usize::MAX, 4),
+            Err(Error::NodeIndexOutOfBounds(usize::MAX))
+        ));
+    }
+
+    #[test]
+    fn best_rate_follows_the_best_path() {
+        // 0 -> 3 directly at 1.5, or 0 -> 1 -> 2 -> 3 at 1.2 * 1.1 * 1.3 = 1.716.
+        let mut edges = vec![
+            (0, 1, 1.2),
+            (1, 2, 1.1),
+            (2, 3, 1.3),
+            (0, 3, 1.5),
+            (1, 3, 1.4),
+            (4, 0, 2.0),
+        ];
+        let graph = build_graph(&mut edges, 5);
+        let solver = SPFASolver;
+
+        let rate = solver.best_rate(&graph, 0, 3).unwrap().unwrap();
+        assert!((rate - 1.2 * 1.1 * 1.3).abs() < 1e-12);
+        assert_eq!(solver.best_rate(&graph, 2, 2).unwrap(), Some(1.0));
+        assert_eq!(solver.best_rate(&graph, 3, 0).unwrap(), None);
+        assert!(matches!(
+            solver.best_rate(&graph, 0, 9),
+            Err(Error::NodeIndexOutOfBounds(9))
+        ));
+
+        let mut edges = vec![(0, 1, 1.0), (1, 2, 1.1), (2, 1, 1.0)];
+        let graph = build_graph(&mut edges, 3);
+        assert!(matches!(
+            solver.best_rate(&graph, 0, 2),
+            Err(Error::UnboundedRate)
+        ));
+    }
+
+    #[test]
+    fn loss_cycle_is_reported_separately_from_profitable_ones() {
+        let mut edges: Vec<Edge> = vec![
+            (0, 1, 0.5),
+            (1, 0, 2.1),
+            (1, 2, 1.0),
+            (2, 3, 0.8),
+            (3, 2, 0.7),
+        ];
+        let graph = build_graph(&mut edges, 4);
+
+        let losses = SPFASolver.find_loss_cycles(&graph).unwrap();
+        assert_eq!(losses.len(), 1);
+        let loss = &losses[0];
+        assert_eq!(loss.path, vec![(2, 3, 0.8), (3, 2, 0.7)]);
+        assert!((loss.product_rate() - 0.56).abs() < 1e-9);
+        assert!(!loss.is_profitable());
+
+        let profitable = SPFASolver.find_all_negative_cycles(&graph).unwrap();
+        assert_eq!(profitable.len(), 1);
+        assert_eq!(profitable[0].nodes(), vec![0, 1]);
+    }
+
+    #[test]
+    fn no_cycle_search_returns_a_feasible_potential() {
+        let mut edges: Vec<Edge> = vec![
+            (0, 1, 1.1),
+            (1, 2, 0.95),
+            (2, 0, 0.9),
+            (2, 3, 2.0),
+            (3, 1, 0.4),
+        ];
+        let graph = build_graph(&mut edges, 4);
+
+        let SearchOutcome::NoArbitrage(certificate) = SPFASolver
+            .find_profitable_cycle_or_certificate(&graph, 0, 5)
+            .unwrap()
+        else {
+            panic!("Expected a no-arbitrage certificate");
+        };
+
+        let potential = &certificate.potentials;
+        for u in 0..graph.num_nodes {
+            for i in graph.node_pointers[u]..graph.node_pointers[u + 1] {
+                let v = graph.edge_targets[i];
+                assert!(potential[v] <= potential[u] + graph.edge_weights[i]);
+            }
+        }
+        assert!(certificate.verify(&graph));
+
+        // A profitable loop yields the cycle, and no potential can satisfy it.
+        let mut edges: Vec<Edge> = vec![(0, 1, 1.1), (1, 0, 0.95)];
+        let graph = build_graph(&mut edges, 2);
+        let outcome = SPFASolver
+            .find_profitable_cycle_or_certificate(&graph, 0, 3)
+            .unwrap();
+        assert!(matches!(outcome, SearchOutcome::Cycle(_)));
+        let zeros = NoArbitrageCertificate {
+            potentials: vec![0.0; 2],
+        };
+        assert!(!zeros.verify(&graph));
+    }
+
+    #[test]
+    fn spfa_no_negative_cycle_returns_none() {
+        let mut edges = vec![(0, 1, 1.0), (1, 2, 1.2), (2, 3, 1.2)];
+        let graph = build_graph(&mut edges, 4);
+        let solver = SPFASolver;
+
+        let cycle = solver.find_profitable_cycle(&graph, 0, 4).unwrap();
+        assert!(cycle.is_none());
+
+        // A zero-weight loop is not profitable either.
+        let cycle = solver
+            .find_profitable_cycle(&test_fixtures::break_even_loop(), 0, 3)
+            .unwrap();
+        assert!(cycle.is_none());
+    }
+
+    #[test]
+    fn spfa_ignores_cycle_unprofitable_after_fee() {
+        // Gross product: 1.002 * 1.0 = 1.002 (0.2% profit)
+        let mut edges = vec![(0, 1, 1.002), (1, 0, 1.0)];
+        let solver = SPFASolver;
+
+        let gross = GraphCSR::from_edges(2, &mut edges.clone(), 2);
+        assert!(
+            solver
+                .find_profitable_cycle(&gross, 0, 3)
+                .unwrap()
+                .is_some()
+        );
+
+        // Net product with 30 bps per hop: 1.002 * 0.997^2 ≈ 0.996
+        let net = GraphCSR::from_edges_with_fee(2, &mut edges, 2, 30.0);
+        assert!(solver.find_profitable_cycle(&net, 0, 3).unwrap().is_none());
+    }
+
+    #[test]
+    fn per_edge_fees_change_which_cycle_is_profitable() {
+        // Gross: loop A (0 <-> 1) = 1.01, loop B (2 <-> 3) = 1.005.
+        let mut edges = vec![(0, 1, 1.01), (1, 0, 1.0), (2, 3, 1.005), (3, 2, 1.0)];
+        let mut graph = GraphCSR::from_edges(4, &mut edges, 10);
+        let keys = |graph: &GraphCSR| -> Vec<Vec<usize>> {
+            SPFASolver
+                .find_all_negative_cycles(graph)
+                .unwrap()
+                .iter()
+                .map(WeightedCycle::canonical_key)
+                .collect()
+        };
+        assert_eq!(keys(&graph), vec![vec![0, 1], vec![2, 3]]);
+
+        // A's venue charges 150 bps (1.01 * 0.985 < 1), B's only 10 bps (1.005 * 0.999 > 1).
+        graph.set_edge_fee_bps(0, 1, 150.0).unwrap();
+        graph.set_edge_fee_bps(2, 3, 10.0).unwrap();
+        assert_eq!(keys(&graph), vec![vec![2, 3]]);
+
+        // Overrides survive rebuilds and gross rates round-trip.
+        graph.rebuild_with_edges(vec![(1, 0, 1.0)]).unwrap();
+        assert_eq!(keys(&graph), vec![vec![2, 3]]);
+        let rate_01 = graph
+            .to_edges()
+            .into_iter()
+            .find(|e| (e.0, e.1) == (0, 1))
+            .unwrap()
+            .2;
+        assert!((rate_01 - 1.01).abs() < 1e-12);
+    }
+
+    #[test]
+    fn near_search_finds_local_cycle_with_fewer_relaxations() {
+        let n = 2000;
+        // A long profitable-looking (but acyclic) chain that a full scan must relax.
+        let mut edges: Vec<Edge> = (0..n - 1).map(|i| (i, i + 1, 1.01)).collect();
+        // The region that just changed: a profitable triangle on fresh nodes.
+        edges.extend([(n, n + 1, 1.0), (n + 1, n + 2, 1.0), (n + 2, n, 1.05)]);
+
+        let graph = build_graph(&mut edges, n + 3);
+        let hop_cap = graph.num_nodes + 1;
+        let solver = SPFASolver;
+
+        let (full_cycle, full_stats) = solver
+            .find_profitable_cycle_with_stats(&graph, 0, hop_cap)
+            .unwrap();
+        let (near_cycle, near_stats) = solver.find_cycle_near(&graph, &[n + 2], hop_cap).unwrap();
+
+        let near_cycle = near_cycle.expect("Near search should find the cycle");
+        assert_eq!(near_cycle.path.len(), 3);
+        assert_eq!(
+            near_cycle.canonical_key(),
+            full_cycle.unwrap().canonical_key()
+        );
+        assert!(!near_stats.fell_back);
+        assert!(near_stats.relaxations < full_stats.relaxations);
+    }
+
+    #[test]
+    fn near_search_falls_back_to_full_scan() {
+        let mut edges = vec![(0, 1, 1.0), (1, 0, 1.1), (2, 3, 1.0)];
+        let graph = build_graph(&mut edges, 4);
+        let solver = SPFASolver;
+
+        // Node 3 cannot reach the profitable loop, so only the fallback finds it.
+        let (cycle, stats) = solver.find_cycle_near(&graph, &[3], 5).unwrap();
+
+        assert!(cycle.is_some());
+        assert!(stats.fell_back);
+    }
+
+    /// A large, busy but unprofitable component next to a tiny profitable one.
+    ///
+    /// The large component is a ring of `-1` edges (running against the FIFO seeding order)
+    /// closed by one heavy edge, so it has no
+    /// negative cycle but keeps improving distances for ~`k` rounds of `k` relaxations each.
+    fn busy_component_fixture(k: usize) -> Vec<Edge> {
+        let mut edges: Vec<Edge> = (0..k - 1).map(|i| (i + 1, i, 1f64.exp())).collect();
+        edges.push((0, k - 1, (-(k as f64 + 1.0)).exp()));
+        edges.extend([(k, k + 1, 1.0), (k + 1, k, 1.1)]);
+        edges
+    }
+
+    #[test]
+    fn fair_search_finds_small_component_cycle_within_bound() {
+        let k = 200;
+        let mut edges = busy_component_fixture(k);
+        let graph = build_graph(&mut edges, k + 2);
+        let hop_cap = graph.num_nodes + 1;
+        let solver = SPFASolver;
+
+        let (fair_cycle, fair_stats) = solver.find_profitable_cycle_fair(&graph, hop_cap).unwrap();
+        let (_, full_stats) = solver
+            .find_profitable_cycle_with_stats(&graph, 0, hop_cap)
+            .unwrap();
+
+        let nodes = fair_cycle
+            .expect("Fair search should find the cycle")
+            .nodes();
+        assert!(nodes.contains(&k) && nodes.contains(&(k + 1)));
+
+        // The 2-node cycle needs ~2 * hop_cap relaxations; round-robin gives the large
+        // component at most the same share, whereas FIFO order makes it wait ~k^2/2.
+        assert!(fair_stats.relaxations <= 4 * hop_cap);
+        assert!(fair_stats.relaxations * 10 < full_stats.relaxations);
+    }
+
+    #[test]
+    fn fair_solver_matches_standard_on_disconnected_fixture() {
+        let graph = test_fixtures::disconnected_two_components();
+
+        let cycle = FairSPFASolver
+            .find_profitable_cycle(&graph, 0, 6)
+            .unwrap()
+            .expect("Cycle expected");
+        assert_eq!(cycle.canonical_key(), vec![3, 4]);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_all_cycles_matches_sequential() {
+        let mut edges = multi_cycle_fixture();
+        let graph = build_graph(&mut edges, 32);
+        let solver = SPFASolver;
+
+        let sequential = solver.find_all_negative_cycles(&graph).unwrap();
+        let parallel = solver.find_all_negative_cycles_par(&graph).unwrap();
+
+        let keys = |cycles: &[WeightedCycle]| -> Vec<(Vec<usize>, f64)> {
+            cycles
+                .iter()
+                .map(|c| (c.canonical_key(), c.log_rate_sum))
+                .collect()
+        };
+        assert_eq!(keys(&parallel), keys(&sequential));
+        assert_eq!(sequential.len(), 12);
+        assert!(sequential.iter().all(|c| c.is_profitable()));
+        assert!(
+            sequential
+                .windows(2)
+                .all(|w| w[0].log_rate_sum <= w[1].log_rate_sum)
+        );
+    }
+
+    #[test]
+    fn capped_enumeration_stops_at_max_cycles() {
+        let mut edges = multi_cycle_fixture();
+        let graph = build_graph(&mut edges, 32);
+        let solver = SPFASolver;
+
+        let (cycles, truncated) = solver.find_all_negative_cycles_capped(&graph, 3).unwrap();
+        assert_eq!(cycles.len(), 3);
+        assert!(truncated);
+        assert!(cycles.iter().all(|c| c.is_profitable()));
+
+        // A cap at or above the total keeps everything and is not truncated.
+        let (cycles, truncated) = solver.find_all_negative_cycles_capped(&graph, 12).unwrap();
+        assert_eq!(cycles.len(), 12);
+        assert!(!truncated);
+        let (cycles, truncated) = solver.find_all_negative_cycles_capped(&graph, 0).unwrap();
+        assert!(cycles.is_empty());
+        assert!(truncated);
+    }
+
+    #[test]
+    fn arbitrage_edge_set_covers_exactly_the_cycle_edges() {
+        let mut edges = multi_cycle_fixture();
+        let graph = build_graph(&mut edges, 32);
+
+        let hot = SPFASolver
+            .arbitrage_edge_set(&graph, graph.num_nodes + 1)
+            .unwrap();
+
+        let index = |u: usize, v: usize| {
+            (graph.node_pointers[u]..graph.node_pointers[u + 1])
+                .find(|&i| graph.edge_targets[i] == v)
+                .unwrap()
+        };
+        let mut expected = HashSet::new();
+        for c in (0..8).filter(|&c| c != 3) {
+            let base = c * 4;
+            // The triangle only pays when boosted (c % 3 != 0); the 2-cycle always does.
+            if c % 3 != 0 {
+                expected.extend([
+                    index(base, base + 1),
+                    index(base + 1, base + 2),
+                    index(base + 2, base),
+                ]);
+            }
+            expected.extend([index(base, base + 3), index(base + 3, base)]);
+        }
+        assert_eq!(hot, expected);
+
+        let mut flat = vec![(0, 1, 0.9), (1, 0, 1.1)];
+        assert!(
+            SPFASolver
+                .arbitrage_edge_set(&build_graph(&mut flat, 2), 3)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    /// Eight 4-node components, each with two loops through node `base` (component 3
+    /// has no profitable one), joined by one-way bridges. Profitable loops: 12.
+    fn multi_cycle_fixture() -> Vec<Edge> {
+        let mut edges: Vec<Edge> = Vec::new();
+        for c in 0..8 {
+            let base = c * 4;
+            let boost = 1.0 + 0.01 * (c % 3) as f64;
+            // Two loops per component sharing node `base`; component 3 has none.
+            let (a, b) = if c == 3 { (0.9, 0.9) } else { (boost, 1.02) };
+            edges.extend([
+                (base, base + 1, a),
+                (base + 1, base + 2, 1.0),
+                (base + 2, base, 1.0),
+                (base, base + 3, b),
+                (base + 3, base, 1.0),
+            ]);
+            // One-way bridges between components cannot carry cycles.
+            if c < 7 {
+                edges.push((base + 1, base + 4, 5.0));
+            }
+        }
+        edges
+    }
+
+    #[test]
+    fn single_pass_reconstruction_matches_two_pass_from_every_node() {
+        let mut fixtures = vec![build_graph(&mut multi_cycle_fixture(), 32)];
+        for seed in 1..=5 {
+            fixtures.push(GraphCSR::random(200, 1_500, 0.9..1.15, seed));
+        }
+        // A 50-node ring entered through a 30-node tail, with one unreachable node.
+        let mut ring: Vec<Edge> = (0..50).map(|i| (i, (i + 1) % 50, 1.001)).collect();
+        ring.extend((50..80).map(|i| (i, i + 1, 1.0)));
+        ring.push((49, 50, 1.0));
+        fixtures.push(build_graph(&mut ring, 82));
+
+        let mut reconstructed = 0;
+        for graph in &fixtures {
+            let (_, state) = SPFASolver
+                .find_profitable_cycle_debug(graph, 0, graph.num_nodes + 1)
+                .unwrap();
+            for v in 0..graph.num_nodes {
+                let single = SPFASolver.reconstruct_cycle(v, &state.pred_edge_idx, graph);
+                let two_pass =
+                    SPFASolver.reconstruct_cycle_two_pass(v, &state.pred_edge_idx, graph);
+                match (single, two_pass) {
+                    (Ok(a), Ok(b)) => {
+                        assert_eq!(a.path, b.path);
+                        assert_eq!(a.log_rate_sum, b.log_rate_sum);
+                        reconstructed += 1;
+                    }
+                    (Err(a), Err(b)) => assert_eq!(a.to_string(), b.to_string()),
+                    (a, b) => panic!("node {}: {:?} vs {:?}", v, a.is_ok(), b.is_ok()),
+                }
+            }
+        }
+        assert!(reconstructed > 0);
+    }
+
+    #[test]
+    fn shortest_profitable_cycle_prefers_fewer_hops() {
+        let mut edges: Vec<Edge> = vec![
+            // 2-hop cycle, 1% profit
+            (0, 1, 1.01),
+            (1, 0, 1.0),
+            // 4-hop cycle, 20% profit
+            (2, 3, 1.2),
+            (3, 4, 1.0),
+            (4, 5, 1.0),
+            (5, 2, 1.0),
+        ];
+        let graph = build_graph(&mut edges, 6);
+
+        let cycle = SPFASolver
+            .find_shortest_profitable_cycle(&graph, 7)
+            .unwrap()
+            .expect("Cycle expected");
+
+        assert_eq!(cycle.canonical_key(), vec![0, 1]);
+        assert!(
+            SPFASolver
+                .find_shortest_profitable_cycle(&GraphCSR::from_edges(0, &mut [], 1), 1)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn spfa_single_node_graph() {
+        let graph = build_graph(&mut [], 1);
+        let solver = SPFASolver;
+
+        let cycle = solver.find_profitable_cycle(&graph, 0, 1).unwrap();
+        assert!(cycle.is_none());
+    }
+
+    #[test]
+    fn spfa_empty_graph_returns_error() {
+        let graph = build_graph(&mut [], 0);
+        let solver = SPFASolver;
+
+        let result = solver.find_profitable_cycle(&graph, 0, 1);
+        assert!(matches!(result, Err(Error::EmptyGraph)));
+
+        let result = solver.find_cycle_near(&graph, &[], 1);
+        assert!(matches!(result, Err(Error::EmptyGraph)));
+    }
+
+    // ----------------------------
+    // Stress and edge-case tests
+    // ----------------------------
+
+    #[test]
+    fn spfa_out_of_bounds_source_returns_specific_error() {
+        let graph = build_graph(&mut [(0, 1, 1.0)], 2);
+        let solver = SPFASolver;
+
+        let result = solver.find_profitable_cycle(&graph, 7, 2);
+        assert!(matches!(result, Err(Error::NodeIndexOutOfBounds(7))));
+    }
+
+    #[test]
+    fn stats_search_on_empty_graph_returns_empty_graph_error() {
+        let graph = build_graph(&mut [], 0);
+        let solver = SPFASolver;
+
+        let result = solver.find_profitable_cycle_with_stats(&graph, 0, 1);
+        assert!(matches!(result, Err(Error::EmptyGraph)));
+    }
+
+    #[test]
+    fn spfa_large_linear_graph_no_cycle() {
+        let n = 1000;
+        let mut edges: Vec<Edge> = (0..n - 1).map(|i| (i, i + 1, 1.0)).collect();
+        let graph = build_graph(&mut edges, n);
+        let solver = SPFASolver;
+
+        let cycle = solver.find_profitable_cycle(&graph, 0, n).unwrap();
+        assert!(cycle.is_none());
+    }
+
+    #[test]
+    fn spfa_large_circular_graph_negative_cycle() {
+        let n = 1000;
+        let graph = test_fixtures::large_circular(n);
+
+        let solver = SPFASolver;
+        let cycle = solver.find_profitable_cycle(&graph, 0, n + 1).unwrap();
+        assert!(cycle.is_some());
+        let cycle = cycle.unwrap();
+        assert!(cycle.log_rate_sum < 0.0);
+        assert!(cycle.path.len() <= n);
+    }
+
+    #[test]
+    fn scratch_reconstruction_matches_allocating_version() {
+        let mut edges: Vec<Edge> = vec![(0, 1, 1.0), (1, 2, 1.1), (2, 3, 1.0), (3, 1, 1.0)];
+        let graph = build_graph(&mut edges, 4);
+        // Predecessors as SPFA would leave them around the 1 -> 2 -> 3 -> 1 loop.
+        let pred: Vec<Option<usize>> = vec![None, Some(3), Some(1), Some(2)];
+        let solver = SPFASolver;
+
+        let expected = solver.reconstruct_cycle(2, &pred, &graph).unwrap();
+
+        let mut scratch = ReconstructScratch::default();
+        for _ in 0..2 {
+            let cycle = solver
+                .reconstruct_cycle_with_scratch(2, &pred, &graph, &mut scratch)
+                .unwrap();
+            assert_eq!(cycle.path, expected.path);
+            assert_eq!(cycle.rates, expected.rates);
+            assert_eq!(cycle.log_rate_sum, expected.log_rate_sum);
+        }
+        assert_eq!(scratch.walk.len(), 3);
+        assert_eq!(scratch.seen_at.len(), 4);
+
+        // A broken chain leaves marks behind; the next call still starts clean.
+        let broken: Vec<Option<usize>> = vec![None, None, Some(1), Some(2)];
+        assert!(matches!(
+            solver.reconstruct_cycle_with_scratch(3, &broken, &graph, &mut scratch),
+            Err(Error::CycleReconstructionFailed)
+        ));
+        let cycle = solver
+            .reconstruct_cycle_with_scratch(2, &pred, &graph, &mut scratch)
+            .unwrap();
+        assert_eq!(cycle.path, expected.path);
+    }
+
+    #[test]
+    fn spfa_detects_arbitrage_in_disconnected_component() {
+        // Source 0 sits in a losing triangle; the profitable 3 <-> 4 pair is unreachable.
+        let graph = test_fixtures::disconnected_two_components();
 
         let solver = SPFASolver;
 
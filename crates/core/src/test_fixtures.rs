@@ -0,0 +1,78 @@
+//! Named graphs with a known cycle structure, shared by the core tests.
+//!
+//! Each fixture documents the profitable cycles it contains; `fixtures_have_their_documented_cycles`
+//! checks those claims so tests can rely on them without re-deriving the arithmetic.
+
+use common::types::Edge;
+
+use crate::csr::GraphCSR;
+
+fn build(mut edges: Vec<Edge>, num_nodes: usize) -> GraphCSR {
+    let limit = edges.len();
+    GraphCSR::from_edges(num_nodes, &mut edges, limit)
+}
+
+/// `0 -> 1 -> 2 -> 0` with a product of 1.1: exactly one profitable cycle, over all
+/// three nodes.
+pub fn simple_triangle_profit() -> GraphCSR {
+    build(vec![(0, 1, 1.1), (1, 2, 1.0), (2, 0, 1.0)], 3)
+}
+
+/// A losing triangle `0 -> 1 -> 2 -> 0` (product 0.25) next to a profitable pair
+/// `3 <-> 4` (product 1.1) it cannot reach: the only profitable cycle is `[3, 4]`.
+pub fn disconnected_two_components() -> GraphCSR {
+    build(
+        vec![
+            (0, 1, 1.0),
+            (1, 2, 0.5),
+            (2, 0, 0.5),
+            (3, 4, 1.0),
+            (4, 3, 1.1),
+        ],
+        5,
+    )
+}
+
+/// `0 <-> 1` with a product of exactly 1.0: a zero-weight loop, so no profitable cycle.
+pub fn break_even_loop() -> GraphCSR {
+    build(vec![(0, 1, 2.0), (1, 0, 0.5)], 2)
+}
+
+/// A ring `0 -> 1 -> ... -> n-1 -> 0` at 1.001 per hop: one profitable cycle over all
+/// `n` nodes.
+pub fn large_circular(n: usize) -> GraphCSR {
+    build((0..n).map(|i| (i, (i + 1) % n, 1.001)).collect(), n)
+}
+
+#[test]
+fn fixtures_have_their_documented_cycles() {
+    use crate::solver::SPFASolver;
+
+    let cycles = SPFASolver
+        .find_all_negative_cycles(&simple_triangle_profit())
+        .unwrap();
+    assert_eq!(cycles.len(), 1);
+    assert_eq!(cycles[0].canonical_key(), vec![0, 1, 2]);
+    assert!((cycles[0].product_rate() - 1.1).abs() < 1e-9);
+
+    let cycles = SPFASolver
+        .find_all_negative_cycles(&disconnected_two_components())
+        .unwrap();
+    assert_eq!(cycles.len(), 1);
+    assert_eq!(cycles[0].canonical_key(), vec![3, 4]);
+
+    assert!(
+        SPFASolver
+            .find_all_negative_cycles(&break_even_loop())
+            .unwrap()
+            .is_empty()
+    );
+
+    let n = 500;
+    let cycles = SPFASolver
+        .find_all_negative_cycles(&large_circular(n))
+        .unwrap();
+    assert_eq!(cycles.len(), 1);
+    assert_eq!(cycles[0].path.len(), n);
+    assert!(cycles[0].log_rate_sum < 0.0);
+}
@@ -1,4 +1,5 @@
 use super::csr::GraphCSR;
+use super::solver::SPFASolver;
 use common::{error::Error, types::WeightedCycle};
 
 /// Trait for graph solvers capable of detecting negative cycles.
@@ -14,3 +15,76 @@ pub trait GraphSolver {
         hop_cap: usize,
     ) -> Result<Option<WeightedCycle>, Error>;
 }
+
+/// Graph-aware constructors for [`WeightedCycle`], which lives in `common` and cannot
+/// depend on [`GraphCSR`] itself.
+pub trait WeightedCycleExt: Sized {
+    /// Builds the cycle visiting `nodes` in order, e.g. `[0, 1, 2, 0]`; the last node
+    /// must repeat the first.
+    ///
+    /// Each hop uses the graph's stored weight, so `rates` and `log_rate_sum` are net
+    /// of fees, exactly as if a solver had found the cycle. If several edges connect a
+    /// pair, the first one in CSR order is used.
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidGraph` if `nodes` is not a closed walk of at least one
+    /// hop, a node is out of bounds, or a hop has no edge.
+    fn from_nodes(graph: &GraphCSR, nodes: &[usize]) -> Result<Self, Error>;
+}
+
+impl WeightedCycleExt for WeightedCycle {
+    fn from_nodes(graph: &GraphCSR, nodes: &[usize]) -> Result<Self, Error> {
+        if nodes.len() < 2 || nodes.first() != nodes.last() {
+            return Err(Error::InvalidGraph);
+        }
+
+        let edge_indices = nodes
+            .windows(2)
+            .map(|hop| {
+                let (u, v) = (hop[0], hop[1]);
+                if u >= graph.num_nodes {
+                    return Err(Error::InvalidGraph);
+                }
+                (graph.node_pointers[u]..graph.node_pointers[u + 1])
+                    .find(|&i| graph.edge_targets[i] == v)
+                    .ok_or(Error::InvalidGraph)
+            })
+            .collect::<Result<Vec<usize>, Error>>()?;
+
+        SPFASolver::cycle_from_edge_indices(&edge_indices, graph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cycle_from_nodes_matches_manual_product() {
+        let mut edges = vec![(0, 1, 1.1), (1, 2, 0.95), (2, 0, 1.02), (2, 3, 0.5)];
+        let graph = GraphCSR::from_edges(4, &mut edges, 10);
+
+        let cycle = WeightedCycle::from_nodes(&graph, &[0, 1, 2, 0]).unwrap();
+
+        assert!((cycle.product_rate() - 1.1 * 0.95 * 1.02).abs() < 1e-12);
+        assert_eq!(cycle.nodes(), vec![0, 1, 2]);
+        assert_eq!(cycle.rates.len(), 3);
+        assert!(cycle.is_valid());
+    }
+
+    #[test]
+    fn cycle_from_nodes_rejects_missing_hops_and_open_walks() {
+        let graph = crate::test_fixtures::simple_triangle_profit();
+
+        for nodes in [&[0, 2, 1, 0][..], &[0, 1, 2], &[0], &[], &[5, 0, 5]] {
+            assert!(
+                matches!(
+                    WeightedCycle::from_nodes(&graph, nodes),
+                    Err(Error::InvalidGraph)
+                ),
+                "{:?}",
+                nodes
+            );
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use std::fmt;
+use std::sync::Arc;
+
+/// Maps edge rates to the additive weights stored in the CSR, and back.
+///
+/// The solver only ever sees weights and looks for cycles whose weights sum below
+/// zero, so a transform must turn "profitable loop" into "negative-sum loop".
+/// `inverse` must undo `forward` so rates can be recovered on rebuilds and in
+/// reconstructed cycles.
+///
+/// Graph fees are folded in through `apply_fee` before `forward`. The default scales
+/// the value, which suits multiplicative rates; transforms over values that are not
+/// rates should leave them unchanged, as [`Linear`] does.
+pub trait WeightTransform: Send + Sync {
+    fn forward(&self, rate: f64) -> f64;
+    fn inverse(&self, weight: f64) -> f64;
+
+    /// `rate` net of a fee that keeps `fee_multiplier` (`1 - fee`) of it.
+    fn apply_fee(&self, rate: f64, fee_multiplier: f64) -> f64 {
+        rate * fee_multiplier
+    }
+
+    /// Undoes [`WeightTransform::apply_fee`].
+    fn remove_fee(&self, net: f64, fee_multiplier: f64) -> f64 {
+        net / fee_multiplier
+    }
+}
+
+/// Multiplicative exchange rates: `w = -ln(rate)`, so a product above 1 becomes a
+/// negative cycle.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NegLnRate;
+
+impl WeightTransform for NegLnRate {
+    fn forward(&self, rate: f64) -> f64 {
+        -rate.ln()
+    }
+
+    fn inverse(&self, weight: f64) -> f64 {
+        (-weight).exp()
+    }
+}
+
+/// Additive spreads/costs stored as-is; arbitrage is a cycle summing below zero.
+///
+/// Spreads are not rates, so graph fees do not apply to them.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Linear;
+
+impl WeightTransform for Linear {
+    fn forward(&self, rate: f64) -> f64 {
+        rate
+    }
+
+    fn inverse(&self, weight: f64) -> f64 {
+        weight
+    }
+
+    fn apply_fee(&self, rate: f64, _fee_multiplier: f64) -> f64 {
+        rate
+    }
+
+    fn remove_fee(&self, net: f64, _fee_multiplier: f64) -> f64 {
+        net
+    }
+}
+
+/// The transform a `GraphCSR` was built with.
+///
+/// The built-in transforms serialize by name. `Custom` transforms cannot be
+/// serialized, so graphs using one cannot be snapshotted.
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Transform {
+    #[default]
+    NegLnRate,
+    Linear,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(Arc<dyn WeightTransform>),
+}
+
+impl Transform {
+    /// Wraps a user-provided transform.
+    pub fn custom(transform: impl WeightTransform + 'static) -> Self {
+        Transform::Custom(Arc::new(transform))
+    }
+}
+
+impl WeightTransform for Transform {
+    fn forward(&self, rate: f64) -> f64 {
+        match self {
+            Transform::NegLnRate => NegLnRate.forward(rate),
+            Transform::Linear => Linear.forward(rate),
+            Transform::Custom(transform) => transform.forward(rate),
+        }
+    }
+
+    fn inverse(&self, weight: f64) -> f64 {
+        match self {
+            Transform::NegLnRate => NegLnRate.inverse(weight),
+            Transform::Linear => Linear.inverse(weight),
+            Transform::Custom(transform) => transform.inverse(weight),
+        }
+    }
+
+    fn apply_fee(&self, rate: f64, fee_multiplier: f64) -> f64 {
+        match self {
+            Transform::NegLnRate => NegLnRate.apply_fee(rate, fee_multiplier),
+            Transform::Linear => Linear.apply_fee(rate, fee_multiplier),
+            Transform::Custom(transform) => transform.apply_fee(rate, fee_multiplier),
+        }
+    }
+
+    fn remove_fee(&self, net: f64, fee_multiplier: f64) -> f64 {
+        match self {
+            Transform::NegLnRate => NegLnRate.remove_fee(net, fee_multiplier),
+            Transform::Linear => Linear.remove_fee(net, fee_multiplier),
+            Transform::Custom(transform) => transform.remove_fee(net, fee_multiplier),
+        }
+    }
+}
+
+impl fmt::Debug for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Transform::NegLnRate => f.write_str("NegLnRate"),
+            Transform::Linear => f.write_str("Linear"),
+            Transform::Custom(_) => f.write_str("Custom"),
+        }
+    }
+}
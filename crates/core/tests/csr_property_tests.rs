@@ -1,4 +1,6 @@
 use arb_solver_core::csr::GraphCSR;
+use arb_solver_core::solver::{BellmanFordSolver, SPFASolver};
+use arb_solver_core::traits::GraphSolver;
 use proptest::prelude::*;
 use proptest::strategy::Strategy;
 
@@ -76,4 +78,20 @@ proptest! {
             }
         }
     }
+
+    /// Property: SPFA's relaxation-count heuristic agrees with exact Bellman-Ford on
+    /// whether a negative cycle exists, and any reported cycle is really negative.
+    #[test]
+    fn spfa_agrees_with_bellman_ford((num_nodes, mut edges) in csr_strategy()) {
+        let csr = GraphCSR::from_edges(num_nodes, &mut edges, 5);
+        let hop_cap = num_nodes + 1;
+
+        let spfa = SPFASolver.find_profitable_cycle(&csr, 0, hop_cap).unwrap();
+        let bellman_ford = BellmanFordSolver.find_profitable_cycle(&csr, 0, hop_cap).unwrap();
+
+        prop_assert_eq!(spfa.is_some(), bellman_ford.is_some());
+        for cycle in spfa.iter().chain(bellman_ford.iter()) {
+            prop_assert!(cycle.log_rate_sum < 0.0);
+        }
+    }
 }
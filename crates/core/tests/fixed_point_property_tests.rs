@@ -0,0 +1,44 @@
+use arb_solver_core::csr::GraphCSR;
+use arb_solver_core::fixed_point::FixedPointSPFASolver;
+use arb_solver_core::solver::SPFASolver;
+use arb_solver_core::traits::GraphSolver;
+use proptest::prelude::*;
+
+const MAX_NODES: usize = 9;
+
+/// Graphs on at most 9 nodes whose weights are `0.01 * k + 0.001`.
+///
+/// A simple cycle of length `L < 10` then sums to `0.01 * K + 0.001 * L`, which is
+/// never zero and at least `0.001` in magnitude, so f64 rounding cannot flip the
+/// verdict and both solvers must agree.
+fn graph_strategy() -> impl Strategy<Value = (usize, Vec<(usize, usize, f64)>)> {
+    (2..=MAX_NODES).prop_flat_map(|n| {
+        let edge = (0..n, 0..n, -4i32..=4).prop_map(|(u, v, k)| {
+            let weight = 0.01 * k as f64 + 0.001;
+            (u, v, (-weight).exp())
+        });
+        (Just(n), prop::collection::vec(edge, 0..30))
+    })
+}
+
+proptest! {
+    /// Property: the fixed-point solver agrees with the f64 solver on cycle existence.
+    #[test]
+    fn fixed_point_agrees_with_f64_on_cycle_existence((n, mut edges) in graph_strategy()) {
+        // Rebuild dedups parallel edges, as the executor's graphs always are.
+        let mut graph = GraphCSR::from_edges(n, &mut [], 1);
+        graph.rebuild_with_edges(std::mem::take(&mut edges)).unwrap();
+        prop_assume!(graph.num_nodes > 0);
+        let n = graph.num_nodes;
+
+        let float = SPFASolver.find_profitable_cycle(&graph, 0, n + 1).unwrap();
+        let fixed = FixedPointSPFASolver::default()
+            .find_profitable_cycle(&graph, 0, n + 1)
+            .unwrap();
+
+        prop_assert_eq!(float.is_some(), fixed.is_some());
+        if let Some(cycle) = fixed {
+            prop_assert!(cycle.log_rate_sum < 0.0);
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use arb_solver_core::csr::GraphCSR;
+use arb_solver_core::scc::SccIndex;
+use proptest::prelude::*;
+
+fn edge_sequence_strategy() -> impl Strategy<Value = Vec<(usize, usize)>> {
+    prop::collection::vec((0usize..12, 0usize..12), 0..60)
+}
+
+proptest! {
+    /// Property: applying edge additions one by one yields the same partition as
+    /// running Tarjan from scratch on the final graph.
+    #[test]
+    fn incremental_additions_match_from_scratch(sequence in edge_sequence_strategy()) {
+        let mut index = SccIndex::default();
+        for &(u, v) in &sequence {
+            index.add_edge(u, v);
+        }
+
+        let num_nodes = sequence.iter().flat_map(|&(u, v)| [u, v]).max().map_or(0, |m| m + 1);
+        let mut edges: Vec<_> = sequence.iter().map(|&(u, v)| (u, v, 1.0)).collect();
+        let expected = SccIndex::from_graph(&GraphCSR::from_edges(num_nodes, &mut edges, 1));
+
+        prop_assert_eq!(index.canonical_labels(), expected.canonical_labels());
+    }
+}
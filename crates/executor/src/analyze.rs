@@ -0,0 +1,191 @@
+use std::fmt::Write;
+use std::str::FromStr;
+
+use arb_solver_core::GraphCSR;
+use arb_solver_core::solver::SPFASolver;
+use common::types::WeightedCycle;
+
+use super::csv_streamer::CsvStreamer;
+use super::error::Error;
+
+const CSV_BATCH_SIZE: usize = 1024;
+
+/// How `analyze` prints the cycles it found.
+///
+/// - `Table`: one human-readable line per cycle (the default).
+/// - `Json`: a JSON array of cycles, as serialized for sinks.
+/// - `Csv`: a header, then one row per cycle edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+    Csv,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "table" => Ok(OutputFormat::Table),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            other => Err(format!(
+                "Unknown output format {:?} (expected json, csv or table)",
+                other
+            )),
+        }
+    }
+}
+
+/// Loads `path`, finds all profitable cycles and prints them ranked by profit in
+/// `format`.
+///
+/// Runs synchronously and independently of the streaming pipeline. Progress goes to
+/// stderr, so the JSON and CSV output on stdout can be piped into other tools.
+pub fn run(path: &str, format: OutputFormat) -> Result<(), Error> {
+    let edges = CsvStreamer::new(path.to_string(), CSV_BATCH_SIZE).parse_csv_to_edges()?;
+
+    let mut graph = GraphCSR::from_edges(0, &mut [], edges.len());
+    graph.rebuild_with_edges(edges)?;
+    eprintln!(
+        "Loaded {} nodes, {} edges from {}.",
+        graph.num_nodes,
+        graph.edge_targets.len(),
+        path
+    );
+
+    let cycles = SPFASolver.find_all_negative_cycles(&graph)?;
+    print!("{}", render(&cycles, format)?);
+    Ok(())
+}
+
+/// Formats ranked `cycles` (best first) in `format`.
+pub fn render(cycles: &[WeightedCycle], format: OutputFormat) -> Result<String, Error> {
+    let mut out = String::new();
+    match format {
+        OutputFormat::Table => {
+            if cycles.is_empty() {
+                out.push_str("No arbitrage opportunities.\n");
+                return Ok(out);
+            }
+
+            let _ = writeln!(out, "Found {} profitable cycle(s):", cycles.len());
+            for (rank, cycle) in cycles.iter().enumerate() {
+                let mut nodes: Vec<String> = cycle.nodes().iter().map(|n| n.to_string()).collect();
+                nodes.push(nodes[0].clone());
+
+                let _ = writeln!(
+                    out,
+                    "#{} profit: {:.4}% product rate: {:.6} path: {}",
+                    rank + 1,
+                    (cycle.product_rate() - 1.0) * 100.0,
+                    cycle.product_rate(),
+                    nodes.join(" -> ")
+                );
+            }
+        }
+        OutputFormat::Json => {
+            out = serde_json::to_string_pretty(cycles)?;
+            out.push('\n');
+        }
+        OutputFormat::Csv => {
+            out.push_str("rank,hop,from,to,rate,product_rate\n");
+            for (rank, cycle) in cycles.iter().enumerate() {
+                for (hop, &(from, to, rate)) in cycle.path.iter().enumerate() {
+                    let _ = writeln!(
+                        out,
+                        "{},{},{},{},{},{}",
+                        rank + 1,
+                        hop + 1,
+                        from,
+                        to,
+                        rate,
+                        cycle.product_rate()
+                    );
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two disjoint profitable loops: a 3-hop one (+10%) and a 2-hop one (+5%).
+    fn two_cycles() -> Vec<WeightedCycle> {
+        let mut edges = vec![
+            (0, 1, 1.1),
+            (1, 2, 1.0),
+            (2, 0, 1.0),
+            (3, 4, 1.05),
+            (4, 3, 1.0),
+        ];
+        let graph = GraphCSR::from_edges(5, &mut edges, 10);
+        let cycles = SPFASolver.find_all_negative_cycles(&graph).unwrap();
+        assert_eq!(cycles.len(), 2);
+        cycles
+    }
+
+    #[test]
+    fn finds_disjoint_cycles_ranked_by_profit() {
+        let mut edges = vec![
+            (0, 1, 1.01),
+            (1, 0, 1.0),
+            (2, 3, 1.2),
+            (3, 4, 1.0),
+            (4, 2, 1.0),
+            (4, 5, 0.5),
+        ];
+        let graph = GraphCSR::from_edges(6, &mut edges, 10);
+
+        let cycles = SPFASolver.find_all_negative_cycles(&graph).unwrap();
+
+        let keys: Vec<_> = cycles.iter().map(|c| c.canonical_key()).collect();
+        assert_eq!(keys, vec![vec![2, 3, 4], vec![0, 1]]);
+        assert!(cycles.iter().all(|c| c.is_profitable()));
+    }
+
+    #[test]
+    fn json_output_is_an_array_of_cycles() {
+        let out = render(&two_cycles(), OutputFormat::Json).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&out).unwrap();
+
+        let cycles = value.as_array().expect("Expected a JSON array");
+        assert_eq!(cycles.len(), 2);
+        assert_eq!(cycles[0]["path"].as_array().unwrap().len(), 3);
+        assert_eq!(cycles[1]["path"].as_array().unwrap().len(), 2);
+        assert!((cycles[0]["product_rate"].as_f64().unwrap() - 1.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn csv_output_has_one_row_per_cycle_edge() {
+        let out = render(&two_cycles(), OutputFormat::Csv).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+
+        assert_eq!(lines[0], "rank,hop,from,to,rate,product_rate");
+        assert_eq!(lines.len(), 1 + 3 + 2);
+        assert!(lines[1..].iter().all(|line| line.split(',').count() == 6));
+        assert!(lines[1].starts_with("1,1,0,1,1.1,"));
+        assert!(lines[4].starts_with("2,1,3,4,1.05,"));
+    }
+
+    #[test]
+    fn table_output_ranks_cycles_by_profit() {
+        let out = render(&two_cycles(), OutputFormat::Table).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "Found 2 profitable cycle(s):");
+        assert!(lines[1].starts_with("#1 profit: 10.0000%"));
+        assert!(lines[1].ends_with("path: 0 -> 1 -> 2 -> 0"));
+        assert!(lines[2].starts_with("#2 profit: 5.0000%"));
+        assert_eq!(
+            render(&[], OutputFormat::Table).unwrap(),
+            "No arbitrage opportunities.\n"
+        );
+    }
+}
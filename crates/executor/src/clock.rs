@@ -0,0 +1,139 @@
+use std::sync::Arc;
+use tokio::time::{self, Duration, Instant};
+
+use super::types::now_epoch_ms;
+
+/// Source of time for interval-driven tasks (searcher, simulator).
+///
+/// Production code uses [`TokioClock`]; tests can swap in [`MockClock`] to drive
+/// intervals deterministically instead of sleeping in real time.
+#[async_trait::async_trait]
+pub trait Clock: Send + Sync + 'static {
+    fn now(&self) -> Instant;
+
+    /// Current time in Unix epoch milliseconds, for deadlines read outside the process.
+    fn epoch_ms(&self) -> u64;
+
+    /// Completes once `now() >= deadline`.
+    async fn sleep_until(&self, deadline: Instant);
+}
+
+/// Clock backed by `tokio::time`, so it also honours `tokio::time::pause`/`advance`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioClock;
+
+#[async_trait::async_trait]
+impl Clock for TokioClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn epoch_ms(&self) -> u64 {
+        now_epoch_ms()
+    }
+
+    async fn sleep_until(&self, deadline: Instant) {
+        time::sleep_until(deadline).await;
+    }
+}
+
+/// Fixed-period ticker driven by a [`Clock`], mirroring `tokio::time::interval`.
+///
+/// The first tick completes immediately; missed ticks fire back-to-back.
+pub struct Ticker {
+    clock: Arc<dyn Clock>,
+    period: Duration,
+    next: Instant,
+}
+
+impl Ticker {
+    pub fn new(clock: Arc<dyn Clock>, period: Duration) -> Self {
+        let next = clock.now();
+        Ticker {
+            clock,
+            period,
+            next,
+        }
+    }
+
+    /// Waits for the next tick and returns its scheduled instant.
+    pub async fn tick(&mut self) -> Instant {
+        let deadline = self.next;
+        self.clock.sleep_until(deadline).await;
+        self.next = deadline + self.period;
+        deadline
+    }
+}
+
+/// Manually advanced clock for tests. Time only moves on [`MockClock::advance`].
+#[cfg(test)]
+pub struct MockClock {
+    now: tokio::sync::watch::Sender<Instant>,
+    start: Instant,
+    start_epoch_ms: u64,
+}
+
+#[cfg(test)]
+impl MockClock {
+    pub fn new() -> Self {
+        let start = Instant::now();
+        MockClock {
+            now: tokio::sync::watch::Sender::new(start),
+            start,
+            start_epoch_ms: now_epoch_ms(),
+        }
+    }
+
+    /// Moves time forward by `by`, waking every sleeper whose deadline has passed.
+    pub fn advance(&self, by: Duration) {
+        self.now.send_modify(|now| *now += by);
+    }
+}
+
+#[cfg(test)]
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+#[async_trait::async_trait]
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        *self.now.borrow()
+    }
+
+    fn epoch_ms(&self) -> u64 {
+        self.start_epoch_ms + (self.now() - self.start).as_millis() as u64
+    }
+
+    async fn sleep_until(&self, deadline: Instant) {
+        let mut now = self.now.subscribe();
+        // The sender lives in `self`, so the channel cannot close while we wait.
+        let _ = now.wait_for(|&now| now >= deadline).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::timeout;
+
+    #[tokio::test]
+    async fn ticker_on_mock_clock_fires_only_when_advanced() {
+        let clock = Arc::new(MockClock::new());
+        let mut ticker = Ticker::new(clock.clone(), Duration::from_secs(1));
+        let start = ticker.tick().await;
+
+        let mut pending = Box::pin(ticker.tick());
+        assert!(
+            timeout(Duration::from_millis(20), &mut pending)
+                .await
+                .is_err()
+        );
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(pending.await, start + Duration::from_secs(1));
+    }
+}
@@ -1,23 +1,229 @@
 use config::{Config as ConfigLoader, Environment, File};
 use serde::Deserialize;
+use std::collections::HashSet;
 use std::env;
 use std::path::PathBuf;
 
 use super::error::Error;
+use arb_solver_core::csr::{DedupKey, DedupPolicy};
+use common::numeric_kernel::KernelParams;
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct SearcherConfig {
     pub interval_seconds: u64,
+    #[serde(default = "default_dedup_capacity")]
+    pub dedup_capacity: usize,
+    #[serde(default)]
+    pub dedup_undirected: bool,
+    #[serde(default = "default_min_cycle_len")]
+    pub min_cycle_len: usize,
+    #[serde(default)]
+    pub fair_scheduling: bool,
+    #[serde(default)]
+    pub warmup_seconds: u64,
+    /// Number of worker tasks splitting each scan by strongly connected component.
+    #[serde(default = "default_workers")]
+    pub workers: usize,
+    /// How long an emitted opportunity stays valid; defaults to one search interval.
+    #[serde(default)]
+    pub opportunity_ttl_ms: Option<u64>,
+    /// Failed sink deliveries kept for retry.
+    #[serde(default = "default_sink_retry_capacity")]
+    pub sink_retry_capacity: usize,
+    /// Delivery attempts (including the first) before an opportunity is dropped.
+    #[serde(default = "default_sink_max_attempts")]
+    pub sink_max_attempts: u32,
+    /// Delay before the first retry; doubles after every further failure.
+    #[serde(default = "default_sink_retry_backoff_ms")]
+    pub sink_retry_backoff_ms: u64,
+    #[serde(default)]
+    pub scan_mode: ScanMode,
+    #[serde(default)]
+    pub source_mode: SourceMode,
+    /// Only emit cycles with an edge whose rate moved at least this much (bps) since the
+    /// start of the last `edge_move_window_ms`. `None` emits cycles regardless of momentum.
+    #[serde(default)]
+    pub min_edge_move_bps: Option<f64>,
+    #[serde(default = "default_edge_move_window_ms")]
+    pub edge_move_window_ms: u64,
+    /// Longest wait for the graph's read lock before a scan falls back to the previous
+    /// snapshot (with a warning). `None` waits as long as it takes.
+    #[serde(default)]
+    pub lock_timeout_ms: Option<u64>,
+    /// Also report near-misses: cycles profitable before fees by less than this margin
+    /// (bps) but not after fees. `None` disables the extra per-scan search.
+    #[serde(default)]
+    pub near_miss_margin_bps: Option<f64>,
+    /// Only emit cycles visiting at least one of these nodes. Empty emits every cycle.
+    #[serde(default)]
+    pub base_nodes: Vec<usize>,
+    /// Time budget for searching components within one scan; components not started
+    /// before it is spent are skipped until the next scan. `None` searches them all.
+    #[serde(default)]
+    pub search_budget_ms: Option<u64>,
+    /// Treat edges listed in `edge_liquidity` below this depth as absent while searching.
+    /// `None` searches every edge.
+    #[serde(default)]
+    pub min_liquidity: Option<f64>,
+    /// Per-pair liquidity checked against `min_liquidity`; unlisted pairs count as deep.
+    #[serde(default)]
+    pub edge_liquidity: Vec<EdgeLiquidity>,
+}
+
+impl SearcherConfig {
+    /// The `(from, to)` pairs whose liquidity is below `min_liquidity`.
+    pub fn thin_edges(&self) -> HashSet<(usize, usize)> {
+        let Some(min_liquidity) = self.min_liquidity else {
+            return HashSet::new();
+        };
+        self.edge_liquidity
+            .iter()
+            .filter(|edge| edge.liquidity < min_liquidity)
+            .map(|edge| (edge.from, edge.to))
+            .collect()
+    }
+}
+
+/// Liquidity available on a single `from -> to` pair, in the quote units of `min_liquidity`.
+#[derive(Debug, Deserialize, Clone)]
+pub struct EdgeLiquidity {
+    pub from: usize,
+    pub to: usize,
+    pub liquidity: f64,
+}
+
+/// What the searcher examines on each interval.
+///
+/// - `Full`: the whole graph. Every standing opportunity is re-found each scan, at a cost
+///   proportional to the graph size.
+/// - `Incremental`: only the strongly connected components containing a node touched by
+///   an update since the last scan. Latency scales with the amount of change rather than
+///   the graph, but a loop whose rates did not move is not re-reported (its opportunity
+///   book entry is not refreshed and eventually expires).
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ScanMode {
+    #[default]
+    Full,
+    Incremental,
+}
+
+/// Where each search starts.
+///
+/// - `AllNodes`: every node is seeded (a virtual source), so every scan finds any
+///   profitable cycle in the graph, doing the same full work each time.
+/// - `RoundRobin`: a single seed node, advanced by one (wrapping around the node count)
+///   on every scan. Each search only covers what the seed reaches, spreading detection
+///   across scans; pairs well with `ScanMode::Incremental`, where each component is
+///   searched on its own and any member reaches all of its cycles.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceMode {
+    #[default]
+    AllNodes,
+    RoundRobin,
+}
+
+fn default_sink_retry_capacity() -> usize {
+    256
+}
+
+fn default_sink_max_attempts() -> u32 {
+    5
+}
+
+fn default_sink_retry_backoff_ms() -> u64 {
+    500
+}
+
+fn default_edge_move_window_ms() -> u64 {
+    10_000
+}
+
+fn default_workers() -> usize {
+    1
+}
+
+fn default_dedup_capacity() -> usize {
+    1024
+}
+
+fn default_min_cycle_len() -> usize {
+    3
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct WriterConfig {
     pub batch_capacity: usize,
+    /// Updates touching a node id `>= max_nodes` are dropped.
+    #[serde(default = "default_max_nodes")]
+    pub max_nodes: usize,
+    /// Batches prefetched from the channel while a flush runs, then applied together
+    /// in the next flush. 0 processes one batch at a time.
+    #[serde(default)]
+    pub max_in_flight: usize,
+    /// Updates whose rate differs from the last applied rate for the same edge by less
+    /// than this are skipped entirely. `None` applies every update.
+    #[serde(default)]
+    pub rate_epsilon: Option<f64>,
+    /// Once a rebuild is triggered, batches arriving within this window are folded into
+    /// it instead of triggering another one right after. 0 rebuilds immediately.
+    #[serde(default)]
+    pub rebuild_debounce_ms: u64,
+    /// Reject a whole batch (one producer message) if any of its updates is invalid,
+    /// instead of dropping only the invalid updates.
+    ///
+    /// This is all-or-nothing at admission only: admitted batches join the graph's
+    /// pending buffer and are committed by whichever rebuild comes next, together with
+    /// everything else pending. An update going stale while buffered is dropped on its
+    /// own at that rebuild, and a failed rebuild drops every pending batch.
+    #[serde(default)]
+    pub strict: bool,
+    #[serde(default)]
+    pub update_kind: UpdateKind,
+}
+
+/// What the rate field of an incoming update means.
+///
+/// - `Absolute`: the edge's new rate, replacing the previous one.
+/// - `Delta`: a change in bps (`+0.5` multiplies the rate by 1.00005), applied in
+///   log-space to the edge's latest rate. The first delta for an edge starts from its
+///   committed rate; a delta for an edge without one has no defined result and is
+///   rejected as non-finite.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateKind {
+    #[default]
+    Absolute,
+    Delta,
+}
+
+/// The graph grows eagerly to the largest accepted id, so even a single stray id must
+/// not be able to request an unbounded allocation.
+fn default_max_nodes() -> usize {
+    1_000_000
+}
+
+/// Flavour of the producer → writer channel.
+///
+/// - `Bounded`: holds at most `buffer_size` batches; producers are backpressured
+///   (await on send) when the writer falls behind. Memory is capped, but a producer
+///   can stall for as long as the writer is busy rebuilding.
+/// - `Unbounded`: sends never block; bursts are absorbed by growing the queue.
+///   Memory is unbounded if the writer is persistently slower than the feed.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ChannelKind {
+    #[default]
+    Bounded,
+    Unbounded,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct ExecutorConfig {
     pub buffer_size: usize,
+    #[serde(default)]
+    pub channel: ChannelKind,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -31,6 +237,204 @@ pub struct SimulatorConfig {
     pub batch_size: usize,
     pub simulation_interval_ms: u64,
     pub rate_fluctuation_bps: f64,
+    /// Nodes of a profitable loop appended to every batch (demos/integration tests).
+    #[serde(default)]
+    pub inject_cycle: Option<Vec<usize>>,
+    /// Move related edges together instead of independently.
+    #[serde(default)]
+    pub correlation: Option<CorrelationConfig>,
+    /// Seed for a reproducible stream; `None` seeds from the OS. The generator is
+    /// ChaCha8, whose output is portable across platforms and `rand` releases.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Introduce node ids gradually instead of using all `total_nodes` from the start.
+    #[serde(default)]
+    pub node_growth: Option<NodeGrowthConfig>,
+}
+
+impl SimulatorConfig {
+    /// Rejects settings the simulator cannot run with.
+    ///
+    /// # Errors
+    /// `Error::ConfigLoadError` if `simulation_interval_ms` is 0, or if `node_growth`
+    /// starts with no nodes.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.simulation_interval_ms == 0 {
+            return Err(Error::ConfigLoadError(
+                "simulator.simulation_interval_ms must be greater than 0".to_string(),
+            ));
+        }
+        if self
+            .node_growth
+            .as_ref()
+            .is_some_and(|growth| growth.initial_nodes == 0)
+        {
+            return Err(Error::ConfigLoadError(
+                "simulator.node_growth.initial_nodes must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Node-count ramp: the first batch draws from `initial_nodes` ids, and every later
+/// batch unlocks `nodes_per_batch` more, up to `total_nodes`.
+#[derive(Debug, Deserialize, Clone)]
+pub struct NodeGrowthConfig {
+    pub initial_nodes: usize,
+    pub nodes_per_batch: usize,
+}
+
+/// Shared-factor rate model: edges are grouped by source node (`from % groups`), and
+/// every batch draws one common move per group on top of each edge's own noise
+/// (`rate_fluctuation_bps`).
+#[derive(Debug, Deserialize, Clone)]
+pub struct CorrelationConfig {
+    pub groups: usize,
+    /// Max magnitude of a group's common move, in the same units as `rate_fluctuation_bps`.
+    pub factor_bps: f64,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct GraphConfig {
+    #[serde(default)]
+    pub fee_bps: f64,
+    #[serde(default)]
+    pub dedup_policy: DedupPolicy,
+    /// Whether `(a, b)` and `(b, a)` quotes are deduplicated as one market.
+    #[serde(default)]
+    pub dedup_key: DedupKey,
+    /// Per-pair fees overriding `fee_bps`.
+    #[serde(default)]
+    pub edge_fees: Vec<EdgeFee>,
+    /// Rebuild once pending updates reach this fraction of committed edges,
+    /// instead of after a fixed number of updates.
+    #[serde(default)]
+    pub rebuild_ratio: Option<f64>,
+    /// Drop edges quoted at exactly 1.0 (zero weight, never profitable).
+    #[serde(default)]
+    pub drop_unit_rates: bool,
+    /// Maximum outgoing edges kept per node on rebuild (best rates win).
+    #[serde(default)]
+    pub max_out_degree: Option<usize>,
+    /// Bounds rates are clamped to before weighting, guarding against fat-finger quotes.
+    #[serde(default)]
+    pub rate_clamp: Option<KernelParams>,
+    /// Hard ceiling on buffered updates; reaching it forces a rebuild.
+    #[serde(default)]
+    pub max_pending: Option<usize>,
+    /// Let the rebuild limit follow the update rate; takes precedence over `rebuild_ratio`.
+    #[serde(default)]
+    pub adaptive_rebuild: Option<AdaptiveRebuildConfig>,
+}
+
+impl GraphConfig {
+    /// Rejects settings the graph cannot be built with.
+    ///
+    /// # Errors
+    /// `Error::ConfigLoadError` if `fee_bps` or any `edge_fees` entry is outside
+    /// `0..10_000`, or if `rate_clamp` has `min_r > max_r` or a NaN bound.
+    pub fn validate(&self) -> Result<(), Error> {
+        if !(0.0..10_000.0).contains(&self.fee_bps) {
+            return Err(Error::ConfigLoadError(format!(
+                "graph.fee_bps must be in 0..10000, got {}",
+                self.fee_bps
+            )));
+        }
+        if let Some(fee) = self
+            .edge_fees
+            .iter()
+            .find(|fee| !(0.0..10_000.0).contains(&fee.fee_bps))
+        {
+            return Err(Error::ConfigLoadError(format!(
+                "graph.edge_fees: fee for {} -> {} must be in 0..10000, got {}",
+                fee.from, fee.to, fee.fee_bps
+            )));
+        }
+        // `KernelParams::new` asserts this, but deserializing bypasses it.
+        if let Some(clamp) = self.rate_clamp
+            && (clamp.min_r.is_nan() || clamp.max_r.is_nan() || clamp.min_r > clamp.max_r)
+        {
+            return Err(Error::ConfigLoadError(format!(
+                "graph.rate_clamp: min_r ({}) must not exceed max_r ({})",
+                clamp.min_r, clamp.max_r
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Bounds and target of the adaptive rebuild limit: about one rebuild per
+/// `target_interval_ms` worth of updates, kept within `min_limit..=max_limit`.
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct AdaptiveRebuildConfig {
+    pub min_limit: usize,
+    pub max_limit: usize,
+    pub target_interval_ms: u64,
+}
+
+/// Fee charged on a single `from -> to` pair, e.g. by a venue with its own schedule.
+#[derive(Debug, Deserialize, Clone)]
+pub struct EdgeFee {
+    pub from: usize,
+    pub to: usize,
+    pub fee_bps: f64,
+}
+
+/// Periodic on-disk snapshots of the graph. Disabled when `interval_seconds` is 0.
+#[derive(Debug, Deserialize, Clone)]
+pub struct SnapshotConfig {
+    #[serde(default)]
+    pub interval_seconds: u64,
+    #[serde(default = "default_snapshot_path")]
+    pub path: String,
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        Self {
+            interval_seconds: 0,
+            path: default_snapshot_path(),
+        }
+    }
+}
+
+fn default_snapshot_path() -> String {
+    "graph_snapshot.json".to_string()
+}
+
+/// `/opportunities` JSON endpoint (requires the `http` feature). Disabled without `bind`.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct HttpConfig {
+    #[serde(default)]
+    pub bind: Option<String>,
+}
+
+/// Where emitted opportunities are delivered. Disabled without `path`.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct SinkConfig {
+    /// File every emitted opportunity is appended to, as one JSON line.
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ReportConfig {
+    /// Print the run summary (scans, cycles, best cycle, edges, rebuilds) on shutdown.
+    #[serde(default = "default_on_shutdown")]
+    pub on_shutdown: bool,
+}
+
+impl Default for ReportConfig {
+    fn default() -> Self {
+        Self {
+            on_shutdown: default_on_shutdown(),
+        }
+    }
+}
+
+fn default_on_shutdown() -> bool {
+    true
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -40,9 +444,23 @@ pub struct Config {
     pub executor: ExecutorConfig,
     pub writer: WriterConfig,
     pub producer: ProducerConfig,
+    #[serde(default)]
+    pub graph: GraphConfig,
+    #[serde(default)]
+    pub snapshot: SnapshotConfig,
+    #[serde(default)]
+    pub http: HttpConfig,
+    #[serde(default)]
+    pub sink: SinkConfig,
+    #[serde(default)]
+    pub report: ReportConfig,
 }
 
 /// Loads configuration from a file and environment variables.
+///
+/// # Errors
+/// `Error::ConfigLoadError` if the file is missing or malformed, or if a section's
+/// `validate` rejects it.
 pub fn load_config() -> Result<Config, Error> {
     let base_path = env::current_dir().map_err(|e| {
         Error::ConfigLoadError(format!("Failed to determine current directory: {}", e))
@@ -73,6 +491,77 @@ pub fn load_config() -> Result<Config, Error> {
     let app_config: Config = s
         .try_deserialize()
         .map_err(|e| Error::ConfigLoadError(format!("Failed to deserialize config: {}", e)))?;
+    app_config.simulator.validate()?;
+    app_config.graph.validate()?;
 
     Ok(app_config)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn graph_fee_outside_the_bps_range_is_rejected() {
+        for fee_bps in [-1.0, 10_000.0, f64::NAN] {
+            let graph = GraphConfig {
+                fee_bps,
+                ..GraphConfig::default()
+            };
+            assert!(matches!(graph.validate(), Err(Error::ConfigLoadError(_))));
+        }
+
+        let graph = GraphConfig {
+            fee_bps: 30.0,
+            ..GraphConfig::default()
+        };
+        assert!(graph.validate().is_ok());
+    }
+
+    #[test]
+    fn edge_fee_outside_the_bps_range_is_rejected() {
+        let graph = GraphConfig {
+            edge_fees: vec![
+                EdgeFee {
+                    from: 0,
+                    to: 1,
+                    fee_bps: 25.0,
+                },
+                EdgeFee {
+                    from: 1,
+                    to: 0,
+                    fee_bps: 10_000.0,
+                },
+            ],
+            ..GraphConfig::default()
+        };
+        assert!(matches!(graph.validate(), Err(Error::ConfigLoadError(_))));
+    }
+
+    #[test]
+    fn inverted_or_nan_rate_clamp_is_rejected() {
+        let graph: GraphConfig = ConfigLoader::builder()
+            .add_source(File::from_str(
+                "rate_clamp = { min_r = 10.0, max_r = 0.1 }",
+                config::FileFormat::Toml,
+            ))
+            .build()
+            .and_then(ConfigLoader::try_deserialize)
+            .expect("Deserializing does not check the bounds");
+        assert!(matches!(graph.validate(), Err(Error::ConfigLoadError(_))));
+
+        for (min_r, max_r) in [(f64::NAN, 1.0), (0.1, f64::NAN)] {
+            let graph = GraphConfig {
+                rate_clamp: Some(KernelParams { min_r, max_r }),
+                ..GraphConfig::default()
+            };
+            assert!(matches!(graph.validate(), Err(Error::ConfigLoadError(_))));
+        }
+
+        let graph = GraphConfig {
+            rate_clamp: Some(KernelParams::new(0.1, 10.0)),
+            ..GraphConfig::default()
+        };
+        assert!(graph.validate().is_ok());
+    }
+}
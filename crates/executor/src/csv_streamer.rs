@@ -1,10 +1,10 @@
-use csv::ReaderBuilder;
+use csv::{ByteRecord, Reader, ReaderBuilder};
 use serde::Deserialize;
 use std::fs::File;
-use tokio::sync::mpsc::Sender;
+use std::io::Read;
 
 use super::error::Error;
-use super::types::UpdateStreamer;
+use super::types::{UpdateSender, UpdateStreamer};
 use common::types::Edge;
 
 // Helper struct for CSV parsing
@@ -20,29 +20,203 @@ pub struct CsvRecord {
     pub rate_value: f64,
 }
 
+/// Streams one or more CSV files, in order, as a single feed.
 pub struct CsvStreamer {
-    path: String,
+    paths: Vec<String>,
     batch_size: usize,
 }
 
+/// Incremental CSV reader yielding edges in batches of at most `batch_size`.
+///
+/// Only the batch currently being filled is held in memory, so arbitrarily large
+/// files can be streamed with bounded memory.
+pub struct CsvBatchReader<R = File> {
+    reader: Reader<R>,
+    /// Reused buffer for the raw row being parsed.
+    record: ByteRecord,
+    /// Data rows read so far (header excluded).
+    rows_read: u64,
+    batch_size: usize,
+    /// Largest number of edges ever buffered at once (for memory diagnostics).
+    pub max_buffered: usize,
+}
+
+impl CsvBatchReader<File> {
+    pub fn open(path: &str, batch_size: usize) -> Result<Self, Error> {
+        let file = File::open(path).map_err(|e| {
+            eprintln!("Failed to read file {}: {:?}", path, e);
+            Error::IoError(e)
+        })?;
+
+        Ok(Self::from_reader(file, batch_size))
+    }
+}
+
+impl<R: Read> CsvBatchReader<R> {
+    /// Reads CSV (with a header row) from any byte source.
+    pub fn from_reader(reader: R, batch_size: usize) -> Self {
+        let rdr = ReaderBuilder::new().has_headers(true).from_reader(reader);
+
+        Self {
+            reader: rdr,
+            record: ByteRecord::new(),
+            rows_read: 0,
+            batch_size: batch_size.max(1),
+            max_buffered: 0,
+        }
+    }
+
+    /// Reads the next batch of up to `batch_size` edges, or `None` at end of file.
+    ///
+    /// Columns other than `from`, `to` and `rate` are ignored, and a leading UTF-8 BOM
+    /// (as written by spreadsheet exports) is skipped by the underlying `csv` reader.
+    ///
+    /// # Errors
+    /// - `Error::CsvParseError` if the header row cannot be read.
+    /// - `Error::CsvRowError` for the first row that cannot be read or deserialized,
+    ///   carrying its row and line numbers and its raw content.
+    pub fn next_batch(&mut self) -> Result<Option<Vec<Edge>>, Error> {
+        let headers = self.reader.byte_headers()?.clone();
+        let mut batch = Vec::with_capacity(self.batch_size);
+
+        while batch.len() < self.batch_size {
+            let row = self.rows_read + 1;
+            match self.reader.read_byte_record(&mut self.record) {
+                Ok(true) => self.rows_read = row,
+                Ok(false) => break,
+                Err(source) => return Err(self.row_error(row, source)),
+            }
+
+            let record: CsvRecord = self
+                .record
+                .deserialize(Some(&headers))
+                .map_err(|source| self.row_error(row, source))?;
+            batch.push((record.from_node, record.to_node, record.rate_value));
+        }
+        self.max_buffered = self.max_buffered.max(batch.len());
+
+        Ok((!batch.is_empty()).then_some(batch))
+    }
+
+    /// Wraps `source` with the position and (lossily decoded) content of the current row.
+    fn row_error(&self, row: u64, source: csv::Error) -> Error {
+        let line = source
+            .position()
+            .or(self.record.position())
+            .map_or(0, |pos| pos.line());
+        let content = self
+            .record
+            .iter()
+            .map(String::from_utf8_lossy)
+            .collect::<Vec<_>>()
+            .join(",");
+
+        Error::CsvRowError {
+            row,
+            line,
+            content,
+            source,
+        }
+    }
+}
+
+/// Chains [`CsvBatchReader`]s over several files, read one after another in order.
+///
+/// Batches never span files, so a file's last batch may be short. Files that cannot
+/// be opened (e.g. missing) are skipped with a warning; empty or header-only files
+/// simply yield nothing.
+pub struct CsvFilesReader {
+    paths: std::vec::IntoIter<String>,
+    current: Option<CsvBatchReader>,
+    batch_size: usize,
+    opened: usize,
+    /// First open failure, returned if no file could be opened at all.
+    first_error: Option<Error>,
+}
+
+impl CsvFilesReader {
+    pub fn new(paths: Vec<String>, batch_size: usize) -> Self {
+        Self {
+            paths: paths.into_iter(),
+            current: None,
+            batch_size,
+            opened: 0,
+            first_error: None,
+        }
+    }
+
+    /// Reads the next batch from the current file, moving on to the next file at its end.
+    /// Returns `None` once every file is exhausted.
+    ///
+    /// # Errors
+    /// - The first open error (usually `Error::IoError`) if none of the files exist.
+    /// - Any error from [`CsvBatchReader::next_batch`] for the file being read.
+    pub fn next_batch(&mut self) -> Result<Option<Vec<Edge>>, Error> {
+        loop {
+            if let Some(reader) = &mut self.current {
+                if let Some(batch) = reader.next_batch()? {
+                    return Ok(Some(batch));
+                }
+                self.current = None;
+            }
+
+            let Some(path) = self.paths.next() else {
+                return match self.first_error.take() {
+                    Some(e) if self.opened == 0 => Err(e),
+                    _ => Ok(None),
+                };
+            };
+
+            match CsvBatchReader::open(&path, self.batch_size) {
+                Ok(reader) => {
+                    self.opened += 1;
+                    self.current = Some(reader);
+                }
+                Err(e) => {
+                    eprintln!("CsvStreamer: Skipping {}: {}", path, e);
+                    self.first_error.get_or_insert(e);
+                }
+            }
+        }
+    }
+}
+
+/// Parses in-memory CSV bytes into edges.
+///
+/// Never panics on malformed input: bad UTF-8, missing or extra columns and
+/// non-numeric fields all surface as `Error::CsvRowError` (or `Error::CsvParseError`
+/// for an unreadable header).
+pub fn parse_csv_bytes(data: &[u8]) -> Result<Vec<Edge>, Error> {
+    let mut reader = CsvBatchReader::from_reader(data, 1024);
+
+    let mut edges = Vec::new();
+    while let Some(batch) = reader.next_batch()? {
+        edges.extend(batch);
+    }
+    Ok(edges)
+}
+
 impl CsvStreamer {
     pub fn new(path: String, batch_size: usize) -> Self {
-        CsvStreamer { path, batch_size }
+        Self::with_paths(vec![path], batch_size)
     }
 
-    fn parse_csv_to_edges(&self) -> Result<Vec<Edge>, Error> {
-        let file = File::open(&self.path).map_err(|e| {
-            eprintln!("Failed to read file {}: {:?}", self.path, e);
-            Error::IoError(e)
-        })?;
+    /// Streams `paths` one after another (see [`CsvFilesReader`]).
+    pub fn with_paths(paths: Vec<String>, batch_size: usize) -> Self {
+        CsvStreamer { paths, batch_size }
+    }
 
-        let mut rdr = ReaderBuilder::new().has_headers(true).from_reader(file);
+    fn reader(&self) -> CsvFilesReader {
+        CsvFilesReader::new(self.paths.clone(), self.batch_size)
+    }
 
-        let mut edges = Vec::new();
+    /// Reads all files into memory. Prefer [`CsvFilesReader`] for large inputs.
+    pub fn parse_csv_to_edges(&self) -> Result<Vec<Edge>, Error> {
+        let mut reader = self.reader();
 
-        for result in rdr.deserialize() {
-            let record: CsvRecord = result?;
-            edges.push((record.from_node, record.to_node, record.rate_value));
+        let mut edges = Vec::new();
+        while let Some(batch) = reader.next_batch()? {
+            edges.extend(batch);
         }
         Ok(edges)
     }
@@ -50,15 +224,19 @@ impl CsvStreamer {
 
 #[async_trait::async_trait]
 impl UpdateStreamer for CsvStreamer {
-    async fn run_stream(self, sender: Sender<Vec<Edge>>) -> Result<(), Error> {
-        let all_edges = self.parse_csv_to_edges()?;
-        let total_edges = all_edges.len();
+    /// Streams the files to the writer one batch at a time as records are read,
+    /// so memory use is bounded by `batch_size` rather than the file sizes.
+    async fn run_stream(self, sender: UpdateSender) -> Result<(), Error> {
+        let mut reader = self.reader();
         let mut edges_sent = 0;
 
-        println!("CsvStreamer: Starting transfer of {} edges...", total_edges);
+        println!(
+            "CsvStreamer: Starting transfer from {}...",
+            self.paths.join(", ")
+        );
 
-        for chunk in all_edges.chunks(self.batch_size) {
-            let batch: Vec<Edge> = chunk.to_vec();
+        while let Some(batch) = reader.next_batch()? {
+            let size = batch.len();
             if let Err(e) = sender.send(batch).await {
                 eprintln!(
                     "CsvStreamer shutting down: Writer receiver dropped during send. Error: {}",
@@ -67,7 +245,7 @@ impl UpdateStreamer for CsvStreamer {
                 return Err(Error::ChannelSendFailed);
             }
 
-            edges_sent += chunk.len();
+            edges_sent += size;
         }
 
         println!(
@@ -81,6 +259,7 @@ impl UpdateStreamer for CsvStreamer {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
     use std::io::Write;
     use tempfile::NamedTempFile;
 
@@ -128,6 +307,93 @@ id,from,to,rate,pool_id,kind
         );
     }
 
+    #[test]
+    fn test_batch_reader_memory_is_bounded_for_large_file() {
+        const ROWS: usize = 100_000;
+        const SMALL_BATCH: usize = 64;
+
+        let mut temp_file = NamedTempFile::new().expect("Failed to create temp file");
+        writeln!(temp_file, "id,from,to,rate").unwrap();
+        for i in 0..ROWS {
+            writeln!(temp_file, "{},{},{},1.0001", i, i % 500, (i + 1) % 500).unwrap();
+        }
+        temp_file.flush().unwrap();
+
+        let path = temp_file.path().to_str().unwrap();
+        let mut reader = CsvBatchReader::open(path, SMALL_BATCH).unwrap();
+
+        let mut total = 0;
+        let mut batches = 0;
+        while let Some(batch) = reader.next_batch().unwrap() {
+            total += batch.len();
+            batches += 1;
+        }
+
+        assert_eq!(total, ROWS);
+        assert_eq!(batches, ROWS.div_ceil(SMALL_BATCH));
+        assert_eq!(reader.max_buffered, SMALL_BATCH);
+    }
+
+    #[tokio::test]
+    async fn test_run_stream_sends_batches_of_batch_size() {
+        let mut temp_file = NamedTempFile::new().expect("Failed to create temp file");
+        temp_file.write_all(MOCK_CSV_CONTENT.as_bytes()).unwrap();
+        let path = temp_file.path().to_str().unwrap().to_string();
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        CsvStreamer::new(path, 3)
+            .run_stream(tx.into())
+            .await
+            .unwrap();
+
+        let mut sizes = Vec::new();
+        while let Ok(batch) = rx.try_recv() {
+            sizes.push(batch.len());
+        }
+        assert_eq!(sizes, vec![3, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_run_stream_reads_every_file_in_order() {
+        let mut first = NamedTempFile::new().expect("Failed to create temp file");
+        first.write_all(MOCK_CSV_CONTENT.as_bytes()).unwrap();
+        let mut second = NamedTempFile::new().expect("Failed to create temp file");
+        second
+            .write_all(b"from,to,rate\n6,7,0.99\n7,5,1.01\n")
+            .unwrap();
+        let empty = NamedTempFile::new().expect("Failed to create temp file");
+
+        let path = |file: &NamedTempFile| file.path().to_str().unwrap().to_string();
+        let paths = vec![
+            path(&first),
+            "missing_feed.csv".to_string(),
+            path(&empty),
+            path(&second),
+        ];
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        CsvStreamer::with_paths(paths, BATCH_SIZE)
+            .run_stream(tx.into())
+            .await
+            .unwrap();
+
+        let mut edges = Vec::new();
+        while let Ok(batch) = rx.try_recv() {
+            edges.extend(batch);
+        }
+        assert_eq!(
+            edges,
+            vec![
+                (0, 1, 1.05),
+                (1, 2, 0.95),
+                (2, 0, 1.001),
+                (5, 6, 1.2),
+                (6, 7, 0.99),
+                (7, 5, 1.01),
+            ]
+        );
+    }
+
     #[test]
     fn test_parse_csv_to_edges_file_not_found() {
         let streamer = CsvStreamer::new("non_existent_file.csv".to_string(), BATCH_SIZE);
@@ -144,4 +410,109 @@ id,from,to,rate,pool_id,kind
             panic!("Expected IoError, got: {:?}", result.err());
         }
     }
+
+    #[test]
+    fn malformed_rows_are_errors_not_panics() {
+        for input in [
+            "from,to,rate\n0,1\n",
+            "from,to,rate\n0,1,1.0,extra\n",
+            "from,to,rate\n0,1,abc\n",
+            "from,to,rate\n-1,1,1.0\n",
+            "from,to,rate\n99999999999999999999999,1,1.0\n",
+            "from,to\n0,1\n",
+            "from,to,rate\n\u{0}\u{ff},\",\n",
+        ] {
+            assert!(
+                matches!(
+                    parse_csv_bytes(input.as_bytes()),
+                    Err(Error::CsvRowError { row: 1, .. })
+                ),
+                "expected a parse error for {:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_third_row_is_reported_with_its_position_and_content() {
+        let input = "from,to,rate\n0,1,1.05\n1,2,0.95\n2,zero,1.001\n3,0,1.0\n";
+
+        let err = parse_csv_bytes(input.as_bytes()).unwrap_err();
+        match &err {
+            Error::CsvRowError {
+                row, line, content, ..
+            } => {
+                assert_eq!(*row, 3);
+                assert_eq!(*line, 4);
+                assert_eq!(content, "2,zero,1.001");
+            }
+            other => panic!("Expected CsvRowError, got: {:?}", other),
+        }
+        assert!(err.to_string().contains("row 3"), "{}", err);
+    }
+
+    #[test]
+    fn bom_prefixed_csv_parses_like_the_plain_one() {
+        let with_bom = ["\u{feff}", MOCK_CSV_CONTENT].concat();
+
+        assert_eq!(
+            parse_csv_bytes(with_bom.as_bytes()).unwrap(),
+            parse_csv_bytes(MOCK_CSV_CONTENT.as_bytes()).unwrap()
+        );
+    }
+
+    #[test]
+    fn empty_and_header_only_inputs_parse_to_no_edges() {
+        assert_eq!(parse_csv_bytes(b"").unwrap(), vec![]);
+        assert_eq!(parse_csv_bytes(b"from,to,rate\n").unwrap(), vec![]);
+    }
+
+    proptest! {
+        /// Fuzz: arbitrary bytes never panic the parser.
+        #[test]
+        fn parse_csv_bytes_never_panics(data in prop::collection::vec(any::<u8>(), 0..512)) {
+            let _ = parse_csv_bytes(&data);
+        }
+
+        /// Fuzz: CSV-shaped input with a valid header and random cells never panics.
+        #[test]
+        fn parse_csv_rows_never_panic(
+            rows in prop::collection::vec(prop::collection::vec("[-0-9a-z.,\"eE]{0,12}", 0..6), 0..20)
+        ) {
+            let mut data = String::from("from,to,rate\n");
+            for row in rows {
+                data.push_str(&row.join(","));
+                data.push('\n');
+            }
+            let _ = parse_csv_bytes(data.as_bytes());
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        /// Fuzz: reading arbitrary bytes from a file, in small batches, never panics and
+        /// agrees with the in-memory parser.
+        #[test]
+        fn parse_csv_to_edges_matches_parse_csv_bytes(
+            data in prop::collection::vec(any::<u8>(), 0..512),
+            batch_size in 1..4usize,
+        ) {
+            let mut temp_file = NamedTempFile::new().expect("Failed to create temp file");
+            temp_file.write_all(&data).expect("Failed to write fuzz input");
+            let path = temp_file.path().to_str().expect("Failed to get path string");
+
+            let from_file = CsvStreamer::new(path.to_string(), batch_size).parse_csv_to_edges();
+            match (from_file, parse_csv_bytes(&data)) {
+                // Compare rates bitwise: a "NaN" cell parses to a rate unequal to itself.
+                (Ok(file_edges), Ok(edges)) => {
+                    let bits = |edges: Vec<Edge>| -> Vec<(usize, usize, u64)> {
+                        edges.into_iter().map(|(u, v, rate)| (u, v, rate.to_bits())).collect()
+                    };
+                    prop_assert_eq!(bits(file_edges), bits(edges))
+                }
+                (from_file, in_memory) => prop_assert_eq!(from_file.is_ok(), in_memory.is_ok()),
+            }
+        }
+    }
 }
@@ -16,6 +16,24 @@ pub enum Error {
     #[error("CSV data parsing error: {0}")]
     CsvParseError(#[from] csv::Error),
 
+    /// A data row (1-based, header excluded) that could not be read or deserialized.
+    #[error("CSV row {row} (line {line}) is malformed: {source}. Content: {content:?}")]
+    CsvRowError {
+        row: u64,
+        line: u64,
+        content: String,
+        source: csv::Error,
+    },
+
+    #[error("Background task failed: {0}")]
+    TaskJoinError(#[from] tokio::task::JoinError),
+
+    #[error("Snapshot serialization error: {0}")]
+    SnapshotError(#[from] serde_json::Error),
+
     #[error("I/O error: {0}")]
     IoError(#[from] std::io::Error),
+
+    #[error("Cycle sink delivery failed: {0}")]
+    SinkError(String),
 }
@@ -0,0 +1,168 @@
+use std::collections::{HashSet, VecDeque};
+
+use super::metrics::RejectionStats;
+use arb_solver_core::GraphCSR;
+use common::types::{Edge, WeightedCycle};
+
+/// Drops updates the graph cannot or should not hold, counting each reason.
+///
+/// An update is checked, in order, for a node id `>= max_nodes` (or one so large that the
+/// node count `id + 1` overflows), a self-loop, and
+/// a rate that maps to a non-finite weight under the pair's fee and `graph`'s transform; it is
+/// counted under the first reason that applies.
+pub fn validate_edges(
+    edges: Vec<Edge>,
+    graph: &GraphCSR,
+    max_nodes: usize,
+) -> (Vec<Edge>, RejectionStats) {
+    let mut stats = RejectionStats::default();
+
+    let valid = edges
+        .into_iter()
+        .filter(|&(u, v, rate)| {
+            if u.max(v)
+                .checked_add(1)
+                .is_none_or(|count| count > max_nodes)
+            {
+                stats.out_of_range += 1;
+            } else if u == v {
+                stats.self_loops += 1;
+            } else if !graph.is_valid_rate(u, v, rate) {
+                stats.non_finite += 1;
+            } else {
+                return true;
+            }
+            false
+        })
+        .collect();
+
+    (valid, stats)
+}
+
+/// [`validate_edges`] with all-or-nothing batches: a batch containing any invalid update
+/// is dropped entirely, leaving none of its updates to apply.
+///
+/// `batch_lens` splits `edges` into the batches as received, in order. Returns the
+/// updates of the fully valid batches, the counts of the invalid updates only, and the
+/// number of dropped batches.
+///
+/// Batch boundaries end here: the returned updates are one flat list, so nothing
+/// downstream can commit or roll back a batch as a unit.
+pub fn validate_batches(
+    edges: Vec<Edge>,
+    batch_lens: &[usize],
+    graph: &GraphCSR,
+    max_nodes: usize,
+) -> (Vec<Edge>, RejectionStats, usize) {
+    debug_assert_eq!(batch_lens.iter().sum::<usize>(), edges.len());
+
+    let mut valid = Vec::with_capacity(edges.len());
+    let mut stats = RejectionStats::default();
+    let mut dropped = 0;
+    let mut rest = edges.into_iter();
+    for &len in batch_lens {
+        let (batch, rejected) = validate_edges(rest.by_ref().take(len).collect(), graph, max_nodes);
+        if rejected.total() == 0 {
+            valid.extend(batch);
+        } else {
+            stats.add(&rejected);
+            dropped += 1;
+        }
+    }
+
+    (valid, stats, dropped)
+}
+
+/// Suppresses repeated reports of the same arbitrage loop.
+///
+/// Cycles are identified by their canonical key, so the same loop reported from a
+/// different starting node (or, with `undirected`, in the reverse direction) is
+/// treated as a duplicate. Only the `capacity` most recently seen keys are
+/// remembered; older keys are evicted in FIFO order to bound memory.
+pub struct CycleDeduplicator {
+    seen: HashSet<Vec<usize>>,
+    order: VecDeque<Vec<usize>>,
+    capacity: usize,
+    undirected: bool,
+}
+
+impl CycleDeduplicator {
+    pub fn new(capacity: usize, undirected: bool) -> Self {
+        Self {
+            seen: HashSet::with_capacity(capacity),
+            order: VecDeque::with_capacity(capacity),
+            capacity,
+            undirected,
+        }
+    }
+
+    /// Returns `true` if the cycle has not been seen recently, remembering it.
+    pub fn is_new(&mut self, cycle: &WeightedCycle) -> bool {
+        if self.capacity == 0 {
+            return true;
+        }
+
+        let key = if self.undirected {
+            cycle.undirected_key()
+        } else {
+            cycle.canonical_key()
+        };
+
+        if self.seen.contains(&key) {
+            return false;
+        }
+
+        if self.order.len() >= self.capacity
+            && let Some(oldest) = self.order.pop_front()
+        {
+            self.seen.remove(&oldest);
+        }
+
+        self.seen.insert(key.clone());
+        self.order.push_back(key);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cycle(path: Vec<(usize, usize, f64)>) -> WeightedCycle {
+        let rates: Vec<f64> = path.iter().map(|&(_, _, r)| r).collect();
+        WeightedCycle {
+            log_rate_sum: rates.iter().map(|r| -r.ln()).sum(),
+            path,
+            rates,
+        }
+    }
+
+    #[test]
+    fn rotations_are_deduplicated() {
+        let mut dedup = CycleDeduplicator::new(8, false);
+
+        assert!(dedup.is_new(&cycle(vec![(0, 1, 1.1), (1, 2, 1.0), (2, 0, 1.0)])));
+        assert!(!dedup.is_new(&cycle(vec![(2, 0, 1.0), (0, 1, 1.1), (1, 2, 1.0)])));
+        // Reverse direction is a different directed loop.
+        assert!(dedup.is_new(&cycle(vec![(0, 2, 1.0), (2, 1, 1.0), (1, 0, 0.9)])));
+    }
+
+    #[test]
+    fn undirected_mode_deduplicates_reversals() {
+        let mut dedup = CycleDeduplicator::new(8, true);
+
+        assert!(dedup.is_new(&cycle(vec![(0, 1, 1.1), (1, 2, 1.0), (2, 0, 1.0)])));
+        assert!(!dedup.is_new(&cycle(vec![(1, 0, 0.9), (0, 2, 1.0), (2, 1, 1.0)])));
+    }
+
+    #[test]
+    fn oldest_keys_are_evicted_at_capacity() {
+        let mut dedup = CycleDeduplicator::new(1, false);
+        let a = cycle(vec![(0, 1, 1.1), (1, 0, 1.0)]);
+        let b = cycle(vec![(2, 3, 1.1), (3, 2, 1.0)]);
+
+        assert!(dedup.is_new(&a));
+        assert!(dedup.is_new(&b));
+        assert!(dedup.is_new(&a));
+    }
+}
@@ -0,0 +1,177 @@
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+
+use common::types::WeightedCycle;
+
+use super::error::Error;
+use super::opportunities::OpportunityBook;
+
+/// Largest request head accepted; the endpoint takes no body.
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+/// Minimal HTTP/1.1 server exposing the searcher's opportunities for dashboards.
+///
+/// Routes:
+/// - `GET /opportunities`: the currently-known (unexpired) opportunities as a JSON array.
+/// - `GET /best`: the most profitable cycle of the latest scan, or `null`.
+///
+/// Anything else gets a `404`. Every response closes the connection.
+pub struct HttpServer {
+    listener: TcpListener,
+    opportunities: Arc<OpportunityBook>,
+    best: watch::Receiver<Option<WeightedCycle>>,
+}
+
+impl HttpServer {
+    pub async fn bind(addr: &str, opportunities: Arc<OpportunityBook>) -> Result<Self, Error> {
+        let listener = TcpListener::bind(addr).await?;
+        Ok(Self {
+            listener,
+            opportunities,
+            // Serves `null` until a searcher's channel is attached.
+            best: watch::channel(None).1,
+        })
+    }
+
+    /// Serves the latest value of `best` (see `ArbSearcher::with_best_opportunity_channel`).
+    pub fn with_best_opportunity(mut self, best: watch::Receiver<Option<WeightedCycle>>) -> Self {
+        self.best = best;
+        self
+    }
+
+    pub fn local_addr(&self) -> Result<std::net::SocketAddr, Error> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Accepts connections forever, serving each on its own task.
+    pub async fn run(self) -> Result<(), Error> {
+        println!("HTTP server listening on {}.", self.local_addr()?);
+
+        loop {
+            let (stream, _) = self.listener.accept().await?;
+            let opportunities = self.opportunities.clone();
+            let best = self.best.clone();
+            tokio::spawn(async move {
+                if let Err(e) = handle(stream, &opportunities, &best).await {
+                    eprintln!("HTTP Error: {}. Continuing.", e);
+                }
+            });
+        }
+    }
+}
+
+async fn handle(
+    mut stream: TcpStream,
+    opportunities: &OpportunityBook,
+    best: &watch::Receiver<Option<WeightedCycle>>,
+) -> Result<(), Error> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") && head.len() < MAX_REQUEST_BYTES {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        head.extend_from_slice(&buf[..n]);
+    }
+
+    let request_line = head.split(|&b| b == b'\r').next().unwrap_or_default();
+    let (status, body) = match request_line {
+        b"GET /opportunities HTTP/1.1" | b"GET /opportunities HTTP/1.0" => {
+            ("200 OK", serde_json::to_string(&opportunities.current())?)
+        }
+        b"GET /best HTTP/1.1" | b"GET /best HTTP/1.0" => {
+            ("200 OK", serde_json::to_string(&*best.borrow())?)
+        }
+        _ => ("404 Not Found", "{\"error\":\"not found\"}".to_string()),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ScanMode, SearcherConfig, SourceMode};
+    use crate::metrics::Metrics;
+    use crate::searcher::ArbSearcher;
+    use arb_solver_core::{GraphCSR, solver::SPFASolver};
+    use tokio::sync::RwLock;
+
+    async fn get(addr: std::net::SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let request = format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path);
+        stream.write_all(request.as_bytes()).await.unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn opportunities_endpoint_lists_scanned_cycle() {
+        let mut edges = vec![(0, 1, 0.5), (1, 2, 1.0), (2, 0, 2.1), (2, 3, 0.9)];
+        let graph = Arc::new(RwLock::new(GraphCSR::from_edges(4, &mut edges, 10)));
+        let config = SearcherConfig {
+            interval_seconds: 1,
+            dedup_capacity: 16,
+            dedup_undirected: false,
+            min_cycle_len: 3,
+            fair_scheduling: false,
+            warmup_seconds: 0,
+            workers: 1,
+            opportunity_ttl_ms: Some(60_000),
+            sink_retry_capacity: 16,
+            sink_max_attempts: 5,
+            sink_retry_backoff_ms: 100,
+            scan_mode: ScanMode::Full,
+            source_mode: SourceMode::AllNodes,
+            min_edge_move_bps: None,
+            edge_move_window_ms: 10_000,
+            lock_timeout_ms: None,
+            near_miss_margin_bps: None,
+            base_nodes: Vec::new(),
+            search_budget_ms: None,
+            min_liquidity: None,
+            edge_liquidity: Vec::new(),
+        };
+
+        let mut searcher = ArbSearcher::new(graph, config, SPFASolver, Arc::new(Metrics::new()));
+        let cycle = searcher.scan_once().await.unwrap().expect("Cycle expected");
+        searcher.emit(cycle).expect("Cycle should be emitted");
+
+        let server = HttpServer::bind("127.0.0.1:0", searcher.opportunities())
+            .await
+            .unwrap();
+        let addr = server.local_addr().unwrap();
+        let handle = tokio::spawn(server.run());
+
+        let response = get(addr, "/opportunities").await;
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("HTTP/1.1 200 OK"));
+
+        let json: serde_json::Value = serde_json::from_str(body).unwrap();
+        let listed = json.as_array().expect("JSON array expected");
+        assert_eq!(listed.len(), 1);
+        let path: Vec<u64> = listed[0]["cycle"]["path"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hop| hop[0].as_u64().unwrap())
+            .collect();
+        assert_eq!(path, vec![0, 1, 2]);
+
+        assert!(get(addr, "/nope").await.starts_with("HTTP/1.1 404"));
+        handle.abort();
+    }
+}
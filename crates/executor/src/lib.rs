@@ -0,0 +1,17 @@
+pub mod analyze;
+pub mod clock;
+pub mod config;
+pub mod csv_streamer;
+pub mod error;
+pub mod filter;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod metrics;
+pub mod opportunities;
+pub mod producer;
+pub mod searcher;
+pub mod sim_streamer;
+pub mod sink;
+pub mod snapshot;
+pub mod types;
+pub mod writer;
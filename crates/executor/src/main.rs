@@ -1,75 +1,244 @@
-pub mod config;
-pub mod csv_streamer;
-pub mod error;
-pub mod producer;
-pub mod searcher;
-pub mod sim_streamer;
-pub mod types;
-pub mod writer;
-
 use std::env;
 use std::sync::Arc;
-use tokio::sync::{RwLock, mpsc, mpsc::Sender};
+use std::time::Duration;
+use tokio::sync::{RwLock, mpsc, watch};
 use tokio::task::JoinHandle;
 
 use arb_solver_core::GraphCSR;
-use arb_solver_core::solver::SPFASolver;
-use common::types::Edge;
+use arb_solver_core::csr::{AdaptiveLimit, EdgeRatio};
+use arb_solver_core::solver::{FairSPFASolver, SPFASolver, SingleSourceSPFASolver};
+use common::types::WeightedCycle;
+use config::SourceMode;
 use csv_streamer::CsvStreamer;
+#[cfg(feature = "http")]
+use executor::http;
+use executor::{
+    analyze, config, csv_streamer, metrics, opportunities, producer, searcher, sim_streamer, sink,
+    snapshot, types, writer,
+};
+use metrics::Metrics;
+use opportunities::OpportunityBook;
 use producer::Producer;
 use searcher::ArbSearcher;
 use sim_streamer::SimulatorStreamer;
-use types::{DataSource, JoinHandleResult, SharedGraph};
+use sink::{CycleSink, FileSink};
+use types::{
+    AsyncGraphSolver, DataSource, DirtyNodes, JoinHandleResult, SharedGraph, UpdateReceiver,
+    UpdateSender,
+};
 use writer::Writer;
 
 const REBUILD_LIMIT: usize = 100;
+/// Near-misses buffered for the logger before further ones are dropped.
+const NEAR_MISS_BUFFER: usize = 256;
 
 #[tokio::main]
 async fn main() {
-    let source = parse_args();
+    let args = parse_args();
     let config = config::load_config().expect("Failed to load config");
 
-    let shared_graph = Arc::new(RwLock::new(GraphCSR::from_edges(0, &mut [], REBUILD_LIMIT)));
+    let shared_graph = Arc::new(RwLock::new(initial_graph(&args, &config)));
+    let metrics = Arc::new(
+        Metrics::new()
+            .with_edge_move_window(Duration::from_millis(config.searcher.edge_move_window_ms)),
+    );
+    let opportunities = Arc::new(OpportunityBook::new());
+    let dirty = Arc::new(DirtyNodes::new());
+    let (best_sender, best) = watch::channel(None);
+    let (shutdown_sender, shutdown) = watch::channel(false);
 
-    let (sender, receiver) = mpsc::channel::<Vec<Edge>>(config.executor.buffer_size);
+    let (sender, receiver) =
+        types::update_channel(config.executor.channel, config.executor.buffer_size);
 
     // Spawn tasks
-    let producer_handle = spawn_producer(&source, sender, &config);
-    let writer_handle = spawn_writer(shared_graph.clone(), receiver, config.writer.batch_capacity);
-    let searcher_handle = spawn_searcher(shared_graph.clone(), config.searcher.interval_seconds);
+    let mut producer_handle = spawn_producer(&args.source, sender, &config);
+    let writer_handle = spawn_writer(
+        shared_graph.clone(),
+        receiver,
+        config.writer.clone(),
+        metrics.clone(),
+        dirty.clone(),
+    );
+    let searcher_handle = spawn_searcher(
+        shared_graph.clone(),
+        config.searcher.clone(),
+        metrics.clone(),
+        SearcherLinks {
+            opportunities: opportunities.clone(),
+            dirty,
+            best: best_sender,
+            sink: build_sink(&config.sink),
+            shutdown,
+        },
+    );
+    let _snapshot_handle = spawn_snapshotter(shared_graph.clone(), &config.snapshot);
+    let http_handle = spawn_http(&config.http, opportunities, best).await;
+
+    // Run until Ctrl-C or the end of the feed. Dropping the producer closes the
+    // channel, so the writer commits whatever is still pending before it exits.
+    let interrupted = tokio::select! {
+        _ = tokio::signal::ctrl_c() => {
+            println!("Interrupted, shutting down...");
+            producer_handle.abort();
+            true
+        }
+        _ = &mut producer_handle => false,
+    };
+    let _ = writer_handle.await;
 
-    let _ = tokio::join!(writer_handle, searcher_handle, producer_handle);
+    // The searcher scans the final graph once more before it stops.
+    let _ = shutdown_sender.send(true);
+    let _ = searcher_handle.await;
+
+    // A finished feed leaves the HTTP endpoints serving the final results until Ctrl-C.
+    if let Some(http_handle) = http_handle {
+        if !interrupted {
+            println!("Feed finished; serving results until Ctrl-C...");
+            let _ = tokio::signal::ctrl_c().await;
+        }
+        http_handle.abort();
+    }
 
     println!("Pipeline shut down.");
+    if config.report.on_shutdown {
+        println!("{}", metrics.run_report());
+    }
 }
 
-/// Parse command-line arguments to determine data source
-fn parse_args() -> DataSource {
-    let args: Vec<String> = env::args().collect();
+/// Command-line arguments accepted by the executor.
+pub struct CliArgs {
+    pub source: DataSource,
+    pub snapshot: Option<String>,
+}
+
+/// Builds the graph the pipeline starts from.
+///
+/// If `--snapshot` was given, the saved graph is loaded so the searcher can find
+/// cycles immediately; the configured `REBUILD_LIMIT`, dedup policy and key, unit-rate handling,
+/// rate clamp and pending ceiling replace the saved ones.
+/// Otherwise an empty graph is created with the configured fee.
+///
+/// Configured per-pair fees, rebuild policy (adaptive or ratio) and graph limits are
+/// applied in both cases.
+fn initial_graph(args: &CliArgs, config: &config::Config) -> GraphCSR {
+    let mut graph = match &args.snapshot {
+        Some(path) => {
+            println!("Loading graph snapshot from {}...", path);
+            let mut graph = snapshot::load_snapshot(path).expect("Failed to load snapshot");
+            graph.rebuild_limit = REBUILD_LIMIT;
+            graph.dedup_policy = config.graph.dedup_policy;
+            graph.dedup_key = config.graph.dedup_key;
+            graph = graph
+                .with_drop_unit_rates(config.graph.drop_unit_rates)
+                .with_max_out_degree(config.graph.max_out_degree)
+                .with_rate_clamp(config.graph.rate_clamp)
+                .with_max_pending(config.graph.max_pending);
+            println!(
+                "Snapshot loaded: {} nodes, {} edges.",
+                graph.num_nodes,
+                graph.edge_targets.len()
+            );
+            graph
+        }
+        None => GraphCSR::from_edges_with_fee(0, &mut [], REBUILD_LIMIT, config.graph.fee_bps)
+            .with_dedup_policy(config.graph.dedup_policy)
+            .with_dedup_key(config.graph.dedup_key)
+            .with_drop_unit_rates(config.graph.drop_unit_rates)
+            .with_max_out_degree(config.graph.max_out_degree)
+            .with_rate_clamp(config.graph.rate_clamp)
+            .with_max_pending(config.graph.max_pending),
+    };
+
+    for fee in &config.graph.edge_fees {
+        graph
+            .set_edge_fee_bps(fee.from, fee.to, fee.fee_bps)
+            .expect("Edge fees are validated when the config is loaded");
+    }
+    if let Some(adaptive) = config.graph.adaptive_rebuild {
+        graph.set_rebuild_policy(AdaptiveLimit::new(
+            adaptive.min_limit,
+            adaptive.max_limit,
+            std::time::Duration::from_millis(adaptive.target_interval_ms),
+        ));
+    } else if let Some(ratio) = config.graph.rebuild_ratio {
+        graph.set_rebuild_policy(EdgeRatio(ratio));
+    }
+    graph
+}
+
+/// Parse command-line arguments to determine data source and optional snapshot.
+///
+/// `analyze <csv> [--format json|csv|table]` is handled here directly: it runs the
+/// offline analysis and exits.
+fn parse_args() -> CliArgs {
+    let mut args: Vec<String> = env::args().collect();
+
+    let format = match args.iter().position(|a| a == "--format") {
+        Some(pos) => {
+            let format = args
+                .get(pos + 1)
+                .expect("Output format required after --format")
+                .parse::<analyze::OutputFormat>()
+                .unwrap_or_else(|e| {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                });
+            args.drain(pos..=pos + 1);
+            format
+        }
+        None => analyze::OutputFormat::default(),
+    };
+
+    let snapshot = match args.iter().position(|a| a == "--snapshot") {
+        Some(pos) => {
+            let path = args
+                .get(pos + 1)
+                .expect("Snapshot path required after --snapshot")
+                .clone();
+            args.drain(pos..=pos + 1);
+            Some(path)
+        }
+        None => None,
+    };
+
     let source = args
         .get(1)
         .map(|s| s.to_lowercase())
         .unwrap_or_else(|| "sim".to_string());
 
-    match source.as_str() {
+    let source = match source.as_str() {
+        "analyze" => {
+            let path = args
+                .get(2)
+                .expect("CSV path required for analyze mode")
+                .clone();
+            if let Err(e) = analyze::run(&path, format) {
+                eprintln!("Analyze failed: {}", e);
+                std::process::exit(1);
+            }
+            std::process::exit(0);
+        }
         "sim" => DataSource::SIM,
         "csv" => {
-            let path = args.get(2).expect("CSV path required for CSV mode").clone();
-            DataSource::CSV(path)
+            let paths = args[2..].to_vec();
+            assert!(!paths.is_empty(), "CSV path required for CSV mode");
+            DataSource::CSV(paths)
         }
         _ => {
             eprintln!(
-                "Usage: {} <SIM|CSV> [path_to_csv]\n  - SIM: run simulated data stream\n  - CSV: read updates from a CSV file",
+                "Usage: {} <SIM|CSV|ANALYZE> [path_to_csv...] [--snapshot <path>] [--format <json|csv|table>]\n  - SIM: run simulated data stream\n  - CSV: read updates from one or more CSV files, in order\n  - ANALYZE: print all profitable cycles in a CSV file and exit\n  - --snapshot: warm-start from a saved graph snapshot\n  - --format: ANALYZE output format (default: table)",
                 args[0]
             );
             std::process::exit(1);
         }
-    }
+    };
+
+    CliArgs { source, snapshot }
 }
 
 pub fn spawn_producer(
     source: &DataSource,
-    sender: Sender<Vec<Edge>>,
+    sender: UpdateSender,
     config: &config::Config,
 ) -> JoinHandle<()> {
     match source {
@@ -79,9 +248,9 @@ pub fn spawn_producer(
             let producer = Producer::new(streamer);
             producer.spawn(sender)
         }
-        DataSource::CSV(path) => {
+        DataSource::CSV(paths) => {
             println!("Starting CsvStreamer producer task...");
-            let streamer = CsvStreamer::new(path.clone(), config.producer.batch_size);
+            let streamer = CsvStreamer::with_paths(paths.clone(), config.producer.batch_size);
             let producer = Producer::new(streamer);
             producer.spawn(sender)
         }
@@ -91,15 +260,127 @@ pub fn spawn_producer(
 /// Spawn writer task
 fn spawn_writer(
     shared_graph: SharedGraph,
-    receiver: mpsc::Receiver<Vec<Edge>>,
-    batch_capacity: usize,
+    receiver: UpdateReceiver,
+    config: config::WriterConfig,
+    metrics: Arc<Metrics>,
+    dirty: Arc<DirtyNodes>,
 ) -> JoinHandleResult {
-    let writer = Writer::new(shared_graph, receiver, batch_capacity);
+    let writer = Writer::new(shared_graph, receiver, config, metrics).with_dirty_nodes(dirty);
     tokio::spawn(writer.process_updates())
 }
 
+/// Pipeline state the searcher shares with the other tasks.
+struct SearcherLinks {
+    opportunities: Arc<OpportunityBook>,
+    dirty: Arc<DirtyNodes>,
+    best: watch::Sender<Option<WeightedCycle>>,
+    sink: Option<Arc<dyn CycleSink>>,
+    shutdown: watch::Receiver<bool>,
+}
+
 /// Spawn searcher task
-fn spawn_searcher(shared_graph: Arc<RwLock<GraphCSR>>, interval_seconds: u64) -> JoinHandleResult {
-    let searcher = ArbSearcher::new(shared_graph, interval_seconds, SPFASolver);
+///
+/// Fair scheduling seeds every node, so it takes precedence over `source_mode`.
+fn spawn_searcher(
+    shared_graph: Arc<RwLock<GraphCSR>>,
+    config: config::SearcherConfig,
+    metrics: Arc<Metrics>,
+    links: SearcherLinks,
+) -> JoinHandleResult {
+    let near_misses = config
+        .near_miss_margin_bps
+        .map(|_| spawn_near_miss_logger());
+    if config.fair_scheduling {
+        let searcher = ArbSearcher::new(shared_graph, config, FairSPFASolver, metrics);
+        run_searcher(searcher, links, near_misses)
+    } else if config.source_mode == SourceMode::RoundRobin {
+        let searcher = ArbSearcher::new(shared_graph, config, SingleSourceSPFASolver, metrics);
+        run_searcher(searcher, links, near_misses)
+    } else {
+        let searcher = ArbSearcher::new(shared_graph, config, SPFASolver, metrics);
+        run_searcher(searcher, links, near_misses)
+    }
+}
+
+/// Attaches the shared pipeline state to `searcher` and spawns its search loop.
+fn run_searcher<S: AsyncGraphSolver>(
+    searcher: ArbSearcher<S>,
+    links: SearcherLinks,
+    near_misses: Option<mpsc::Sender<WeightedCycle>>,
+) -> JoinHandleResult {
+    let mut searcher = searcher
+        .with_opportunity_book(links.opportunities)
+        .with_dirty_nodes(links.dirty)
+        .with_best_opportunity_channel(links.best)
+        .with_shutdown(links.shutdown);
+    if let Some(near_misses) = near_misses {
+        searcher = searcher.with_near_miss_channel(near_misses);
+    }
+    if let Some(sink) = links.sink {
+        searcher = searcher.with_sink(sink);
+    }
     tokio::spawn(async move { searcher.seacrh_for_arbs().await })
 }
+
+/// Builds the opportunity sink, if a sink path is configured
+fn build_sink(config: &config::SinkConfig) -> Option<Arc<dyn CycleSink>> {
+    let path = config.path.as_ref()?;
+    println!("Delivering opportunities to {}...", path);
+    Some(Arc::new(FileSink::new(path)))
+}
+
+/// Spawns a task logging near-miss cycles, returning the channel that feeds it.
+fn spawn_near_miss_logger() -> mpsc::Sender<WeightedCycle> {
+    let (sender, mut receiver) = mpsc::channel::<WeightedCycle>(NEAR_MISS_BUFFER);
+    tokio::spawn(async move {
+        while let Some(cycle) = receiver.recv().await {
+            println!(
+                "NEAR MISS: Path {:?}, gross profit {:.4}% (unprofitable after fees)",
+                cycle.nodes(),
+                (cycle.product_rate() - 1.0) * 100.0
+            );
+        }
+    });
+    sender
+}
+
+/// Spawn the `/opportunities` and `/best` HTTP endpoints, if a bind address is configured
+#[cfg(feature = "http")]
+async fn spawn_http(
+    config: &config::HttpConfig,
+    opportunities: Arc<OpportunityBook>,
+    best: watch::Receiver<Option<WeightedCycle>>,
+) -> Option<JoinHandleResult> {
+    let addr = config.bind.as_ref()?;
+    let server = http::HttpServer::bind(addr, opportunities)
+        .await
+        .expect("Failed to bind HTTP server")
+        .with_best_opportunity(best);
+    Some(tokio::spawn(server.run()))
+}
+
+#[cfg(not(feature = "http"))]
+async fn spawn_http(
+    config: &config::HttpConfig,
+    _opportunities: Arc<OpportunityBook>,
+    _best: watch::Receiver<Option<WeightedCycle>>,
+) -> Option<JoinHandleResult> {
+    if config.bind.is_some() {
+        eprintln!("http.bind is set but the executor was built without the `http` feature.");
+    }
+    None
+}
+
+/// Spawn the periodic snapshot task, if enabled
+fn spawn_snapshotter(
+    shared_graph: SharedGraph,
+    config: &config::SnapshotConfig,
+) -> Option<JoinHandleResult> {
+    if config.interval_seconds == 0 {
+        return None;
+    }
+
+    let snapshotter =
+        snapshot::Snapshotter::new(shared_graph, &config.path, config.interval_seconds);
+    Some(tokio::spawn(snapshotter.run()))
+}
@@ -0,0 +1,324 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use tokio::time::{Duration, Instant};
+
+use super::clock::{Clock, TokioClock};
+use common::types::{Edge, WeightedCycle};
+
+/// Summary of observed edge-update-to-detection latencies.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LatencyStats {
+    pub count: u64,
+    pub last: Duration,
+    pub max: Duration,
+    pub total: Duration,
+}
+
+impl LatencyStats {
+    /// Mean latency over all recorded detections, or zero if none were recorded.
+    pub fn mean(&self) -> Duration {
+        match self.count {
+            0 => Duration::ZERO,
+            n => self.total / n as u32,
+        }
+    }
+
+    fn record(&mut self, latency: Duration) {
+        self.count += 1;
+        self.last = latency;
+        self.max = self.max.max(latency);
+        self.total += latency;
+    }
+}
+
+/// Counts of edge updates dropped by the writer's validation, per reason.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RejectionStats {
+    /// Node id at or above the configured `max_nodes`.
+    pub out_of_range: u64,
+    /// `u -> u` edges, which can never be part of an arbitrage loop.
+    pub self_loops: u64,
+    /// Rates whose weight is NaN or infinite (e.g. zero or negative rates).
+    pub non_finite: u64,
+}
+
+impl RejectionStats {
+    pub fn total(&self) -> u64 {
+        self.out_of_range + self.self_loops + self.non_finite
+    }
+
+    pub fn add(&mut self, other: &RejectionStats) {
+        self.out_of_range += other.out_of_range;
+        self.self_loops += other.self_loops;
+        self.non_finite += other.non_finite;
+    }
+}
+
+/// Window edge moves are measured over unless set with [`Metrics::with_edge_move_window`].
+const DEFAULT_EDGE_MOVE_WINDOW: Duration = Duration::from_secs(10);
+
+/// `(tick, rate)` quotes of one edge, oldest first.
+type Quotes = VecDeque<(u64, f64)>;
+
+/// Rate change of one `(from, to)` edge over the edge-move window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EdgeMove {
+    /// Latest quoted rate.
+    pub rate: f64,
+    /// Relative change of the latest quote versus the rate in effect when the window
+    /// started (`0.01` = +1%), so a drift in small steps adds up. An edge first quoted
+    /// inside the window is measured from that first quote.
+    pub delta: f64,
+    /// Tick of the latest quote.
+    pub tick: u64,
+}
+
+/// Run totals printed as a summary when the pipeline shuts down.
+#[derive(Debug, Clone, Default)]
+pub struct RunReport {
+    /// Searcher scans started (including incremental scans with nothing to do).
+    pub scans: u64,
+    /// Cycles reported as new, i.e. not suppressed by the deduplicator.
+    pub distinct_cycles: u64,
+    /// Most profitable reported cycle (highest product rate).
+    pub best_cycle: Option<WeightedCycle>,
+    /// Valid edge updates committed to the graph by a rebuild.
+    pub edges_processed: u64,
+    /// Successful graph rebuilds.
+    pub rebuilds: u64,
+}
+
+impl fmt::Display for RunReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "--- Run Report ---")?;
+        writeln!(f, "Scans: {}", self.scans)?;
+        writeln!(f, "Distinct cycles found: {}", self.distinct_cycles)?;
+        match &self.best_cycle {
+            Some(cycle) => writeln!(
+                f,
+                "Best cycle: {:?} (product rate {:.6}, profit {:.4}%)",
+                cycle.nodes(),
+                cycle.product_rate(),
+                (cycle.product_rate() - 1.0) * 100.0
+            )?,
+            None => writeln!(f, "Best cycle: none")?,
+        }
+        writeln!(f, "Edges processed: {}", self.edges_processed)?;
+        write!(f, "Rebuilds: {}", self.rebuilds)
+    }
+}
+
+/// Pipeline-wide metrics shared (via `Arc`) between the writer and the searcher.
+///
+/// Ticks are microseconds on a monotonic clock started when the metrics are created,
+/// so they are strictly comparable across tasks and unaffected by wall-clock changes.
+pub struct Metrics {
+    clock: Arc<dyn Clock>,
+    start: Instant,
+    /// Tick of the most recent update seen for each `(from, to)` edge.
+    edge_ticks: Mutex<HashMap<(usize, usize), u64>>,
+    /// Quotes of each `(from, to)` edge within the edge-move window,
+    /// oldest first, plus the last quote before it as the window's starting rate.
+    edge_quotes: Mutex<HashMap<(usize, usize), Quotes>>,
+    edge_move_window: Duration,
+    detection_latency: Mutex<LatencyStats>,
+    rejections: Mutex<RejectionStats>,
+    /// Opportunities the sink never accepted (out of retries or evicted).
+    dropped_deliveries: Mutex<u64>,
+    /// Valid updates lost to a failed rebuild.
+    dropped_updates: Mutex<u64>,
+    run: Mutex<RunReport>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            clock: Arc::new(TokioClock),
+            start: Instant::now(),
+            edge_ticks: Mutex::new(HashMap::new()),
+            edge_quotes: Mutex::new(HashMap::new()),
+            edge_move_window: DEFAULT_EDGE_MOVE_WINDOW,
+            detection_latency: Mutex::new(LatencyStats::default()),
+            rejections: Mutex::new(RejectionStats::default()),
+            dropped_deliveries: Mutex::new(0),
+            dropped_updates: Mutex::new(0),
+            run: Mutex::new(RunReport::default()),
+        }
+    }
+
+    /// Measures edge moves over `window` instead of the default 10 seconds.
+    pub fn with_edge_move_window(mut self, window: Duration) -> Self {
+        self.edge_move_window = window;
+        self
+    }
+
+    /// Replaces the clock ticks are read from (e.g. with a mock in tests), restarting
+    /// ticks at 0.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.start = clock.now();
+        self.clock = clock;
+        self
+    }
+
+    /// Current monotonic tick (microseconds since the metrics were created).
+    pub fn now_tick(&self) -> u64 {
+        (self.clock.now() - self.start).as_micros() as u64
+    }
+
+    /// Tick at which the edge-move window ending now started.
+    fn window_start(&self) -> u64 {
+        self.now_tick()
+            .saturating_sub(self.edge_move_window.as_micros() as u64)
+    }
+
+    /// Stamps every edge in `updates` with the current tick and records its quote for
+    /// the edge-move window.
+    pub fn stamp_updates(&self, updates: &[Edge]) {
+        let tick = self.now_tick();
+        let since = self.window_start();
+        let mut edge_ticks = self.edge_ticks.lock().unwrap();
+        let mut edge_quotes = self.edge_quotes.lock().unwrap();
+        for &(u, v, rate) in updates {
+            edge_ticks.insert((u, v), tick);
+            let quotes = edge_quotes.entry((u, v)).or_default();
+            quotes.push_back((tick, rate));
+            // Keep only the newest quote at or before the window start.
+            while quotes.get(1).is_some_and(|&(t, _)| t <= since) {
+                quotes.pop_front();
+            }
+        }
+    }
+
+    /// Rate change of the `from -> to` edge over the edge-move window, if it was ever
+    /// quoted.
+    pub fn edge_move(&self, from: usize, to: usize) -> Option<EdgeMove> {
+        let since = self.window_start();
+        let edge_quotes = self.edge_quotes.lock().unwrap();
+        let quotes = edge_quotes.get(&(from, to))?;
+        let &(tick, rate) = quotes.back()?;
+        let start = quotes
+            .iter()
+            .rev()
+            .find(|&&(t, _)| t <= since)
+            .or(quotes.front())
+            .map_or(rate, |&(_, start)| start);
+        let delta = if start != 0.0 {
+            (rate - start) / start
+        } else {
+            0.0
+        };
+        Some(EdgeMove { rate, delta, tick })
+    }
+
+    /// Returns true if some edge of `cycle` moved by at least `min_move` (relative,
+    /// either direction) over the edge-move window.
+    pub fn has_recent_move(&self, cycle: &WeightedCycle, min_move: f64) -> bool {
+        cycle.path.iter().any(|&(u, v, _)| {
+            self.edge_move(u, v)
+                .is_some_and(|m| m.delta.abs() >= min_move)
+        })
+    }
+
+    /// Returns the newest update tick among the edges of `cycle`, i.e. the update
+    /// that completed the opportunity.
+    pub fn contributing_tick(&self, cycle: &WeightedCycle) -> Option<u64> {
+        let edge_ticks = self.edge_ticks.lock().unwrap();
+        cycle
+            .path
+            .iter()
+            .filter_map(|&(u, v, _)| edge_ticks.get(&(u, v)).copied())
+            .max()
+    }
+
+    /// Records `now - contributing_tick` for an emitted cycle.
+    ///
+    /// Returns the recorded latency, or `None` if none of the cycle's edges were stamped.
+    pub fn record_detection(&self, cycle: &WeightedCycle) -> Option<Duration> {
+        let contributing = self.contributing_tick(cycle)?;
+        let latency = Duration::from_micros(self.now_tick().saturating_sub(contributing));
+
+        self.detection_latency.lock().unwrap().record(latency);
+        Some(latency)
+    }
+
+    /// Snapshot of the detection latency statistics.
+    pub fn detection_latency(&self) -> LatencyStats {
+        *self.detection_latency.lock().unwrap()
+    }
+
+    /// Adds `batch` to the cumulative rejection counters and returns the new totals.
+    pub fn record_rejections(&self, batch: &RejectionStats) -> RejectionStats {
+        let mut rejections = self.rejections.lock().unwrap();
+        rejections.add(batch);
+        *rejections
+    }
+
+    /// Snapshot of the cumulative rejection counters.
+    pub fn rejections(&self) -> RejectionStats {
+        *self.rejections.lock().unwrap()
+    }
+
+    /// Adds `count` undeliverable opportunities and returns the new total.
+    pub fn record_dropped_deliveries(&self, count: u64) -> u64 {
+        let mut dropped = self.dropped_deliveries.lock().unwrap();
+        *dropped += count;
+        *dropped
+    }
+
+    /// Total opportunities dropped after failed sink deliveries.
+    pub fn dropped_deliveries(&self) -> u64 {
+        *self.dropped_deliveries.lock().unwrap()
+    }
+
+    /// Adds `count` updates lost to a failed rebuild and returns the new total.
+    pub fn record_dropped_updates(&self, count: u64) -> u64 {
+        let mut dropped = self.dropped_updates.lock().unwrap();
+        *dropped += count;
+        *dropped
+    }
+
+    /// Total updates lost to failed rebuilds.
+    pub fn dropped_updates(&self) -> u64 {
+        *self.dropped_updates.lock().unwrap()
+    }
+
+    /// Counts one searcher scan.
+    pub fn record_scan(&self) {
+        self.run.lock().unwrap().scans += 1;
+    }
+
+    /// Counts a newly reported cycle, keeping it if it beats the best seen so far.
+    pub fn record_cycle(&self, cycle: &WeightedCycle) {
+        let mut run = self.run.lock().unwrap();
+        run.distinct_cycles += 1;
+        let is_best = run
+            .best_cycle
+            .as_ref()
+            .is_none_or(|best| cycle.product_rate() > best.product_rate());
+        if is_best {
+            run.best_cycle = Some(cycle.clone());
+        }
+    }
+
+    /// Counts `count` edge updates committed to the graph.
+    pub fn record_edges_processed(&self, count: u64) {
+        self.run.lock().unwrap().edges_processed += count;
+    }
+
+    /// Counts one successful graph rebuild.
+    pub fn record_rebuild(&self) {
+        self.run.lock().unwrap().rebuilds += 1;
+    }
+
+    /// Snapshot of the run totals, e.g. for the shutdown report.
+    pub fn run_report(&self) -> RunReport {
+        self.run.lock().unwrap().clone()
+    }
+}
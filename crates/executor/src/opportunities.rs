@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use super::types::{Opportunity, now_epoch_ms};
+
+/// The currently-known profitable cycles, shared between the searcher and readers
+/// such as the HTTP endpoint.
+///
+/// Opportunities are keyed by the cycle's canonical key, so re-detecting a loop
+/// refreshes its rates and deadline instead of adding a duplicate. Expired entries
+/// are dropped lazily on read.
+#[derive(Default)]
+pub struct OpportunityBook {
+    entries: Mutex<HashMap<Vec<usize>, Opportunity>>,
+}
+
+impl OpportunityBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts `opportunity`, replacing any entry for the same loop.
+    pub fn insert(&self, opportunity: Opportunity) {
+        let key = opportunity.cycle.canonical_key();
+        self.entries.lock().unwrap().insert(key, opportunity);
+    }
+
+    /// Unexpired opportunities, most profitable first.
+    pub fn current(&self) -> Vec<Opportunity> {
+        self.current_at(now_epoch_ms())
+    }
+
+    /// Unexpired opportunities as of `epoch_ms`, most profitable first.
+    pub fn current_at(&self, epoch_ms: u64) -> Vec<Opportunity> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, opportunity| !opportunity.is_expired_at(epoch_ms));
+
+        let mut current: Vec<Opportunity> = entries.values().cloned().collect();
+        current.sort_by(|a, b| a.cycle.log_rate_sum.total_cmp(&b.cycle.log_rate_sum));
+        current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::types::WeightedCycle;
+
+    fn opportunity(path: Vec<(usize, usize, f64)>, valid_until_epoch: u64) -> Opportunity {
+        let cycle = WeightedCycle {
+            rates: path.iter().map(|&(_, _, rate)| rate).collect(),
+            log_rate_sum: path.iter().map(|&(_, _, rate)| -f64::ln(rate)).sum(),
+            path,
+        };
+        Opportunity {
+            cycle,
+            valid_until_epoch,
+        }
+    }
+
+    #[test]
+    fn redetected_loop_replaces_entry_and_expired_entries_are_dropped() {
+        let book = OpportunityBook::new();
+        book.insert(opportunity(vec![(0, 1, 1.1), (1, 0, 1.0)], 100));
+        book.insert(opportunity(vec![(1, 0, 1.0), (0, 1, 1.2)], 200));
+        book.insert(opportunity(vec![(2, 3, 1.05), (3, 2, 1.0)], 50));
+
+        let current = book.current_at(60);
+        assert_eq!(current.len(), 1);
+        assert_eq!(current[0].valid_until_epoch, 200);
+
+        assert!(book.current_at(201).is_empty());
+    }
+}
@@ -1,8 +1,6 @@
-use tokio::sync::mpsc::Sender;
 use tokio::task::JoinHandle;
 
-use super::types::UpdateStreamer;
-use common::types::Edge;
+use super::types::{UpdateSender, UpdateStreamer};
 
 pub struct Producer<S: UpdateStreamer> {
     streamer: S,
@@ -17,7 +15,7 @@ where
     }
 
     /// Spawn the producer task and return its JoinHandle
-    pub fn spawn(self, sender: Sender<Vec<Edge>>) -> JoinHandle<()> {
+    pub fn spawn(self, sender: UpdateSender) -> JoinHandle<()> {
         println!("Producer ready.");
 
         tokio::spawn(async move {
@@ -1,71 +1,1740 @@
-use tokio::time::{self, Duration};
+use std::collections::{HashSet, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, watch};
+use tokio::time::{Duration, Instant};
 
-use super::{error::Error, types::SharedGraph};
-use arb_solver_core::traits::GraphSolver;
+use super::{
+    clock::{Clock, Ticker, TokioClock},
+    config::{ScanMode, SearcherConfig, SourceMode},
+    error::Error,
+    filter::CycleDeduplicator,
+    metrics::Metrics,
+    opportunities::OpportunityBook,
+    sink::{CycleSink, RetryQueue},
+    types::{AsyncGraphSolver, DirtyNodes, Opportunity, SharedGraph},
+};
+use arb_solver_core::{GraphCSR, scc::SccIndex};
+use common::types::{Edge, WeightedCycle};
+
+/// Structural requirements a cycle must meet to be reported.
+///
+/// Checked while each component is searched, so a rejected cycle cannot hide an
+/// acceptable one in the same component.
+#[derive(Debug, Clone)]
+struct CycleFilter {
+    min_len: usize,
+    /// Cycles must visit one of these nodes; empty means no restriction.
+    base_nodes: Arc<HashSet<usize>>,
+    /// `(from, to)` pairs too thin to trade, left out of every search.
+    thin_edges: Arc<HashSet<(usize, usize)>>,
+}
+
+impl CycleFilter {
+    /// Returns `true` if some cycle could be rejected.
+    fn is_active(&self) -> bool {
+        self.min_len > 1 || !self.base_nodes.is_empty() || !self.thin_edges.is_empty()
+    }
+
+    fn accepts(&self, cycle: &WeightedCycle) -> bool {
+        cycle.path.len() >= self.min_len
+            && self.touches_base_node(cycle)
+            && !self.uses_thin_edge(cycle)
+    }
+
+    fn touches_base_node(&self, cycle: &WeightedCycle) -> bool {
+        self.base_nodes.is_empty()
+            || cycle
+                .path
+                .iter()
+                .any(|&(u, _, _)| self.base_nodes.contains(&u))
+    }
+
+    fn uses_thin_edge(&self, cycle: &WeightedCycle) -> bool {
+        cycle
+            .path
+            .iter()
+            .any(|&(u, v, _)| self.thin_edges.contains(&(u, v)))
+    }
+}
 
 pub struct ArbSearcher<S> {
-    solver: S,
+    solver: Arc<S>,
     graph: SharedGraph,
     interval: u64, // interval in seconds
+    workers: usize,
+    scan_mode: ScanMode,
+    source_mode: SourceMode,
+    /// Scans started so far; picks the seed under `SourceMode::RoundRobin`.
+    next_source: AtomicUsize,
+    /// Nodes touched since the last incremental scan.
+    dirty: Arc<DirtyNodes>,
+    /// Set until the first incremental scan, which has no baseline and covers everything.
+    needs_full_scan: bool,
+    dedup: CycleDeduplicator,
+    metrics: Arc<Metrics>,
+    filter: CycleFilter,
+    /// Minimum relative rate move one edge of an emitted cycle must have made recently.
+    /// Measured over the metrics' edge-move window, see [`Metrics::with_edge_move_window`].
+    min_edge_move: Option<f64>,
+    /// Validity window stamped on emitted opportunities.
+    opportunity_ttl: Duration,
+    warmup: Duration,
+    /// Cycles found before this instant are logged but not emitted.
+    warmup_until: Instant,
+    clock: Arc<dyn Clock>,
+    /// Currently-known opportunities, refreshed on every detection.
+    opportunities: Arc<OpportunityBook>,
+    sink: Option<Arc<dyn CycleSink>>,
+    retries: RetryQueue,
+    lock_timeout: Option<Duration>,
+    /// How long one component-wise scan may keep starting new component searches.
+    search_budget: Option<Duration>,
+    /// Components searched so far; rotates where the next component-wise scan starts,
+    /// so components skipped once the budget is spent are searched first next time.
+    next_component: AtomicUsize,
+    /// Most recent snapshot, reused when the graph stays write-locked past `lock_timeout`.
+    /// Only kept when a timeout is configured.
+    last_snapshot: Mutex<Option<Arc<GraphCSR>>>,
+    /// Upper bound on the gross profit (relative) of a reported near-miss.
+    near_miss_margin: Option<f64>,
+    near_misses: Option<mpsc::Sender<WeightedCycle>>,
+    /// Suppresses near-misses already sent recently.
+    near_miss_dedup: CycleDeduplicator,
+    /// Snapshot searched by the latest scan, taken by the near-miss scan that follows
+    /// it. Only kept while a near-miss channel is attached.
+    scanned: Mutex<Option<Arc<GraphCSR>>>,
+    best: Option<watch::Sender<Option<WeightedCycle>>>,
+    shutdown: Option<watch::Receiver<bool>>,
 }
 
 impl<S> ArbSearcher<S>
 where
-    S: GraphSolver,
+    S: AsyncGraphSolver,
 {
-    pub fn new(graph: SharedGraph, interval: u64, solver: S) -> Self {
+    pub fn new(
+        graph: SharedGraph,
+        config: SearcherConfig,
+        solver: S,
+        metrics: Arc<Metrics>,
+    ) -> Self {
+        let clock: Arc<dyn Clock> = Arc::new(TokioClock);
+        let warmup = Duration::from_secs(config.warmup_seconds);
+        let thin_edges = Arc::new(config.thin_edges());
         ArbSearcher {
             graph,
-            interval,
-            solver,
+            interval: config.interval_seconds,
+            workers: config.workers.max(1),
+            scan_mode: config.scan_mode,
+            source_mode: config.source_mode,
+            next_source: AtomicUsize::new(0),
+            dirty: Arc::new(DirtyNodes::new()),
+            needs_full_scan: true,
+            solver: Arc::new(solver),
+            dedup: CycleDeduplicator::new(config.dedup_capacity, config.dedup_undirected),
+            metrics,
+            filter: CycleFilter {
+                min_len: config.min_cycle_len,
+                base_nodes: Arc::new(config.base_nodes.into_iter().collect()),
+                thin_edges,
+            },
+            min_edge_move: config.min_edge_move_bps.map(|bps| bps / 10_000.0),
+            opportunity_ttl: match config.opportunity_ttl_ms {
+                Some(ms) => Duration::from_millis(ms),
+                // A new scan (and so a fresher verdict) is due one interval from now.
+                None => Duration::from_secs(config.interval_seconds),
+            },
+            warmup,
+            warmup_until: clock.now() + warmup,
+            clock,
+            opportunities: Arc::new(OpportunityBook::new()),
+            sink: None,
+            retries: RetryQueue::new(
+                config.sink_retry_capacity,
+                config.sink_max_attempts,
+                Duration::from_millis(config.sink_retry_backoff_ms),
+            ),
+            lock_timeout: config.lock_timeout_ms.map(Duration::from_millis),
+            search_budget: config.search_budget_ms.map(Duration::from_millis),
+            next_component: AtomicUsize::new(0),
+            last_snapshot: Mutex::new(None),
+            near_miss_margin: config.near_miss_margin_bps.map(|bps| bps / 10_000.0),
+            near_misses: None,
+            near_miss_dedup: CycleDeduplicator::new(config.dedup_capacity, config.dedup_undirected),
+            scanned: Mutex::new(None),
+            best: None,
+            shutdown: None,
+        }
+    }
+
+    /// Delivers every emitted opportunity to `sink`, retrying transient failures.
+    pub fn with_sink(mut self, sink: Arc<dyn CycleSink>) -> Self {
+        self.sink = Some(sink);
+        self
+    }
+
+    /// Sends near-misses (see `near_miss_margin_bps`) to `near_misses`, apart from the
+    /// actionable opportunities delivered to the sink.
+    ///
+    /// Near-misses are for monitoring only: when the channel is full they are dropped.
+    /// Like emitted cycles, a near-miss seen recently (see `dedup_capacity`) is not sent
+    /// again.
+    pub fn with_near_miss_channel(mut self, near_misses: mpsc::Sender<WeightedCycle>) -> Self {
+        self.near_misses = Some(near_misses);
+        self
+    }
+
+    /// Publishes the most profitable cycle of every scan (or `None` if it found none)
+    /// to `best`, so readers always see the latest one without a backlog.
+    ///
+    /// The value is the best cycle passing the emit filters (see [`ArbSearcher::emit`]),
+    /// in canonical rotation. A failed scan leaves the previous value in place, as does
+    /// an incremental scan finding nothing, since it only covers touched components.
+    pub fn with_best_opportunity_channel(
+        mut self,
+        best: watch::Sender<Option<WeightedCycle>>,
+    ) -> Self {
+        self.best = Some(best);
+        self
+    }
+
+    /// Stops the search loop once `shutdown` turns true (or its sender is dropped), after
+    /// one final search iteration over the graph as it is then.
+    pub fn with_shutdown(mut self, shutdown: watch::Receiver<bool>) -> Self {
+        self.shutdown = Some(shutdown);
+        self
+    }
+
+    /// Reads the nodes touched by the writer from `dirty` (see [`ScanMode::Incremental`]).
+    pub fn with_dirty_nodes(mut self, dirty: Arc<DirtyNodes>) -> Self {
+        self.dirty = dirty;
+        self
+    }
+
+    /// Number of failed deliveries waiting to be retried.
+    pub fn pending_retries(&self) -> usize {
+        self.retries.len()
+    }
+
+    /// Shares `opportunities` with other readers (e.g. the HTTP endpoint).
+    pub fn with_opportunity_book(mut self, opportunities: Arc<OpportunityBook>) -> Self {
+        self.opportunities = opportunities;
+        self
+    }
+
+    /// The book of currently-known opportunities maintained by this searcher.
+    pub fn opportunities(&self) -> Arc<OpportunityBook> {
+        self.opportunities.clone()
+    }
+
+    /// Replaces the clock driving the search interval and warm-up (e.g. with a mock in tests).
+    ///
+    /// The warm-up window restarts from the new clock's current time.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.warmup_until = clock.now() + self.warmup;
+        self.clock = clock;
+        self
+    }
+
+    /// Clones the shared graph for a scan, holding the read lock only while cloning.
+    ///
+    /// With `lock_timeout` set, a writer holding the lock longer (e.g. a long rebuild)
+    /// does not stall the searcher: a warning is logged and the previous snapshot is
+    /// reused. Returns the snapshot and whether it is fresh, or `None` if the lock timed
+    /// out before any snapshot was taken.
+    async fn snapshot(&self) -> Option<(Arc<GraphCSR>, bool)> {
+        let guard = match self.lock_timeout {
+            Some(limit) => tokio::time::timeout(limit, self.graph.read()).await.ok(),
+            None => Some(self.graph.read().await),
+        };
+
+        let Some(guard) = guard else {
+            let previous = self.last_snapshot.lock().unwrap().clone();
+            eprintln!(
+                "Searcher Warning: Graph write-locked for over {:?}; {}.",
+                self.lock_timeout.unwrap_or_default(),
+                if previous.is_some() {
+                    "scanning the previous snapshot"
+                } else {
+                    "no previous snapshot, skipping this scan"
+                }
+            );
+            if let Some(graph) = &previous {
+                self.remember_scanned(graph);
+            }
+            return previous.map(|graph| (graph, false));
+        };
+
+        let graph = Arc::new(guard.clone());
+        drop(guard);
+        if self.lock_timeout.is_some() {
+            *self.last_snapshot.lock().unwrap() = Some(graph.clone());
+        }
+        self.remember_scanned(&graph);
+        Some((graph, true))
+    }
+
+    /// Keeps `graph` for the near-miss scan following this scan, if there is one.
+    fn remember_scanned(&self, graph: &Arc<GraphCSR>) {
+        if self.near_misses.is_some() {
+            *self.scanned.lock().unwrap() = Some(graph.clone());
         }
     }
 
-    pub async fn seacrh_for_arbs(self) -> Result<(), Error> {
+    /// Runs a single cycle search over a fresh snapshot of the shared graph.
+    ///
+    /// The read lock is held only while cloning the graph (see `lock_timeout_ms` for
+    /// when a previous snapshot is used instead). Returns `Ok(None)` without searching
+    /// if the graph is too small to contain a cycle.
+    pub async fn scan_once(&self) -> Result<Option<WeightedCycle>, Error> {
+        let Some((graph_snapshot, _)) = self.snapshot().await else {
+            return Ok(None);
+        };
+
+        // Only run the expensive search if the graph has meaningful data
+        if graph_snapshot.num_nodes <= 1 {
+            println!("Searcher: Graph too small to search for cycles. Skipping.");
+            return Ok(None);
+        }
+
+        println!("Searcher: Starting cycle search on new snapshot...");
+
+        let source = self.next_source() % graph_snapshot.num_nodes;
+        let hop_cap = graph_snapshot.num_nodes + 1;
+        self.solver
+            .find_profitable_cycle_async(graph_snapshot, source, hop_cap)
+            .await
+    }
+
+    /// Seed for the next search, before reduction modulo the searched graph's node
+    /// count: always 0 with `SourceMode::AllNodes`, one more on every call with
+    /// `SourceMode::RoundRobin`.
+    fn next_source(&self) -> usize {
+        match self.source_mode {
+            SourceMode::AllNodes => 0,
+            SourceMode::RoundRobin => self.next_source.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    /// Runs one scan according to the configured [`ScanMode`] and worker count.
+    ///
+    /// A full scan searches component by component whenever `min_cycle_len`,
+    /// `base_nodes` or `min_liquidity` can reject a cycle, even with a single worker,
+    /// so that rejected cycles (or thin edges) are skipped during the search rather
+    /// than after it. The same holds with `search_budget_ms` set, which only applies
+    /// to component-wise scans.
+    pub async fn scan(&mut self) -> Result<Vec<WeightedCycle>, Error> {
+        self.metrics.record_scan();
+        match self.scan_mode {
+            ScanMode::Incremental => self.scan_touched_components(self.workers).await,
+            ScanMode::Full
+                if self.workers > 1 || self.filter.is_active() || self.search_budget.is_some() =>
+            {
+                self.scan_components(self.workers).await
+            }
+            ScanMode::Full => Ok(self.scan_once().await?.into_iter().collect()),
+        }
+    }
+
+    /// Searches every strongly connected component of a fresh snapshot, spreading the
+    /// components over `workers` tasks that pull from a shared work queue.
+    ///
+    /// Each component is searched as its own induced subgraph, so one profitable cycle
+    /// per component can be returned (ids are mapped back to the full graph).
+    ///
+    /// A cycle shorter than `min_cycle_len`, or missing every configured base node, is
+    /// not returned: its highest-rate edge is disabled, as in
+    /// [`arb_solver_core::solver::SPFASolver::find_all_negative_cycles`], and the
+    /// component searched again until an acceptable cycle is found or none is left.
+    /// Results are merged into a single list ordered by canonical key. The first worker
+    /// error is returned.
+    pub async fn scan_components(&self, workers: usize) -> Result<Vec<WeightedCycle>, Error> {
+        let Some((graph_snapshot, _)) = self.snapshot().await else {
+            return Ok(Vec::new());
+        };
+
+        if graph_snapshot.num_nodes <= 1 {
+            println!("Searcher: Graph too small to search for cycles. Skipping.");
+            return Ok(Vec::new());
+        }
+
+        let components: Vec<Vec<usize>> = SccIndex::from_graph(&graph_snapshot)
+            .components()
+            .map(<[usize]>::to_vec)
+            .collect();
+        let source = self.next_source();
+        let filter = self.filter.clone();
+        self.search_components(
+            graph_snapshot,
+            components,
+            source,
+            workers,
+            move |cycle| filter.accepts(cycle),
+            1,
+        )
+        .await
+    }
+
+    /// Like [`ArbSearcher::scan_components`], but only searches components containing a
+    /// node marked dirty since the previous call. The first call searches everything.
+    ///
+    /// The dirty set is drained before the snapshot is taken, so updates applied in
+    /// between are either in this snapshot or marked for the next scan. If the lock
+    /// times out and a stale snapshot is used, the next scan covers everything again.
+    pub async fn scan_touched_components(
+        &mut self,
+        workers: usize,
+    ) -> Result<Vec<WeightedCycle>, Error> {
+        let touched = self.dirty.take();
+        let full = std::mem::take(&mut self.needs_full_scan);
+        if !full && touched.is_empty() {
+            println!("Searcher: No updates since the last scan. Skipping.");
+            return Ok(Vec::new());
+        }
+
+        let snapshot = self.snapshot().await;
+        if !matches!(snapshot, Some((_, true))) {
+            // The touched nodes' updates are not in this snapshot.
+            self.needs_full_scan = true;
+        }
+        let Some((graph_snapshot, _)) = snapshot else {
+            return Ok(Vec::new());
+        };
+
+        if graph_snapshot.num_nodes <= 1 {
+            println!("Searcher: Graph too small to search for cycles. Skipping.");
+            return Ok(Vec::new());
+        }
+
+        let components: Vec<Vec<usize>> = SccIndex::from_graph(&graph_snapshot)
+            .components()
+            .filter(|members| full || members.iter().any(|node| touched.contains(node)))
+            .map(<[usize]>::to_vec)
+            .collect();
+        let source = self.next_source();
+        let filter = self.filter.clone();
+        self.search_components(
+            graph_snapshot,
+            components,
+            source,
+            workers,
+            move |cycle| filter.accepts(cycle),
+            1,
+        )
+        .await
+    }
+
+    /// Finds cycles that are profitable before fees, by less than `near_miss_margin_bps`,
+    /// but not after fees.
+    ///
+    /// The gross rates (fees undone) of `graph_snapshot`, normally the one just scanned,
+    /// are searched per strongly connected component. Every gross cycle found is
+    /// re-priced on the fee-bearing snapshot, then disabled like a filtered cycle (see
+    /// [`ArbSearcher::scan_components`]) so the search goes on to the component's other
+    /// cycles. Returns an empty list if no margin is configured. Cycles carry gross rates.
+    pub async fn scan_near_misses(
+        &self,
+        graph_snapshot: Arc<GraphCSR>,
+    ) -> Result<Vec<WeightedCycle>, Error> {
+        let Some(margin) = self.near_miss_margin else {
+            return Ok(Vec::new());
+        };
+        if graph_snapshot.num_nodes <= 1 {
+            return Ok(Vec::new());
+        }
+
+        let gross = Arc::new(GraphCSR::from_edges_with_transform(
+            graph_snapshot.num_nodes,
+            &mut graph_snapshot.to_edges(),
+            usize::MAX,
+            graph_snapshot.weight_transform.clone(),
+        ));
+        let components: Vec<Vec<usize>> = SccIndex::from_graph(&gross)
+            .components()
+            .map(<[usize]>::to_vec)
+            .collect();
+        let filter = self.filter.clone();
+        let is_near_miss = move |cycle: &WeightedCycle| {
+            let mut nodes = cycle.nodes();
+            nodes.extend(nodes.first().copied());
+            let net_profitable = graph_snapshot
+                .path_log_weight(&nodes)
+                .is_ok_and(|weight| weight < 0.0);
+            filter.accepts(cycle) && cycle.product_rate() < 1.0 + margin && !net_profitable
+        };
+        self.search_components(gross, components, 0, self.workers, is_near_miss, usize::MAX)
+            .await
+    }
+
+    /// Searches each of `components` of `graph_snapshot` on a pool of `workers` tasks,
+    /// seeding each component's search at member `source % len`.
+    ///
+    /// Up to `per_component` cycles passing `accept` are returned per component. After
+    /// each detection the cycle's highest-rate edge is disabled and the component
+    /// searched again, so a rejected cycle does not hide the others. Edges below
+    /// `min_liquidity` are left out of each component's subgraph.
+    ///
+    /// With `search_budget_ms` set, workers stop taking components once the budget is
+    /// spent and the cycles found so far are returned. A search already running is not
+    /// interrupted, so a scan can overrun the budget by up to one component search.
+    /// Each call starts where the previous one stopped, so skipped components are
+    /// searched first by the next scan rather than starved.
+    async fn search_components(
+        &self,
+        graph_snapshot: Arc<GraphCSR>,
+        mut components: Vec<Vec<usize>>,
+        source: usize,
+        workers: usize,
+        accept: impl Fn(&WeightedCycle) -> bool + Clone + Send + 'static,
+        per_component: usize,
+    ) -> Result<Vec<WeightedCycle>, Error> {
+        println!(
+            "Searcher: Starting search of {} component(s) with {} worker(s)...",
+            components.len(),
+            workers
+        );
+
+        let total = components.len();
+        if total > 0 {
+            components.rotate_left(self.next_component.load(Ordering::Relaxed) % total);
+        }
+
+        let deadline = self.search_budget.map(|budget| self.clock.now() + budget);
+        let queue = Arc::new(Mutex::new(VecDeque::from(components)));
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let handles: Vec<_> = (0..workers.max(1))
+            .map(|_| {
+                let queue = queue.clone();
+                let graph = graph_snapshot.clone();
+                let solver = self.solver.clone();
+                let sender = sender.clone();
+                let clock = self.clock.clone();
+                let accept = accept.clone();
+                let thin_edges = self.filter.thin_edges.clone();
+                tokio::spawn(async move {
+                    loop {
+                        if deadline.is_some_and(|deadline| clock.now() >= deadline) {
+                            return Ok::<(), Error>(());
+                        }
+                        let Some(members) = queue.lock().unwrap().pop_front() else {
+                            return Ok(());
+                        };
+
+                        let mut sub = Arc::new(graph.induced_subgraph_masked(&members, |u, v| {
+                            thin_edges.contains(&(u, v))
+                        }));
+                        if sub.edge_targets.is_empty() {
+                            continue;
+                        }
+
+                        let hop_cap = sub.num_nodes + 1;
+                        let mut found = 0;
+                        while found < per_component
+                            && let Some(cycle) = solver
+                                .find_profitable_cycle_async(
+                                    sub.clone(),
+                                    source % members.len(),
+                                    hop_cap,
+                                )
+                                .await?
+                        {
+                            let Some(&(u, v, _)) =
+                                cycle.path.iter().max_by(|a, b| a.2.total_cmp(&b.2))
+                            else {
+                                break;
+                            };
+                            let path: Vec<Edge> = cycle
+                                .path
+                                .iter()
+                                .map(|&(u, v, rate)| (members[u], members[v], rate))
+                                .collect();
+                            let cycle = WeightedCycle { path, ..cycle };
+                            if accept(&cycle) {
+                                found += 1;
+                                // The receiver outlives every worker.
+                                let _ = sender.send(cycle);
+                            }
+                            disable_edge(Arc::make_mut(&mut sub), u, v);
+                        }
+                    }
+                })
+            })
+            .collect();
+        drop(sender);
+
+        let mut cycles = Vec::new();
+        while let Some(cycle) = receiver.recv().await {
+            cycles.push(cycle);
+        }
+        for handle in handles {
+            handle.await??;
+        }
+
+        let skipped = queue.lock().unwrap().len();
+        self.next_component
+            .fetch_add(total - skipped, Ordering::Relaxed);
+        if skipped > 0 {
+            println!(
+                "Searcher: Search budget of {:?} spent; {} component(s) skipped, the next scan starts with them.",
+                self.search_budget.unwrap_or_default(),
+                skipped
+            );
+        }
+
+        cycles.sort_by_key(WeightedCycle::canonical_key);
+        Ok(cycles)
+    }
+
+    /// Reports a detected cycle unless it is shorter than `min_cycle_len`, misses every
+    /// configured base node, crosses an edge below `min_liquidity`, the searcher is
+    /// still warming up, or it was already reported recently.
+    ///
+    /// Cycles suppressed during warm-up are not remembered by the deduplicator,
+    /// so they are emitted once warm-up ends if they persist. The same holds for
+    /// cycles without a recently moved edge when `min_edge_move_bps` is set.
+    ///
+    /// Every cycle passing the length and warm-up checks is recorded in the
+    /// opportunity book (refreshing its deadline), even if deduplicated.
+    /// Newly reported cycles also record their update-to-detection latency.
+    /// Returns the emitted opportunity, valid for the configured TTL from now, or
+    /// `None` if the cycle was suppressed.
+    pub fn emit(&mut self, cycle: WeightedCycle) -> Option<Opportunity> {
+        if !self.passes_filters(&cycle) {
+            return None;
+        }
+        self.record(cycle)
+    }
+
+    /// Returns `true` if `cycle` passes the length, base node, liquidity, warm-up and
+    /// edge move checks of [`ArbSearcher::emit`], logging why otherwise.
+    fn passes_filters(&self, cycle: &WeightedCycle) -> bool {
+        if cycle.path.len() < self.filter.min_len {
+            println!(
+                "Search complete: Ignoring {}-edge cycle (min_cycle_len = {}).",
+                cycle.path.len(),
+                self.filter.min_len
+            );
+            return false;
+        }
+
+        if !self.filter.touches_base_node(cycle) {
+            println!(
+                "Search complete: Ignoring {}-edge cycle touching no base node.",
+                cycle.path.len()
+            );
+            return false;
+        }
+
+        if self.filter.uses_thin_edge(cycle) {
+            println!(
+                "Search complete: Ignoring {}-edge cycle crossing an edge below min_liquidity.",
+                cycle.path.len()
+            );
+            return false;
+        }
+
+        if self.clock.now() < self.warmup_until {
+            println!(
+                "Search complete: Warming up, not emitting {}-edge cycle (product rate {}).",
+                cycle.path.len(),
+                cycle.product_rate()
+            );
+            return false;
+        }
+
+        if let Some(min_move) = self.min_edge_move
+            && !self.metrics.has_recent_move(cycle, min_move)
+        {
+            println!(
+                "Search complete: No edge of the {}-edge cycle moved recently. Not emitting.",
+                cycle.path.len()
+            );
+            return false;
+        }
+
+        true
+    }
+
+    /// Records an emittable `cycle` in the opportunity book and reports it unless the
+    /// deduplicator has seen it recently.
+    fn record(&mut self, cycle: WeightedCycle) -> Option<Opportunity> {
+        // Still-present loops refresh their rates and deadline in the book even when
+        // the deduplicator suppresses re-reporting them.
+        let valid_until_epoch = self.clock.epoch_ms() + self.opportunity_ttl.as_millis() as u64;
+        self.opportunities.insert(Opportunity {
+            cycle: cycle.canonicalize(),
+            valid_until_epoch,
+        });
+
+        if !self.dedup.is_new(&cycle) {
+            println!("Search complete: Cycle already reported. Skipping.");
+            return None;
+        }
+
+        let cycle = cycle.canonicalize();
+        self.metrics.record_cycle(&cycle);
+        println!(
+            "Cycle FOUND! Path: {:?} (valid until epoch ms {})",
+            cycle.path, valid_until_epoch
+        );
+        println!("CYCLE IS PROFITABLE: {}", cycle.is_profitable());
+        println!("PROFIT MEASURE: {}", cycle.product_rate());
+
+        if let Some(latency) = self.metrics.record_detection(&cycle) {
+            println!("DETECTION LATENCY: {:?}", latency);
+        }
+
+        Some(Opportunity {
+            cycle,
+            valid_until_epoch,
+        })
+    }
+
+    /// Emits `cycle` (see [`ArbSearcher::emit`]) and delivers it to the sink, if any.
+    ///
+    /// A failed delivery is queued for retry rather than lost.
+    pub async fn report(&mut self, cycle: WeightedCycle) -> Option<Opportunity> {
+        let opportunity = self.emit(cycle)?;
+        self.deliver(opportunity.clone(), 1).await;
+        Some(opportunity)
+    }
+
+    /// Re-attempts every queued delivery whose backoff has elapsed.
+    ///
+    /// Opportunities that expired while queued are dropped (and counted) instead.
+    pub async fn retry_deliveries(&mut self) {
+        let expired = self.retries.drop_expired(self.clock.epoch_ms());
+        if expired > 0 {
+            let total = self.metrics.record_dropped_deliveries(expired);
+            eprintln!(
+                "Searcher Error: Dropped {} opportunity(ies) that expired awaiting retry ({} total).",
+                expired, total
+            );
+        }
+        for pending in self.retries.take_due(self.clock.now()) {
+            self.deliver(pending.opportunity, pending.attempts + 1)
+                .await;
+        }
+    }
+
+    /// Makes delivery attempt number `attempt`, queueing or dropping on failure.
+    async fn deliver(&mut self, opportunity: Opportunity, attempt: u32) {
+        let Some(sink) = self.sink.clone() else {
+            return;
+        };
+
+        if let Err(e) = sink.deliver(&opportunity).await {
+            eprintln!(
+                "Searcher Error: Sink delivery attempt {} failed: {}.",
+                attempt, e
+            );
+            let dropped = self
+                .retries
+                .record_failure(opportunity, attempt, self.clock.now());
+            if dropped > 0 {
+                let total = self.metrics.record_dropped_deliveries(dropped);
+                eprintln!(
+                    "Searcher Error: Dropped {} undeliverable opportunity(ies) ({} total).",
+                    dropped, total
+                );
+            }
+        }
+    }
+
+    /// Publishes the most profitable of a scan's `admitted` cycles to the best-opportunity
+    /// channel, if one is attached.
+    fn publish_best(&self, admitted: &[WeightedCycle]) {
+        let Some(best) = &self.best else {
+            return;
+        };
+        if admitted.is_empty() && matches!(self.scan_mode, ScanMode::Incremental) {
+            return;
+        }
+        best.send_replace(
+            admitted
+                .iter()
+                .max_by(|a, b| a.product_rate().total_cmp(&b.product_rate()))
+                .map(WeightedCycle::canonicalize),
+        );
+    }
+
+    pub async fn seacrh_for_arbs(mut self) -> Result<(), Error> {
         println!("Searcher ready.");
 
-        let mut interval = time::interval(Duration::from_secs(self.interval));
+        let mut interval = Ticker::new(self.clock.clone(), Duration::from_secs(self.interval));
 
         // The first tick occurs immediately, but we skip it to wait the full duration
         interval.tick().await;
 
+        let mut shutdown = self.shutdown.take();
         loop {
-            interval.tick().await;
-
-            let graph_snapshot = {
-                let graph_guard = self.graph.read().await;
-                graph_guard.clone()
+            let stopping = tokio::select! {
+                _ = interval.tick() => false,
+                _ = Self::stopped(&mut shutdown) => true,
             };
+            if stopping {
+                println!("Searcher: Running a final scan before shutdown...");
+            }
+            self.search_iteration().await;
+            if stopping {
+                return Ok(());
+            }
+        }
+    }
 
-            // Only run the expensive search if the graph has meaningful data
-            if graph_snapshot.num_nodes > 1 {
-                println!("Searcher: Starting cycle search on new snapshot...");
+    /// Completes once `shutdown` is signalled; never without a shutdown channel.
+    async fn stopped(shutdown: &mut Option<watch::Receiver<bool>>) {
+        match shutdown {
+            Some(shutdown) => {
+                let _ = shutdown.wait_for(|&stop| stop).await;
+            }
+            None => std::future::pending().await,
+        }
+    }
 
-                let cycle_result = self.solver.find_profitable_cycle(
-                    &graph_snapshot,
-                    0,
-                    graph_snapshot.num_nodes + 1,
-                );
+    /// One search interval: retries due deliveries, scans and reports opportunities,
+    /// then forwards near-misses if a near-miss channel is attached.
+    pub async fn search_iteration(&mut self) {
+        self.retry_deliveries().await;
 
-                match cycle_result {
-                    Ok(Some(cycle)) => {
-                        println!("Cycle FOUND! Path: {:?}", cycle.path);
-                        println!("CYCLE IS PROFITABLE: {}", cycle.is_profitable());
-                        println!("PROFIT MEASURE: {}", cycle.product_rate());
+        match self.scan().await {
+            Ok(cycles) => {
+                if cycles.is_empty() {
+                    println!("Search complete: No arbitrage opportunities.");
+                }
+                let admitted: Vec<WeightedCycle> = cycles
+                    .into_iter()
+                    .filter(|cycle| self.passes_filters(cycle))
+                    .collect();
+                self.publish_best(&admitted);
+                for cycle in admitted {
+                    if let Some(opportunity) = self.record(cycle) {
+                        self.deliver(opportunity, 1).await;
                     }
-                    Ok(None) => {
-                        println!("Search complete: No arbitrage opportunities.");
+                }
+            }
+            Err(e) => {
+                eprintln!(
+                    "Searcher Error: Graph cycle finder failed due to: {}. Continuing.",
+                    e
+                );
+            }
+        }
+
+        let Some(near_misses) = self.near_misses.clone() else {
+            return;
+        };
+        // A skipped scan leaves nothing new to look at.
+        let Some(graph_snapshot) = self.scanned.lock().unwrap().take() else {
+            return;
+        };
+        match self.scan_near_misses(graph_snapshot).await {
+            Ok(cycles) => {
+                for cycle in cycles {
+                    if !self.near_miss_dedup.is_new(&cycle) {
+                        continue;
                     }
-                    Err(e) => {
-                        eprintln!(
-                            "Searcher Error: Graph cycle finder failed due to: {}. Continuing.",
-                            e
-                        );
+                    if near_misses.try_send(cycle).is_err() {
+                        eprintln!("Searcher Warning: Near-miss channel full or closed. Dropping.");
                     }
                 }
-            } else {
-                println!("Searcher: Graph too small to search for cycles. Skipping.");
             }
+            Err(e) => {
+                eprintln!(
+                    "Searcher Error: Near-miss search failed due to: {}. Continuing.",
+                    e
+                );
+            }
+        }
+    }
+}
+
+/// Disables every `src -> dst` edge of `graph` in place: an infinite weight can never
+/// be relaxed.
+fn disable_edge(graph: &mut GraphCSR, src: usize, dst: usize) {
+    for i in graph.node_pointers[src]..graph.node_pointers[src + 1] {
+        if graph.edge_targets[i] == dst {
+            graph.set_edge_weight(i, f64::INFINITY);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::types::update_channel;
+    use crate::{
+        config::{ChannelKind, EdgeLiquidity},
+        writer::{Writer, tests::writer_config},
+    };
+    use arb_solver_core::{GraphCSR, solver::SPFASolver, traits::GraphSolver};
+    use common::error::Error as ArbSolverError;
+    use tokio::sync::RwLock;
+
+    /// `SPFASolver` that counts how many searches it has run.
+    #[derive(Clone, Default)]
+    struct CountingSolver(Arc<AtomicUsize>);
+
+    impl GraphSolver for CountingSolver {
+        fn find_profitable_cycle(
+            &self,
+            graph: &GraphCSR,
+            source: usize,
+            hop_cap: usize,
+        ) -> Result<Option<WeightedCycle>, ArbSolverError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            SPFASolver.find_profitable_cycle(graph, source, hop_cap)
+        }
+    }
+
+    /// `SPFASolver` on a mock clock, where every search takes 10ms.
+    #[derive(Clone)]
+    struct SlowSolver {
+        clock: Arc<MockClock>,
+        searches: Arc<AtomicUsize>,
+    }
+
+    impl GraphSolver for SlowSolver {
+        fn find_profitable_cycle(
+            &self,
+            graph: &GraphCSR,
+            source: usize,
+            hop_cap: usize,
+        ) -> Result<Option<WeightedCycle>, ArbSolverError> {
+            self.searches.fetch_add(1, Ordering::SeqCst);
+            self.clock.advance(Duration::from_millis(10));
+            SPFASolver.find_profitable_cycle(graph, source, hop_cap)
+        }
+    }
+
+    /// Solver that records the `source` of every search.
+    #[derive(Clone, Default)]
+    struct SourceRecorder(Arc<Mutex<Vec<usize>>>);
+
+    impl GraphSolver for SourceRecorder {
+        fn find_profitable_cycle(
+            &self,
+            graph: &GraphCSR,
+            source: usize,
+            hop_cap: usize,
+        ) -> Result<Option<WeightedCycle>, ArbSolverError> {
+            self.0.lock().unwrap().push(source);
+            SPFASolver.find_profitable_cycle(graph, source, hop_cap)
+        }
+    }
+
+    /// Sink that fails the first `failures` deliveries, then records the rest.
+    #[derive(Default)]
+    struct FlakySink {
+        failures: usize,
+        attempts: AtomicUsize,
+        delivered: std::sync::Mutex<Vec<Opportunity>>,
+    }
+
+    #[async_trait::async_trait]
+    impl CycleSink for FlakySink {
+        async fn deliver(&self, opportunity: &Opportunity) -> Result<(), Error> {
+            if self.attempts.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err(Error::SinkError("webhook unavailable".into()));
+            }
+            self.delivered.lock().unwrap().push(opportunity.clone());
+            Ok(())
+        }
+    }
+
+    fn searcher_config() -> SearcherConfig {
+        SearcherConfig {
+            interval_seconds: 1,
+            dedup_capacity: 16,
+            dedup_undirected: false,
+            min_cycle_len: 2,
+            fair_scheduling: false,
+            warmup_seconds: 0,
+            workers: 1,
+            opportunity_ttl_ms: None,
+            sink_retry_capacity: 16,
+            sink_max_attempts: 5,
+            sink_retry_backoff_ms: 100,
+            scan_mode: ScanMode::Full,
+            source_mode: SourceMode::AllNodes,
+            min_edge_move_bps: None,
+            edge_move_window_ms: 10_000,
+            lock_timeout_ms: None,
+            near_miss_margin_bps: None,
+            base_nodes: Vec::new(),
+            search_budget_ms: None,
+            min_liquidity: None,
+            edge_liquidity: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn emitting_cycle_records_bounded_detection_latency() {
+        let graph: SharedGraph = Arc::new(RwLock::new(GraphCSR::from_edges(0, &mut [], 1)));
+        let metrics = Arc::new(Metrics::new());
+
+        let (sender, receiver) = update_channel(ChannelKind::Bounded, 4);
+        let config = writer_config();
+        let writer = Writer::new(graph.clone(), receiver, config, metrics.clone());
+        let writer_handle = tokio::spawn(writer.process_updates());
+
+        // The second edge closes a profitable loop: 1.0 * 1.1 = 1.1
+        sender.send(vec![(0, 1, 1.0)]).await.unwrap();
+        sender.send(vec![(1, 0, 1.1)]).await.unwrap();
+        drop(sender);
+        writer_handle.await.unwrap().unwrap();
+
+        let mut searcher = ArbSearcher::new(graph, searcher_config(), SPFASolver, metrics.clone());
+        let cycle = searcher.scan_once().await.unwrap().expect("Cycle expected");
+        assert!(searcher.emit(cycle).is_some());
+
+        let latency = metrics.detection_latency();
+        assert_eq!(latency.count, 1);
+        assert!(latency.last > Duration::ZERO);
+        assert!(latency.last < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn shutdown_report_contains_best_cycle_profit() {
+        let graph: SharedGraph = Arc::new(RwLock::new(GraphCSR::from_edges(0, &mut [], 2)));
+        let metrics = Arc::new(Metrics::new());
+
+        let (sender, receiver) = update_channel(ChannelKind::Bounded, 4);
+        let config = writer_config();
+        let writer = Writer::new(graph.clone(), receiver, config, metrics.clone());
+        let writer_handle = tokio::spawn(writer.process_updates());
+
+        // 1.0 * 1.05 * 1.2 = 1.26, i.e. a 26% loop.
+        for edge in [(0, 1, 1.0), (1, 2, 1.05), (2, 0, 1.2), (2, 3, 0.9)] {
+            sender.send(vec![edge]).await.unwrap();
+        }
+        drop(sender);
+        writer_handle.await.unwrap().unwrap();
+
+        let mut searcher = ArbSearcher::new(graph, searcher_config(), SPFASolver, metrics.clone());
+        for _ in 0..2 {
+            for cycle in searcher.scan().await.unwrap() {
+                searcher.report(cycle).await;
+            }
+        }
+
+        let report = metrics.run_report();
+        assert_eq!(report.scans, 2);
+        assert_eq!(report.distinct_cycles, 1);
+        assert_eq!(report.edges_processed, 4);
+        assert_eq!(report.rebuilds, 2);
+        assert!((report.best_cycle.unwrap().product_rate() - 1.26).abs() < 1e-9);
+        assert!(metrics.run_report().to_string().contains("profit 26.0000%"));
+    }
+
+    #[tokio::test]
+    async fn static_cycle_is_emitted_only_after_an_edge_moves_sharply() {
+        let mut edges = vec![(0, 1, 1.0), (1, 0, 1.1)];
+        let graph = Arc::new(RwLock::new(GraphCSR::from_edges(2, &mut edges, 10)));
+        let metrics = Arc::new(Metrics::new());
+        metrics.stamp_updates(&edges);
+        // Re-quoting the same rates is not a move.
+        metrics.stamp_updates(&edges);
+
+        let config = SearcherConfig {
+            min_edge_move_bps: Some(100.0),
+            ..searcher_config()
+        };
+        let mut searcher = ArbSearcher::new(graph.clone(), config, SPFASolver, metrics.clone());
+
+        let cycle = searcher.scan_once().await.unwrap().expect("Cycle expected");
+        assert!(searcher.emit(cycle).is_none());
+
+        // A small move stays below the 1% threshold.
+        metrics.stamp_updates(&[(1, 0, 1.101)]);
+        let cycle = searcher.scan_once().await.unwrap().expect("Cycle expected");
+        assert!(searcher.emit(cycle).is_none());
+
+        // 1.1 -> 1.2 since the window started is a ~9% move.
+        metrics.stamp_updates(&[(1, 0, 1.2)]);
+        graph
+            .write()
+            .await
+            .rebuild_with_edges(vec![(1, 0, 1.2)])
+            .unwrap();
+        assert!((metrics.edge_move(1, 0).unwrap().delta - 0.0909).abs() < 1e-3);
+
+        let cycle = searcher.scan_once().await.unwrap().expect("Cycle expected");
+        assert!(searcher.emit(cycle).is_some());
+    }
+
+    #[tokio::test]
+    async fn edge_moves_add_up_over_the_window_and_expire_with_it() {
+        let cycle = WeightedCycle {
+            path: vec![(0, 1, 0.0), (1, 0, 0.0)],
+            rates: vec![1.0, 1.0],
+            log_rate_sum: 0.0,
+        };
+
+        // Three 0.5% steps: none is a 1% move alone, together they are.
+        let metrics = Metrics::new();
+        for rate in [1.0, 1.005, 1.010025, 1.015075] {
+            metrics.stamp_updates(&[(0, 1, rate)]);
+        }
+        assert!((metrics.edge_move(0, 1).unwrap().delta - 0.015075).abs() < 1e-9);
+        assert!(metrics.has_recent_move(&cycle, 0.01));
+
+        // Once the window has passed without a quote, the move is over.
+        let clock = Arc::new(MockClock::new());
+        let metrics = Metrics::new()
+            .with_edge_move_window(Duration::from_millis(20))
+            .with_clock(clock.clone());
+        metrics.stamp_updates(&[(0, 1, 1.0)]);
+        clock.advance(Duration::from_millis(5));
+        metrics.stamp_updates(&[(0, 1, 1.1)]);
+        clock.advance(Duration::from_millis(10));
+        assert!(metrics.has_recent_move(&cycle, 0.01));
+        clock.advance(Duration::from_millis(10));
+        assert_eq!(metrics.edge_move(0, 1).unwrap().delta, 0.0);
+        assert!(!metrics.has_recent_move(&cycle, 0.01));
+    }
+
+    #[tokio::test]
+    async fn round_robin_seed_advances_and_wraps_each_scan() {
+        let mut edges = vec![(0, 1, 1.0), (1, 2, 1.0), (2, 0, 0.9)];
+        let graph = Arc::new(RwLock::new(GraphCSR::from_edges(3, &mut edges, 10)));
+        let solver = SourceRecorder::default();
+        let sources = solver.0.clone();
+
+        let config = SearcherConfig {
+            source_mode: SourceMode::RoundRobin,
+            ..searcher_config()
+        };
+        let mut searcher =
+            ArbSearcher::new(graph.clone(), config, solver, Arc::new(Metrics::new()));
+        for _ in 0..7 {
+            searcher.scan().await.unwrap();
+        }
+        assert_eq!(*sources.lock().unwrap(), vec![0, 1, 2, 0, 1, 2, 0]);
+
+        // The default seeds every node, so the source never moves.
+        let solver = SourceRecorder::default();
+        let sources = solver.0.clone();
+        let mut searcher =
+            ArbSearcher::new(graph, searcher_config(), solver, Arc::new(Metrics::new()));
+        for _ in 0..3 {
+            searcher.scan().await.unwrap();
+        }
+        assert_eq!(*sources.lock().unwrap(), vec![0, 0, 0]);
+    }
+
+    #[tokio::test]
+    async fn searcher_scans_previous_snapshot_while_rebuild_holds_the_lock() {
+        let mut edges = vec![(0, 1, 1.0), (1, 0, 1.1)];
+        let graph = Arc::new(RwLock::new(GraphCSR::from_edges(2, &mut edges, 10)));
+        let config = SearcherConfig {
+            lock_timeout_ms: Some(50),
+            ..searcher_config()
+        };
+        let searcher =
+            ArbSearcher::new(graph.clone(), config, SPFASolver, Arc::new(Metrics::new()));
+
+        // Without a previous snapshot a timed-out scan is skipped rather than blocking.
+        let long_rebuild = graph.write().await;
+        let skipped = tokio::time::timeout(Duration::from_secs(5), searcher.scan_once())
+            .await
+            .expect("Scan must not block on the write lock");
+        assert!(skipped.unwrap().is_none());
+        drop(long_rebuild);
+
+        assert!(searcher.scan_once().await.unwrap().is_some());
+
+        // A "rebuild" that removes the loop and holds the lock for a long time.
+        let mut long_rebuild = graph.write().await;
+        long_rebuild.rebuild_with_edges(vec![(1, 0, 0.5)]).unwrap();
+        let cycle = tokio::time::timeout(Duration::from_secs(5), searcher.scan_once())
+            .await
+            .expect("Scan must not block on the write lock")
+            .unwrap()
+            .expect("Previous snapshot still has the loop");
+        assert!((cycle.product_rate() - 1.1).abs() < 1e-12);
+
+        drop(long_rebuild);
+        assert!(searcher.scan_once().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn gross_but_not_net_profitable_cycle_goes_to_near_miss_channel() {
+        // 0 -> 1 -> 2 -> 0 gains 0.3% before fees; 30 bps per hop turns it into a loss.
+        let mut edges = vec![(0, 1, 1.001), (1, 2, 1.001), (2, 0, 1.001)];
+        let graph = GraphCSR::from_edges_with_fee(3, &mut edges, 10, 30.0);
+        let config = SearcherConfig {
+            near_miss_margin_bps: Some(50.0),
+            ..searcher_config()
+        };
+        let sink = Arc::new(FlakySink::default());
+        let (sender, mut near_misses) = mpsc::channel(4);
+        let mut searcher = ArbSearcher::new(
+            Arc::new(RwLock::new(graph)),
+            config,
+            SPFASolver,
+            Arc::new(Metrics::new()),
+        )
+        .with_sink(sink.clone())
+        .with_near_miss_channel(sender);
+
+        searcher.search_iteration().await;
+
+        assert!(sink.delivered.lock().unwrap().is_empty());
+        let cycle = near_misses.try_recv().expect("Expected a near-miss");
+        assert_eq!(cycle.canonicalize().nodes(), vec![0, 1, 2]);
+        assert!((cycle.product_rate() - 1.001f64.powi(3)).abs() < 1e-12);
+        assert!(near_misses.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn every_near_miss_of_a_component_is_sent_once() {
+        // One component with two loops gaining 0.2% and 0.3% before fees; 30 bps per
+        // hop turns both into losses.
+        let mut edges = vec![(0, 1, 1.0), (1, 0, 1.002), (1, 2, 1.0), (2, 1, 1.003)];
+        let graph = GraphCSR::from_edges_with_fee(3, &mut edges, 10, 30.0);
+        let config = SearcherConfig {
+            near_miss_margin_bps: Some(50.0),
+            ..searcher_config()
+        };
+        let (sender, mut near_misses) = mpsc::channel(4);
+        let mut searcher = ArbSearcher::new(
+            Arc::new(RwLock::new(graph)),
+            config,
+            SPFASolver,
+            Arc::new(Metrics::new()),
+        )
+        .with_near_miss_channel(sender);
+
+        searcher.search_iteration().await;
+        let mut keys = Vec::new();
+        while let Ok(cycle) = near_misses.try_recv() {
+            keys.push(cycle.canonical_key());
+        }
+        keys.sort();
+        assert_eq!(keys, vec![vec![0, 1], vec![1, 2]]);
+
+        // Unchanged rates: the same near-misses are not sent again.
+        searcher.search_iteration().await;
+        assert!(near_misses.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn two_edge_cycle_is_filtered_by_min_cycle_len() {
+        let mut edges = vec![(0, 1, 1.0), (1, 0, 1.1)];
+        let graph = Arc::new(RwLock::new(GraphCSR::from_edges(2, &mut edges, 10)));
+        let config = SearcherConfig {
+            min_cycle_len: 3,
+            ..searcher_config()
+        };
+
+        let mut searcher = ArbSearcher::new(graph, config, SPFASolver, Arc::new(Metrics::new()));
+        let cycle = searcher.scan_once().await.unwrap().expect("Cycle expected");
+
+        assert_eq!(cycle.path.len(), 2);
+        assert!(searcher.emit(cycle).is_none());
+    }
+
+    #[tokio::test]
+    async fn short_cycle_does_not_hide_a_long_enough_one_in_its_component() {
+        // One component: the 2-cycle 0 <-> 1 (20%) and the triangle 0 -> 1 -> 2 -> 0 (10%).
+        let mut edges = vec![(0, 1, 1.0), (1, 0, 1.2), (1, 2, 1.0), (2, 0, 1.1)];
+        let graph = GraphCSR::from_edges(3, &mut edges, 10);
+        let first = SPFASolver
+            .find_profitable_cycle(&graph, 0, 4)
+            .unwrap()
+            .expect("Cycle expected");
+        assert_eq!(first.path.len(), 2);
+
+        let config = SearcherConfig {
+            min_cycle_len: 3,
+            ..searcher_config()
+        };
+        let mut searcher = ArbSearcher::new(
+            Arc::new(RwLock::new(graph)),
+            config,
+            SPFASolver,
+            Arc::new(Metrics::new()),
+        );
+        let cycles = searcher.scan().await.unwrap();
+
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(cycles[0].canonical_key(), vec![0, 1, 2]);
+        assert!(searcher.emit(cycles[0].clone()).is_some());
+    }
+
+    #[tokio::test]
+    async fn only_cycles_touching_a_base_node_are_emitted() {
+        // Two disjoint profitable loops: {0, 1} and {2, 3}; only node 3 is a base node.
+        let mut edges = vec![(0, 1, 1.0), (1, 0, 1.1), (2, 3, 1.0), (3, 2, 1.1)];
+        let graph = Arc::new(RwLock::new(GraphCSR::from_edges(4, &mut edges, 10)));
+        let config = SearcherConfig {
+            base_nodes: vec![3],
+            ..searcher_config()
+        };
+
+        let mut searcher = ArbSearcher::new(graph, config, SPFASolver, Arc::new(Metrics::new()));
+        let cycles = searcher.scan_components(1).await.unwrap();
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(cycles[0].canonical_key(), vec![2, 3]);
+
+        // A cycle found elsewhere is still checked when emitting.
+        let cycle = searcher.scan_once().await.unwrap().expect("Cycle expected");
+        assert_eq!(cycle.canonical_key(), vec![0, 1]);
+        assert!(searcher.emit(cycle).is_none());
+    }
+
+    #[tokio::test]
+    async fn cycle_missing_base_nodes_does_not_hide_one_through_a_base_node() {
+        // One component: 0 <-> 1 (20%) misses base node 2; 1 <-> 2 (10%) visits it.
+        let mut edges = vec![(0, 1, 1.0), (1, 0, 1.2), (1, 2, 1.0), (2, 1, 1.1)];
+        let graph = GraphCSR::from_edges(3, &mut edges, 10);
+        let first = SPFASolver
+            .find_profitable_cycle(&graph, 0, 4)
+            .unwrap()
+            .expect("Cycle expected");
+        assert_eq!(first.canonical_key(), vec![0, 1]);
+
+        let config = SearcherConfig {
+            base_nodes: vec![2],
+            ..searcher_config()
+        };
+        let mut searcher = ArbSearcher::new(
+            Arc::new(RwLock::new(graph)),
+            config,
+            SPFASolver,
+            Arc::new(Metrics::new()),
+        );
+        let cycles = searcher.scan().await.unwrap();
+
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(cycles[0].canonical_key(), vec![1, 2]);
+        assert!(searcher.emit(cycles[0].clone()).is_some());
+    }
+
+    #[tokio::test]
+    async fn thin_edges_are_left_out_of_the_search() {
+        // One component: 0 <-> 1 (20%) runs through a thin 1 -> 0 pool; 1 <-> 2 (10%) is deep.
+        let mut edges = vec![(0, 1, 1.0), (1, 0, 1.2), (1, 2, 1.0), (2, 1, 1.1)];
+        let graph = Arc::new(RwLock::new(GraphCSR::from_edges(3, &mut edges, 10)));
+        let config = SearcherConfig {
+            min_liquidity: Some(1_000.0),
+            edge_liquidity: vec![
+                EdgeLiquidity {
+                    from: 1,
+                    to: 0,
+                    liquidity: 50.0,
+                },
+                EdgeLiquidity {
+                    from: 1,
+                    to: 2,
+                    liquidity: 5_000.0,
+                },
+            ],
+            ..searcher_config()
+        };
+
+        let mut searcher =
+            ArbSearcher::new(graph.clone(), config, SPFASolver, Arc::new(Metrics::new()));
+        let cycles = searcher.scan().await.unwrap();
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(cycles[0].canonical_key(), vec![1, 2]);
+
+        // The shared graph keeps the thin edge; a cycle found on it is still not emitted.
+        let thin = searcher.scan_once().await.unwrap().expect("Cycle expected");
+        assert_eq!(thin.canonical_key(), vec![0, 1]);
+        assert!(searcher.emit(thin).is_none());
+        assert_eq!(graph.read().await.edge_targets.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn component_search_stops_once_the_budget_is_spent() {
+        // Five disjoint profitable 2-cycles, one component each.
+        let mut edges: Vec<Edge> = (0..5)
+            .flat_map(|i| [(2 * i, 2 * i + 1, 1.0), (2 * i + 1, 2 * i, 1.1)])
+            .collect();
+        let graph = Arc::new(RwLock::new(GraphCSR::from_edges(10, &mut edges, 20)));
+        let clock = Arc::new(MockClock::new());
+        let solver = SlowSolver {
+            clock: clock.clone(),
+            searches: Arc::new(AtomicUsize::new(0)),
+        };
+        let searches = solver.searches.clone();
+        let config = SearcherConfig {
+            search_budget_ms: Some(25),
+            ..searcher_config()
+        };
+
+        let mut searcher =
+            ArbSearcher::new(graph, config, solver, Arc::new(Metrics::new())).with_clock(clock);
+        let first = searcher.scan().await.unwrap();
+
+        // Searches start at 0ms, 10ms and 20ms; at 30ms the 25ms budget is spent.
+        assert_eq!(searches.load(Ordering::SeqCst), 3);
+        assert_eq!(first.len(), 3);
+
+        // The next scan starts with the two skipped components.
+        let second = searcher.scan().await.unwrap();
+        assert_eq!(searches.load(Ordering::SeqCst), 6);
+        let found: HashSet<_> = first
+            .iter()
+            .chain(&second)
+            .map(WeightedCycle::canonical_key)
+            .collect();
+        assert_eq!(found.len(), 5);
+    }
+
+    #[tokio::test]
+    async fn best_opportunity_watch_follows_the_latest_scan() {
+        let mut edges = vec![(0, 1, 1.0), (1, 0, 1.1)];
+        let graph = Arc::new(RwLock::new(GraphCSR::from_edges(2, &mut edges, 10)));
+        let (best_sender, best) = watch::channel(None);
+
+        let mut searcher = ArbSearcher::new(
+            graph.clone(),
+            searcher_config(),
+            SPFASolver,
+            Arc::new(Metrics::new()),
+        )
+        .with_best_opportunity_channel(best_sender);
+        assert!(best.borrow().is_none());
+
+        searcher.search_iteration().await;
+        let key = best.borrow().as_ref().map(WeightedCycle::canonical_key);
+        assert_eq!(key, Some(vec![0, 1]));
+
+        // The return leg drops below break-even; the loop disappears.
+        graph
+            .write()
+            .await
+            .rebuild_with_edges(vec![(1, 0, 0.9)])
+            .unwrap();
+        searcher.search_iteration().await;
+        assert!(best.borrow().is_none());
+    }
+
+    #[tokio::test]
+    async fn best_opportunity_watch_skips_unemitted_cycles_and_keeps_incremental_best() {
+        let mut edges = vec![(0, 1, 1.0), (1, 0, 1.1)];
+        let graph = Arc::new(RwLock::new(GraphCSR::from_edges(2, &mut edges, 10)));
+        let clock = Arc::new(MockClock::new());
+        let dirty = Arc::new(DirtyNodes::new());
+        let (best_sender, best) = watch::channel(None);
+        let config = SearcherConfig {
+            scan_mode: ScanMode::Incremental,
+            warmup_seconds: 1,
+            ..searcher_config()
+        };
+
+        let mut searcher = ArbSearcher::new(graph, config, SPFASolver, Arc::new(Metrics::new()))
+            .with_clock(clock.clone())
+            .with_dirty_nodes(dirty.clone())
+            .with_best_opportunity_channel(best_sender);
+
+        // Found while warming up, so not emitted and not published.
+        searcher.search_iteration().await;
+        assert!(best.borrow().is_none());
+
+        clock.advance(Duration::from_secs(1));
+        dirty.mark(&[(0, 1, 1.0)]);
+        searcher.search_iteration().await;
+        let key = best.borrow().as_ref().map(WeightedCycle::canonical_key);
+        assert_eq!(key, Some(vec![0, 1]));
+
+        // Nothing touched: the scan finds nothing, but the loop is still there.
+        searcher.search_iteration().await;
+        let key = best.borrow().as_ref().map(WeightedCycle::canonical_key);
+        assert_eq!(key, Some(vec![0, 1]));
+    }
+
+    #[tokio::test]
+    async fn cycles_are_suppressed_during_warmup() {
+        let mut edges = vec![(0, 1, 1.0), (1, 0, 1.1)];
+        let graph = Arc::new(RwLock::new(GraphCSR::from_edges(2, &mut edges, 10)));
+        let config = SearcherConfig {
+            warmup_seconds: 1,
+            ..searcher_config()
+        };
+
+        let clock = Arc::new(MockClock::new());
+        let mut searcher = ArbSearcher::new(graph, config, SPFASolver, Arc::new(Metrics::new()))
+            .with_clock(clock.clone());
+
+        let cycle = searcher.scan_once().await.unwrap().expect("Cycle expected");
+        assert!(searcher.emit(cycle).is_none());
+
+        clock.advance(Duration::from_secs(1));
+
+        let cycle = searcher.scan_once().await.unwrap().expect("Cycle expected");
+        assert!(searcher.emit(cycle).is_some());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn two_workers_emit_a_cycle_per_independent_component() {
+        // Two disjoint profitable triangles: {0, 1, 2} and {3, 4, 5}.
+        let mut edges = vec![
+            (0, 1, 1.0),
+            (1, 2, 1.0),
+            (2, 0, 1.1),
+            (3, 4, 1.0),
+            (4, 5, 1.0),
+            (5, 3, 1.2),
+        ];
+        let graph = Arc::new(RwLock::new(GraphCSR::from_edges(6, &mut edges, 10)));
+        let config = SearcherConfig {
+            workers: 2,
+            ..searcher_config()
+        };
+
+        let mut searcher = ArbSearcher::new(graph, config, SPFASolver, Arc::new(Metrics::new()));
+        let cycles = searcher.scan_components(2).await.unwrap();
+
+        let keys: Vec<_> = cycles.iter().map(WeightedCycle::canonical_key).collect();
+        assert_eq!(keys, vec![vec![0, 1, 2], vec![3, 4, 5]]);
+        for cycle in cycles {
+            assert!(searcher.emit(cycle).is_some());
+        }
+    }
+
+    #[tokio::test]
+    async fn emitted_opportunity_is_valid_for_configured_ttl() {
+        let mut edges = vec![(0, 1, 1.0), (1, 0, 1.1)];
+        let graph = Arc::new(RwLock::new(GraphCSR::from_edges(2, &mut edges, 10)));
+        let config = SearcherConfig {
+            opportunity_ttl_ms: Some(5_000),
+            ..searcher_config()
+        };
+
+        let clock = Arc::new(MockClock::new());
+        clock.advance(Duration::from_secs(3));
+
+        let mut searcher = ArbSearcher::new(graph, config, SPFASolver, Arc::new(Metrics::new()))
+            .with_clock(clock.clone());
+        let cycle = searcher.scan_once().await.unwrap().expect("Cycle expected");
+        let opportunity = searcher.emit(cycle).expect("Cycle should be emitted");
+
+        // Stamped from the searcher's clock, not the wall clock.
+        let now = clock.epoch_ms();
+        assert_eq!(opportunity.valid_until_epoch, now + 5_000);
+        assert!(!opportunity.is_expired_at(now + 5_000));
+        assert!(opportunity.is_expired_at(now + 5_001));
+    }
+
+    #[tokio::test]
+    async fn mock_clock_drives_exactly_three_search_iterations() {
+        let mut edges = vec![(0, 1, 1.0), (1, 0, 1.1)];
+        let graph = Arc::new(RwLock::new(GraphCSR::from_edges(2, &mut edges, 10)));
+        let clock = Arc::new(MockClock::new());
+        let solver = CountingSolver::default();
+        let scans = solver.0.clone();
+
+        let searcher = ArbSearcher::new(graph, searcher_config(), solver, Arc::new(Metrics::new()))
+            .with_clock(clock.clone());
+        let handle = tokio::spawn(searcher.seacrh_for_arbs());
+        // Let the searcher start its ticker before time moves.
+        tokio::task::yield_now().await;
+
+        for expected in 1..=3 {
+            clock.advance(Duration::from_secs(1));
+            tokio::time::timeout(Duration::from_secs(5), async {
+                while scans.load(Ordering::SeqCst) < expected {
+                    tokio::task::yield_now().await;
+                }
+            })
+            .await
+            .expect("Searcher should scan once per tick");
+        }
+
+        // Without another advance, no further scan happens.
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(scans.load(Ordering::SeqCst), 3);
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn shutdown_runs_one_final_scan_and_stops_the_loop() {
+        let mut edges = vec![(0, 1, 1.0), (1, 0, 1.1)];
+        let graph = Arc::new(RwLock::new(GraphCSR::from_edges(2, &mut edges, 10)));
+        let clock = Arc::new(MockClock::new());
+        let solver = CountingSolver::default();
+        let scans = solver.0.clone();
+        let (stop, shutdown) = watch::channel(false);
+
+        let searcher = ArbSearcher::new(graph, searcher_config(), solver, Arc::new(Metrics::new()))
+            .with_clock(clock)
+            .with_shutdown(shutdown);
+        let handle = tokio::spawn(searcher.seacrh_for_arbs());
+        tokio::task::yield_now().await;
+        assert_eq!(scans.load(Ordering::SeqCst), 0);
+
+        // No tick is due: the scan comes from the shutdown alone.
+        stop.send(true).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), handle)
+            .await
+            .expect("Searcher should stop after the final scan")
+            .unwrap()
+            .unwrap();
+        assert_eq!(scans.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn flaky_sink_receives_cycle_exactly_once_after_retries() {
+        let mut edges = vec![(0, 1, 1.0), (1, 0, 1.1)];
+        let graph = Arc::new(RwLock::new(GraphCSR::from_edges(2, &mut edges, 10)));
+        let clock = Arc::new(MockClock::new());
+        let sink = Arc::new(FlakySink {
+            failures: 3,
+            ..Default::default()
+        });
+        let metrics = Arc::new(Metrics::new());
+
+        let mut searcher = ArbSearcher::new(graph, searcher_config(), SPFASolver, metrics.clone())
+            .with_clock(clock.clone())
+            .with_sink(sink.clone());
+        let cycle = searcher.scan_once().await.unwrap().expect("Cycle expected");
+        let key = cycle.canonical_key();
+        assert!(searcher.report(cycle).await.is_some());
+        assert_eq!(searcher.pending_retries(), 1);
+
+        // Backoff is 100ms, 200ms, 400ms; step well past each one.
+        for _ in 0..10 {
+            clock.advance(Duration::from_millis(100));
+            searcher.retry_deliveries().await;
+        }
+
+        assert_eq!(sink.attempts.load(Ordering::SeqCst), 4);
+        let delivered = sink.delivered.lock().unwrap();
+        assert_eq!(delivered.len(), 1);
+        assert_eq!(delivered[0].cycle.canonical_key(), key);
+        assert_eq!(searcher.pending_retries(), 0);
+        assert_eq!(metrics.dropped_deliveries(), 0);
+    }
+
+    #[tokio::test]
+    async fn opportunity_expiring_while_queued_is_not_redelivered() {
+        let mut edges = vec![(0, 1, 1.0), (1, 0, 1.1)];
+        let graph = Arc::new(RwLock::new(GraphCSR::from_edges(2, &mut edges, 10)));
+        let clock = Arc::new(MockClock::new());
+        let sink = Arc::new(FlakySink {
+            failures: 1,
+            ..Default::default()
+        });
+        let metrics = Arc::new(Metrics::new());
+        let config = SearcherConfig {
+            opportunity_ttl_ms: Some(50),
+            ..searcher_config()
+        };
+
+        let mut searcher = ArbSearcher::new(graph, config, SPFASolver, metrics.clone())
+            .with_clock(clock.clone())
+            .with_sink(sink.clone());
+        let cycle = searcher.scan_once().await.unwrap().expect("Cycle expected");
+        searcher.report(cycle).await;
+        assert_eq!(searcher.pending_retries(), 1);
+
+        // The 100ms backoff outlasts the 50ms validity window.
+        clock.advance(Duration::from_millis(100));
+        searcher.retry_deliveries().await;
+
+        assert_eq!(sink.attempts.load(Ordering::SeqCst), 1);
+        assert!(sink.delivered.lock().unwrap().is_empty());
+        assert_eq!(searcher.pending_retries(), 0);
+        assert_eq!(metrics.dropped_deliveries(), 1);
+    }
+
+    #[tokio::test]
+    async fn delivery_is_dropped_and_counted_after_max_attempts() {
+        let mut edges = vec![(0, 1, 1.0), (1, 0, 1.1)];
+        let graph = Arc::new(RwLock::new(GraphCSR::from_edges(2, &mut edges, 10)));
+        let clock = Arc::new(MockClock::new());
+        let sink = Arc::new(FlakySink {
+            failures: usize::MAX,
+            ..Default::default()
+        });
+        let metrics = Arc::new(Metrics::new());
+        let config = SearcherConfig {
+            sink_max_attempts: 2,
+            ..searcher_config()
+        };
+
+        let mut searcher = ArbSearcher::new(graph, config, SPFASolver, metrics.clone())
+            .with_clock(clock.clone())
+            .with_sink(sink.clone());
+        let cycle = searcher.scan_once().await.unwrap().expect("Cycle expected");
+        searcher.report(cycle).await;
+
+        clock.advance(Duration::from_secs(1));
+        searcher.retry_deliveries().await;
+
+        assert_eq!(sink.attempts.load(Ordering::SeqCst), 2);
+        assert_eq!(searcher.pending_retries(), 0);
+        assert_eq!(metrics.dropped_deliveries(), 1);
+    }
+
+    #[tokio::test]
+    async fn incremental_scan_skips_untouched_components() {
+        // Two disjoint profitable triangles: {0, 1, 2} and {3, 4, 5}.
+        let mut edges = vec![
+            (0, 1, 1.0),
+            (1, 2, 1.0),
+            (2, 0, 1.1),
+            (3, 4, 1.0),
+            (4, 5, 1.0),
+            (5, 3, 1.2),
+        ];
+        let graph = Arc::new(RwLock::new(GraphCSR::from_edges(6, &mut edges, 10)));
+        let dirty = Arc::new(DirtyNodes::new());
+
+        let solver = CountingSolver::default();
+        let scans = solver.0.clone();
+        let config = SearcherConfig {
+            scan_mode: ScanMode::Incremental,
+            ..searcher_config()
+        };
+        let mut searcher = ArbSearcher::new(graph, config, solver, Arc::new(Metrics::new()))
+            .with_dirty_nodes(dirty.clone());
+
+        // No baseline yet: both components are searched.
+        assert_eq!(searcher.scan().await.unwrap().len(), 2);
+        assert_eq!(scans.load(Ordering::SeqCst), 2);
+
+        // Nothing touched: nothing searched.
+        assert!(searcher.scan().await.unwrap().is_empty());
+        assert_eq!(scans.load(Ordering::SeqCst), 2);
+
+        dirty.mark(&[(4, 5, 1.0)]);
+        let cycles = searcher.scan().await.unwrap();
+        assert_eq!(scans.load(Ordering::SeqCst), 3);
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(cycles[0].canonical_key(), vec![3, 4, 5]);
+    }
+
+    #[tokio::test]
+    async fn full_scan_searches_whole_graph_every_time() {
+        let mut edges = vec![
+            (0, 1, 1.0),
+            (1, 2, 1.0),
+            (2, 0, 1.1),
+            (3, 4, 1.0),
+            (4, 5, 1.0),
+            (5, 3, 1.2),
+        ];
+        let graph = Arc::new(RwLock::new(GraphCSR::from_edges(6, &mut edges, 10)));
+        let dirty = Arc::new(DirtyNodes::new());
+
+        let solver = CountingSolver::default();
+        let scans = solver.0.clone();
+        let config = SearcherConfig {
+            workers: 2,
+            ..searcher_config()
+        };
+        let mut searcher = ArbSearcher::new(graph, config, solver, Arc::new(Metrics::new()))
+            .with_dirty_nodes(dirty.clone());
+
+        for round in 1..=3 {
+            assert_eq!(searcher.scan().await.unwrap().len(), 2);
+            assert_eq!(scans.load(Ordering::SeqCst), 2 * round);
         }
     }
 }
@@ -1,14 +1,19 @@
 use async_trait::async_trait;
-use rand::rngs::SmallRng;
 use rand::{Rng, SeedableRng};
-use tokio::sync::mpsc::Sender;
-use tokio::time::{self, Duration};
+use rand_chacha::ChaCha8Rng;
+use std::sync::Arc;
+use tokio::time::Duration;
 
+use super::clock::{Clock, Ticker, TokioClock};
 use super::config::SimulatorConfig;
 use super::error::Error;
-use super::types::UpdateStreamer;
+use super::types::{UpdateSender, UpdateStreamer};
 use common::types::Edge;
 
+/// Rate of every hop of an injected cycle; a `k`-hop loop yields `1.01^k`,
+/// far above anything the random noise can produce.
+pub const INJECTED_HOP_RATE: f64 = 1.01;
+
 /// Produces synthetic edge updates for simulation purposes.
 ///
 /// Generates batches of `EdgeUpdate` events with randomized
@@ -18,16 +23,97 @@ pub struct SimulatorStreamer {
     pub total_nodes: usize, // total nodes in the network
     pub batch_size: usize,  // number of updates per batch
     pub config: SimulatorConfig,
+    clock: Arc<dyn Clock>,
 }
 
 impl SimulatorStreamer {
     pub fn new(mut config: SimulatorConfig) -> Self {
         config.rate_fluctuation_bps /= 100000.0;
+        if let Some(correlation) = &mut config.correlation {
+            correlation.factor_bps /= 100000.0;
+        }
 
         SimulatorStreamer {
             total_nodes: config.total_nodes,
             batch_size: config.batch_size,
             config,
+            clock: Arc::new(TokioClock),
+        }
+    }
+
+    /// Replaces the clock driving the simulation interval (e.g. with a mock in tests).
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Number of node ids in play for the `batch`-th batch (0-based).
+    ///
+    /// All `total_nodes` without `node_growth`; otherwise `initial_nodes` plus
+    /// `nodes_per_batch` for every earlier batch, capped at `total_nodes`.
+    fn active_nodes(&self, batch: usize) -> usize {
+        match &self.config.node_growth {
+            Some(growth) => growth
+                .initial_nodes
+                .saturating_add(growth.nodes_per_batch.saturating_mul(batch))
+                .min(self.total_nodes),
+            None => self.total_nodes,
+        }
+    }
+
+    /// Generates one batch of random updates over node ids `0..nodes` (without the
+    /// injected cycle).
+    ///
+    /// Each rate is `1 + noise`, with `noise` uniform in `±rate_fluctuation_bps`. With
+    /// `correlation` configured, every edge also moves by its source group's common
+    /// factor, drawn once per batch, so related edges tend to move in the same direction.
+    ///
+    /// The RNG call sequence is fixed so a seed always yields the same stream: per batch,
+    /// one draw per correlation group (if any), then exactly three draws per edge in
+    /// this order: source, target offset, noise. The target is `from + offset` modulo
+    /// `nodes` with `offset` in `1..nodes`, so self-loops are avoided
+    /// without resampling. Changes must keep this sequence, or the seeded regression
+    /// test will flag them.
+    fn generate_batch(&self, rng: &mut impl Rng, nodes: usize) -> Vec<Edge> {
+        let noise_range = -self.config.rate_fluctuation_bps..=self.config.rate_fluctuation_bps;
+        let node_range = 0..nodes;
+        // With a single node the offset is 1 and the (unavoidable) self-loop is kept.
+        let offset_range = 1..nodes.max(2);
+
+        let (groups, factors): (usize, Vec<f64>) = match &self.config.correlation {
+            Some(correlation) if correlation.groups > 0 => {
+                let factor_range = -correlation.factor_bps..=correlation.factor_bps;
+                (
+                    correlation.groups,
+                    (0..correlation.groups)
+                        .map(|_| rng.random_range(factor_range.clone()))
+                        .collect(),
+                )
+            }
+            _ => (1, vec![0.0]),
+        };
+
+        (0..self.batch_size)
+            .map(|_| {
+                let from = rng.random_range(node_range.clone());
+                let to = (from + rng.random_range(offset_range.clone())) % nodes;
+                let fluctuation = factors[from % groups] + rng.random_range(noise_range.clone());
+                let new_rate = 1.0 + fluctuation;
+
+                (from, to, new_rate)
+            })
+            .collect()
+    }
+
+    /// Edges of the configured injected cycle, closing back to its first node.
+    fn injected_edges(&self) -> Vec<Edge> {
+        match &self.config.inject_cycle {
+            Some(nodes) if nodes.len() >= 2 => nodes
+                .iter()
+                .zip(nodes.iter().cycle().skip(1))
+                .map(|(&from, &to)| (from, to, INJECTED_HOP_RATE))
+                .collect(),
+            _ => Vec::new(),
         }
     }
 }
@@ -40,29 +126,31 @@ impl UpdateStreamer for SimulatorStreamer {
     /// them via the provided `Sender`. Backpressure is handled
     /// naturally via awaiting on `sender.send()`. Exits gracefully
     /// if the receiver is dropped.
-    async fn run_stream(self, sender: Sender<Vec<Edge>>) -> Result<(), Error> {
-        let mut interval =
-            time::interval(Duration::from_millis(self.config.simulation_interval_ms));
-
-        let mut rng: SmallRng = SmallRng::from_os_rng();
+    ///
+    /// If `inject_cycle` is configured, its edges are appended after the random
+    /// noise in every batch, so they always win over noise on the same pair. With
+    /// `node_growth`, each batch draws from the ids unlocked so far.
+    async fn run_stream(self, sender: UpdateSender) -> Result<(), Error> {
+        self.config.validate()?;
+        let mut interval = Ticker::new(
+            self.clock.clone(),
+            Duration::from_millis(self.config.simulation_interval_ms),
+        );
 
-        let rate_range = -self.config.rate_fluctuation_bps..=self.config.rate_fluctuation_bps;
-        let node_range = 0..self.total_nodes;
+        let mut rng: ChaCha8Rng = match self.config.seed {
+            Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+            None => ChaCha8Rng::from_os_rng(),
+        };
+        let injected = self.injected_edges();
+        let mut batch = 0;
 
         loop {
             interval.tick().await;
 
             // Generate a batch of edge updates
-            let updates: Vec<Edge> = (0..self.batch_size)
-                .map(|_| {
-                    let from = rng.random_range(node_range.clone());
-                    let to = rng.random_range(node_range.clone());
-                    let fluctuation = rng.random_range(rate_range.clone());
-                    let new_rate = 1.0 + fluctuation;
-
-                    (from, to, new_rate)
-                })
-                .collect();
+            let mut updates = self.generate_batch(&mut rng, self.active_nodes(batch));
+            batch += 1;
+            updates.extend_from_slice(&injected);
 
             let size = updates.len();
 
@@ -82,7 +170,15 @@ impl UpdateStreamer for SimulatorStreamer {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tokio::sync::mpsc;
+    use crate::clock::MockClock;
+    use crate::config::{ChannelKind, CorrelationConfig, NodeGrowthConfig};
+    use crate::metrics::Metrics;
+    use crate::types::{SharedGraph, update_channel};
+    use crate::writer::{Writer, tests::writer_config};
+    use arb_solver_core::GraphCSR;
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+    use tokio::sync::{RwLock, mpsc};
     use tokio::time::{Duration, timeout};
 
     const SIM_CONFIG_MOCK: SimulatorConfig = SimulatorConfig {
@@ -90,6 +186,10 @@ mod tests {
         batch_size: 5,
         simulation_interval_ms: 100,
         rate_fluctuation_bps: 0.5,
+        inject_cycle: None,
+        correlation: None,
+        seed: None,
+        node_growth: None,
     };
 
     /// SimulatorStreamer can be created correctly.
@@ -99,6 +199,7 @@ mod tests {
             total_nodes: 10,
             batch_size: 5,
             config: SIM_CONFIG_MOCK,
+            clock: Arc::new(TokioClock),
         };
         assert_eq!(sim.total_nodes, 10);
         assert_eq!(sim.batch_size, 5);
@@ -111,13 +212,14 @@ mod tests {
             total_nodes: 10,
             batch_size: 5,
             config: SIM_CONFIG_MOCK,
+            clock: Arc::new(TokioClock),
         };
 
         let (tx, mut rx) = mpsc::channel(10);
 
         // Run simulator for one tick using timeout to avoid infinite loop
         tokio::spawn(async move {
-            let _ = sim.run_stream(tx).await;
+            let _ = sim.run_stream(tx.into()).await;
         });
 
         // Receive first batch
@@ -136,12 +238,13 @@ mod tests {
             total_nodes: 10,
             batch_size: 50,
             config: SIM_CONFIG_MOCK,
+            clock: Arc::new(TokioClock),
         };
 
         let (tx, mut rx) = mpsc::channel(10);
 
         tokio::spawn(async move {
-            let _ = sim.run_stream(tx).await;
+            let _ = sim.run_stream(tx.into()).await;
         });
 
         let updates = timeout(Duration::from_millis(200), rx.recv())
@@ -160,4 +263,225 @@ mod tests {
             );
         }
     }
+
+    /// A fixed seed always produces the same stream. If this fails, the RNG call
+    /// sequence documented on `generate_batch` changed.
+    #[tokio::test]
+    async fn test_seeded_stream_is_reproducible() {
+        let sim = SimulatorStreamer::new(SimulatorConfig {
+            seed: Some(42),
+            ..SIM_CONFIG_MOCK
+        });
+        let (tx, mut rx) = mpsc::channel(10);
+        tokio::spawn(async move {
+            let _ = sim.run_stream(tx.into()).await;
+        });
+
+        let updates = timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("Did not receive batch")
+            .expect("Channel closed");
+        assert_eq!(
+            &updates[..3],
+            &[
+                (2, 9, 1.0000045027540767),
+                (7, 1, 1.000001273605212),
+                (7, 0, 0.999996499588703),
+            ]
+        );
+    }
+
+    /// Targets are drawn as an offset from the source, so no self-loops are generated.
+    #[test]
+    fn test_generated_edges_have_no_self_loops() {
+        let sim = SimulatorStreamer::new(SimulatorConfig {
+            total_nodes: 3,
+            batch_size: 500,
+            ..SIM_CONFIG_MOCK
+        });
+        let batch = sim.generate_batch(&mut ChaCha8Rng::seed_from_u64(1), sim.total_nodes);
+
+        assert!(batch.iter().all(|&(from, to, _)| from != to && to < 3));
+    }
+
+    /// With node growth, ids are unlocked batch by batch, and a writer fed the stream
+    /// grows the graph to each new id without losing the edges it already had.
+    #[tokio::test]
+    async fn test_node_growth_ramps_node_count_and_preserves_edges() {
+        let sim = SimulatorStreamer::new(SimulatorConfig {
+            total_nodes: 40,
+            batch_size: 50,
+            node_growth: Some(NodeGrowthConfig {
+                initial_nodes: 4,
+                nodes_per_batch: 4,
+            }),
+            ..SIM_CONFIG_MOCK
+        });
+
+        // A rebuild limit of 1 commits every batch; record the graph after each commit.
+        let mut graph = GraphCSR::from_edges(0, &mut [], 1);
+        let rebuilt = Arc::new(Mutex::new(Vec::new()));
+        let log = rebuilt.clone();
+        graph.set_on_rebuild(move |graph| {
+            let edges: HashSet<(usize, usize)> =
+                graph.to_edges().iter().map(|&(u, v, _)| (u, v)).collect();
+            log.lock().unwrap().push((graph.num_nodes, edges));
+        });
+        let graph: SharedGraph = Arc::new(RwLock::new(graph));
+        let config = writer_config();
+        let (sender, receiver) = update_channel(ChannelKind::Bounded, 4);
+        let writer = Writer::new(graph.clone(), receiver, config, Arc::new(Metrics::new()));
+        let handle = writer.spawn_task();
+
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        let mut expected_nodes = Vec::new();
+        let mut max_id = 0;
+        for batch in 0..10 {
+            let active = sim.active_nodes(batch);
+            assert_eq!(active, 4 + 4 * batch);
+
+            let updates = sim.generate_batch(&mut rng, active);
+            assert!(
+                updates
+                    .iter()
+                    .all(|&(from, to, _)| from < active && to < active)
+            );
+            max_id = updates
+                .iter()
+                .map(|&(from, to, _)| from.max(to))
+                .fold(max_id, usize::max);
+            expected_nodes.push(max_id + 1);
+            sender.send(updates).await.unwrap();
+        }
+        drop(sender);
+        handle.await.unwrap().unwrap();
+
+        let rebuilt = rebuilt.lock().unwrap();
+        let node_counts: Vec<usize> = rebuilt.iter().map(|(nodes, _)| *nodes).collect();
+        assert_eq!(node_counts, expected_nodes);
+        for (batch, pair) in rebuilt.windows(2).enumerate() {
+            assert!(
+                pair[0].1.is_subset(&pair[1].1),
+                "batch {} lost edges",
+                batch + 1
+            );
+        }
+        assert_eq!(sim.active_nodes(100), 40);
+    }
+
+    /// Edges of the same group share their common factor, so they differ by noise alone,
+    /// however large the factor.
+    #[test]
+    fn test_correlated_edges_move_together() {
+        let sim = SimulatorStreamer::new(SimulatorConfig {
+            batch_size: 200,
+            correlation: Some(CorrelationConfig {
+                groups: 3,
+                factor_bps: 500.0,
+            }),
+            ..SIM_CONFIG_MOCK
+        });
+        let mut rng = ChaCha8Rng::seed_from_u64(42);
+        let noise = sim.config.rate_fluctuation_bps;
+
+        for _ in 0..20 {
+            let batch = sim.generate_batch(&mut rng, sim.total_nodes);
+            for group in 0..3 {
+                let moves: Vec<f64> = batch
+                    .iter()
+                    .filter(|&&(from, _, _)| from % 3 == group)
+                    .map(|&(_, _, rate)| rate - 1.0)
+                    .collect();
+                let spread = moves.iter().copied().fold(f64::NEG_INFINITY, f64::max)
+                    - moves.iter().copied().fold(f64::INFINITY, f64::min);
+                assert!(
+                    spread <= 2.0 * noise + 1e-12,
+                    "group {} spread beyond the noise: {:?}",
+                    group,
+                    moves
+                );
+            }
+        }
+    }
+
+    /// Batches are spaced by the configured interval.
+    #[tokio::test]
+    async fn test_custom_interval_spaces_batches() {
+        let clock = Arc::new(MockClock::new());
+        let sim = SimulatorStreamer::new(SimulatorConfig {
+            simulation_interval_ms: 250,
+            ..SIM_CONFIG_MOCK
+        })
+        .with_clock(clock.clone());
+
+        let (tx, mut rx) = mpsc::channel(10);
+        tokio::spawn(async move {
+            let _ = sim.run_stream(tx.into()).await;
+        });
+
+        // The first batch is sent immediately.
+        timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("Did not receive first batch");
+
+        clock.advance(Duration::from_millis(249));
+        assert!(
+            timeout(Duration::from_millis(50), rx.recv()).await.is_err(),
+            "Batch sent before the interval elapsed"
+        );
+
+        clock.advance(Duration::from_millis(1));
+        timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("Did not receive batch after one interval");
+    }
+
+    /// A zero interval is rejected instead of spinning.
+    #[tokio::test]
+    async fn test_zero_interval_is_rejected() {
+        let sim = SimulatorStreamer::new(SimulatorConfig {
+            simulation_interval_ms: 0,
+            ..SIM_CONFIG_MOCK
+        });
+        let (tx, _rx) = mpsc::channel(10);
+
+        assert!(matches!(
+            sim.run_stream(tx.into()).await,
+            Err(Error::ConfigLoadError(_))
+        ));
+    }
+
+    /// The injected cycle is present in the stream and detected once applied to a graph.
+    #[tokio::test]
+    async fn test_injected_cycle_is_streamed_and_detected() {
+        let sim = SimulatorStreamer::new(SimulatorConfig {
+            inject_cycle: Some(vec![2, 5, 7]),
+            ..SIM_CONFIG_MOCK
+        });
+
+        let (tx, mut rx) = mpsc::channel(10);
+
+        tokio::spawn(async move {
+            let _ = sim.run_stream(tx.into()).await;
+        });
+
+        let updates = timeout(Duration::from_millis(200), rx.recv())
+            .await
+            .expect("Did not receive batch")
+            .expect("Channel closed");
+
+        assert_eq!(updates.len(), 5 + 3);
+        for hop in [(2, 5), (5, 7), (7, 2)] {
+            assert!(updates.contains(&(hop.0, hop.1, INJECTED_HOP_RATE)));
+        }
+
+        let mut graph = GraphCSR::from_edges(0, &mut [], 1);
+        graph.rebuild_with_edges(updates).unwrap();
+
+        let cycle = graph
+            .best_triangle()
+            .expect("Injected cycle should be detected");
+        assert_eq!(cycle.canonical_key(), vec![2, 5, 7]);
+        assert!((cycle.product_rate() - INJECTED_HOP_RATE.powi(3)).abs() < 1e-12);
+    }
 }
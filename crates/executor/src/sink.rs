@@ -0,0 +1,214 @@
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use tokio::time::{Duration, Instant};
+
+use super::error::Error;
+use super::types::Opportunity;
+
+/// Destination for emitted opportunities (e.g. a webhook or an execution engine).
+///
+/// Failures are treated as transient: the searcher queues the opportunity and
+/// retries it with backoff (see [`RetryQueue`]).
+#[async_trait::async_trait]
+pub trait CycleSink: Send + Sync + 'static {
+    async fn deliver(&self, opportunity: &Opportunity) -> Result<(), Error>;
+}
+
+/// Sink appending every opportunity to a file as one JSON line.
+pub struct FileSink {
+    path: PathBuf,
+}
+
+impl FileSink {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        FileSink { path: path.into() }
+    }
+}
+
+#[async_trait::async_trait]
+impl CycleSink for FileSink {
+    async fn deliver(&self, opportunity: &Opportunity) -> Result<(), Error> {
+        let line = serde_json::to_string(opportunity)?;
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| writeln!(file, "{}", line))
+            .map_err(|e| Error::SinkError(format!("{}: {}", self.path.display(), e)))
+    }
+}
+
+/// An opportunity whose delivery failed and is waiting to be retried.
+#[derive(Debug, Clone)]
+pub struct PendingDelivery {
+    pub opportunity: Opportunity,
+    /// Delivery attempts made so far (at least 1).
+    pub attempts: u32,
+    pub next_attempt: Instant,
+}
+
+/// Bounded queue of failed deliveries, retried with exponential backoff.
+///
+/// After the `n`-th failed attempt the next one is scheduled `base_backoff * 2^(n-1)`
+/// later. An entry is dropped once it has failed `max_attempts` times, or when the
+/// queue is full and a newer failure needs the slot (the oldest entry goes).
+pub struct RetryQueue {
+    entries: VecDeque<PendingDelivery>,
+    capacity: usize,
+    max_attempts: u32,
+    base_backoff: Duration,
+}
+
+impl RetryQueue {
+    pub fn new(capacity: usize, max_attempts: u32, base_backoff: Duration) -> Self {
+        RetryQueue {
+            entries: VecDeque::new(),
+            capacity,
+            max_attempts: max_attempts.max(1),
+            base_backoff,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Records a failed attempt at `now`.
+    ///
+    /// Returns the number of deliveries dropped as a result: the entry itself if it
+    /// is out of attempts (or the queue has no room at all), or the oldest queued
+    /// entry if it was evicted to make room.
+    pub fn record_failure(&mut self, opportunity: Opportunity, attempts: u32, now: Instant) -> u64 {
+        if attempts >= self.max_attempts || self.capacity == 0 {
+            return 1;
+        }
+
+        let backoff = self.base_backoff * 2u32.saturating_pow(attempts - 1);
+        let mut dropped = 0;
+        if self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+            dropped += 1;
+        }
+        self.entries.push_back(PendingDelivery {
+            opportunity,
+            attempts,
+            next_attempt: now + backoff,
+        });
+        dropped
+    }
+
+    /// Drops every entry whose opportunity has expired at `epoch_ms`, so a stale
+    /// opportunity is never re-delivered. Returns the number dropped.
+    pub fn drop_expired(&mut self, epoch_ms: u64) -> u64 {
+        let before = self.entries.len();
+        self.entries
+            .retain(|entry| !entry.opportunity.is_expired_at(epoch_ms));
+        (before - self.entries.len()) as u64
+    }
+
+    /// Removes and returns every entry whose retry is due at `now`.
+    pub fn take_due(&mut self, now: Instant) -> Vec<PendingDelivery> {
+        let (due, waiting): (VecDeque<_>, VecDeque<_>) = self
+            .entries
+            .drain(..)
+            .partition(|entry| entry.next_attempt <= now);
+        self.entries = waiting;
+        due.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::types::WeightedCycle;
+
+    fn opportunity() -> Opportunity {
+        expiring_at(0)
+    }
+
+    fn expiring_at(valid_until_epoch: u64) -> Opportunity {
+        Opportunity {
+            cycle: WeightedCycle {
+                path: vec![(0, 1, 1.1), (1, 0, 1.0)],
+                rates: vec![1.1, 1.0],
+                log_rate_sum: -f64::ln(1.1),
+            },
+            valid_until_epoch,
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_and_entries_drop_after_max_attempts() {
+        let now = Instant::now();
+        let mut queue = RetryQueue::new(4, 3, Duration::from_millis(100));
+
+        assert_eq!(queue.record_failure(opportunity(), 1, now), 0);
+        assert!(queue.take_due(now + Duration::from_millis(99)).is_empty());
+        let due = queue.take_due(now + Duration::from_millis(100));
+        assert_eq!(due.len(), 1);
+
+        assert_eq!(queue.record_failure(opportunity(), 2, now), 0);
+        assert!(queue.take_due(now + Duration::from_millis(199)).is_empty());
+        assert_eq!(queue.take_due(now + Duration::from_millis(200)).len(), 1);
+
+        assert_eq!(queue.record_failure(opportunity(), 3, now), 1);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn full_queue_evicts_oldest() {
+        let now = Instant::now();
+        let mut queue = RetryQueue::new(1, 5, Duration::from_millis(10));
+
+        assert_eq!(queue.record_failure(opportunity(), 1, now), 0);
+        assert_eq!(queue.record_failure(opportunity(), 2, now), 1);
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.take_due(now + Duration::from_secs(1))[0].attempts, 2);
+    }
+
+    #[test]
+    fn expired_entries_are_dropped_before_retry() {
+        let now = Instant::now();
+        let mut queue = RetryQueue::new(4, 5, Duration::from_millis(10));
+        queue.record_failure(expiring_at(100), 1, now);
+        queue.record_failure(expiring_at(200), 1, now);
+
+        assert_eq!(queue.drop_expired(150), 1);
+        let due = queue.take_due(now + Duration::from_secs(1));
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].opportunity.valid_until_epoch, 200);
+    }
+
+    #[tokio::test]
+    async fn file_sink_appends_one_json_line_per_opportunity() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("opportunities.jsonl");
+        let sink = FileSink::new(&path);
+
+        sink.deliver(&expiring_at(100)).await.unwrap();
+        sink.deliver(&expiring_at(200)).await.unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let deadlines: Vec<u64> = contents
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .map(|value| value["valid_until_epoch"].as_u64().unwrap())
+            .collect();
+        assert_eq!(deadlines, vec![100, 200]);
+    }
+
+    #[tokio::test]
+    async fn file_sink_reports_unwritable_path_as_sink_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = FileSink::new(dir.path().join("missing").join("opportunities.jsonl"));
+
+        let err = sink.deliver(&opportunity()).await.unwrap_err();
+        assert!(matches!(err, Error::SinkError(_)));
+    }
+}
@@ -0,0 +1,230 @@
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use tokio::time::{self, Duration};
+
+use super::error::Error;
+use super::types::SharedGraph;
+use arb_solver_core::GraphCSR;
+use common::error::Error as ArbSolverError;
+
+/// Loads a graph snapshot (JSON-serialized `GraphCSR`) from `path`.
+///
+/// The CSR arrays are checked for internal consistency before the graph is
+/// returned, so a truncated or hand-edited snapshot cannot cause out-of-bounds
+/// panics later in the solver.
+///
+/// # Errors
+/// - `Error::IoError` if the file cannot be opened.
+/// - `Error::SnapshotError` if the contents are not a valid snapshot.
+/// - `Error::GraphError(InvalidGraph)` if the CSR arrays are inconsistent.
+pub fn load_snapshot(path: impl AsRef<Path>) -> Result<GraphCSR, Error> {
+    let file = File::open(path.as_ref())?;
+    let graph: GraphCSR = serde_json::from_reader(BufReader::new(file))?;
+
+    let num_edges = graph.edge_targets.len();
+    let consistent = graph.node_pointers.len() == graph.num_nodes + 1
+        && graph.node_pointers.last() == Some(&num_edges)
+        && graph.node_pointers.windows(2).all(|w| w[0] <= w[1])
+        && graph.edge_weights.len() == num_edges
+        && graph.edge_source_by_index.len() == num_edges
+        && graph.edge_targets.iter().all(|&v| v < graph.num_nodes)
+        && graph
+            .edge_source_by_index
+            .iter()
+            .all(|&u| u < graph.num_nodes);
+
+    if !consistent {
+        return Err(ArbSolverError::InvalidGraph.into());
+    }
+
+    Ok(graph)
+}
+
+/// Atomically writes `graph` to `path` as a JSON snapshot.
+///
+/// The snapshot is first written and synced to `<path>.tmp`, then renamed over
+/// `path`. A crash mid-write therefore never leaves a truncated snapshot behind;
+/// readers see either the previous snapshot or the new one.
+pub fn save_snapshot(graph: &GraphCSR, path: impl AsRef<Path>) -> Result<(), Error> {
+    let path = path.as_ref();
+    let mut tmp_path = OsString::from(path.as_os_str());
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+
+    {
+        let mut writer = BufWriter::new(File::create(&tmp_path)?);
+        serde_json::to_writer(&mut writer, graph)?;
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+    }
+
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// Background task that periodically persists the shared graph for crash recovery.
+pub struct Snapshotter {
+    graph: SharedGraph,
+    path: PathBuf,
+    interval: u64, // interval in seconds
+}
+
+impl Snapshotter {
+    pub fn new(graph: SharedGraph, path: impl Into<PathBuf>, interval: u64) -> Self {
+        Self {
+            graph,
+            path: path.into(),
+            interval,
+        }
+    }
+
+    /// Writes a snapshot every `interval` seconds.
+    ///
+    /// The read lock is only held while cloning the graph; serialization and disk
+    /// I/O run on the blocking thread pool. Failed writes are logged and retried on
+    /// the next tick.
+    pub async fn run(self) -> Result<(), Error> {
+        println!("Snapshotter ready.");
+
+        let mut interval = time::interval(Duration::from_secs(self.interval));
+
+        // The first tick occurs immediately, but we skip it to wait the full duration
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+
+            let graph_snapshot = {
+                let graph_guard = self.graph.read().await;
+                graph_guard.clone()
+            };
+
+            let path = self.path.clone();
+            let result =
+                tokio::task::spawn_blocking(move || save_snapshot(&graph_snapshot, path)).await?;
+
+            match result {
+                Ok(()) => println!(
+                    "Snapshotter: Graph snapshot written to {}.",
+                    self.path.display()
+                ),
+                Err(e) => eprintln!(
+                    "Snapshotter Error: Failed to write snapshot: {}. Continuing.",
+                    e
+                ),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ScanMode, SearcherConfig, SourceMode};
+    use crate::metrics::Metrics;
+    use crate::searcher::ArbSearcher;
+    use arb_solver_core::solver::SPFASolver;
+    use std::sync::Arc;
+    use tempfile::NamedTempFile;
+    use tokio::sync::RwLock;
+
+    #[tokio::test]
+    async fn searcher_finds_cycle_on_first_scan_after_warm_start() {
+        let mut edges = vec![(0, 1, 0.5), (1, 2, 1.0), (2, 0, 2.1), (2, 3, 0.9)];
+        let graph = GraphCSR::from_edges(4, &mut edges, 10);
+
+        let file = NamedTempFile::new().expect("Failed to create temp file");
+        save_snapshot(&graph, file.path()).unwrap();
+
+        let restored = load_snapshot(file.path()).unwrap();
+        assert_eq!(restored.edge_targets, graph.edge_targets);
+        assert_eq!(restored.edge_weights, graph.edge_weights);
+
+        let config = SearcherConfig {
+            interval_seconds: 1,
+            dedup_capacity: 0,
+            dedup_undirected: false,
+            min_cycle_len: 3,
+            fair_scheduling: false,
+            warmup_seconds: 0,
+            workers: 1,
+            opportunity_ttl_ms: None,
+            sink_retry_capacity: 16,
+            sink_max_attempts: 5,
+            sink_retry_backoff_ms: 100,
+            scan_mode: ScanMode::Full,
+            source_mode: SourceMode::AllNodes,
+            min_edge_move_bps: None,
+            edge_move_window_ms: 10_000,
+            lock_timeout_ms: None,
+            near_miss_margin_bps: None,
+            base_nodes: Vec::new(),
+            search_budget_ms: None,
+            min_liquidity: None,
+            edge_liquidity: Vec::new(),
+        };
+        let searcher = ArbSearcher::new(
+            Arc::new(RwLock::new(restored)),
+            config,
+            SPFASolver,
+            Arc::new(Metrics::new()),
+        );
+
+        let cycle = searcher
+            .scan_once()
+            .await
+            .unwrap()
+            .expect("Searcher should find the cycle on its first scan");
+        assert!(cycle.is_profitable());
+    }
+
+    #[tokio::test]
+    async fn snapshotter_writes_snapshot_after_one_interval() {
+        let mut edges = vec![(0, 1, 1.1), (1, 0, 0.95)];
+        let graph = GraphCSR::from_edges(2, &mut edges, 10);
+        let shared = Arc::new(RwLock::new(graph.clone()));
+
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let path = dir.path().join("graph.json");
+
+        let handle = tokio::spawn(Snapshotter::new(shared, &path, 1).run());
+        tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+        handle.abort();
+
+        let restored = load_snapshot(&path).expect("Snapshot should exist and be valid");
+        assert_eq!(restored.num_nodes, graph.num_nodes);
+        assert_eq!(restored.node_pointers, graph.node_pointers);
+        assert_eq!(restored.edge_targets, graph.edge_targets);
+        assert_eq!(restored.edge_weights, graph.edge_weights);
+        assert!(!dir.path().join("graph.json.tmp").exists());
+    }
+
+    #[test]
+    fn per_edge_fees_survive_snapshot_round_trip() {
+        let mut graph = GraphCSR::from_edges(2, &mut [(0, 1, 1.1), (1, 0, 0.95)], 10);
+        graph.set_edge_fee_bps(0, 1, 25.0).unwrap();
+
+        let file = NamedTempFile::new().expect("Failed to create temp file");
+        save_snapshot(&graph, file.path()).unwrap();
+        let restored = load_snapshot(file.path()).unwrap();
+
+        assert_eq!(restored.edge_fee_multipliers, graph.edge_fee_multipliers);
+        assert_eq!(restored.edge_weights, graph.edge_weights);
+    }
+
+    #[test]
+    fn inconsistent_snapshot_is_rejected() {
+        let mut graph = GraphCSR::from_edges(2, &mut [(0, 1, 1.0)], 10);
+        graph.edge_targets[0] = 5;
+
+        let file = NamedTempFile::new().expect("Failed to create temp file");
+        save_snapshot(&graph, file.path()).unwrap();
+
+        assert!(matches!(
+            load_snapshot(file.path()),
+            Err(Error::GraphError(ArbSolverError::InvalidGraph))
+        ));
+    }
+}
@@ -1,10 +1,14 @@
-use std::sync::Arc;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::sync::RwLock;
-use tokio::sync::mpsc::Sender;
+use tokio::sync::mpsc::{self, error::SendError};
 
+use super::config::ChannelKind;
 use super::error::Error;
 use arb_solver_core::GraphCSR;
-use common::types::Edge;
+use arb_solver_core::traits::GraphSolver;
+use common::types::{Edge, WeightedCycle};
 
 /// A trait defining the contract for any source that generates and streams updates
 /// into the main processing pipeline.
@@ -16,14 +20,241 @@ use common::types::Edge;
 /// implementation can be safely executed by the multi-threaded asynchronous runtime (Tokio).
 #[async_trait::async_trait]
 pub trait UpdateStreamer: Send + Sync + 'static {
-    async fn run_stream(self, sender: Sender<Vec<Edge>>) -> Result<(), Error>;
+    async fn run_stream(self, sender: UpdateSender) -> Result<(), Error>;
+}
+
+/// Sending half of the producer → writer update channel, either bounded or unbounded.
+#[derive(Debug, Clone)]
+pub enum UpdateSender {
+    Bounded(mpsc::Sender<Vec<Edge>>),
+    Unbounded(mpsc::UnboundedSender<Vec<Edge>>),
+}
+
+impl UpdateSender {
+    /// Sends a batch of updates. Only the bounded variant can wait for capacity.
+    pub async fn send(&self, updates: Vec<Edge>) -> Result<(), SendError<Vec<Edge>>> {
+        match self {
+            UpdateSender::Bounded(sender) => sender.send(updates).await,
+            UpdateSender::Unbounded(sender) => sender.send(updates),
+        }
+    }
+}
+
+impl From<mpsc::Sender<Vec<Edge>>> for UpdateSender {
+    fn from(sender: mpsc::Sender<Vec<Edge>>) -> Self {
+        UpdateSender::Bounded(sender)
+    }
+}
+
+impl From<mpsc::UnboundedSender<Vec<Edge>>> for UpdateSender {
+    fn from(sender: mpsc::UnboundedSender<Vec<Edge>>) -> Self {
+        UpdateSender::Unbounded(sender)
+    }
+}
+
+/// Receiving half of the producer → writer update channel.
+#[derive(Debug)]
+pub enum UpdateReceiver {
+    Bounded(mpsc::Receiver<Vec<Edge>>),
+    Unbounded(mpsc::UnboundedReceiver<Vec<Edge>>),
+}
+
+impl UpdateReceiver {
+    /// Receives the next batch, or `None` once every sender has been dropped.
+    pub async fn recv(&mut self) -> Option<Vec<Edge>> {
+        match self {
+            UpdateReceiver::Bounded(receiver) => receiver.recv().await,
+            UpdateReceiver::Unbounded(receiver) => receiver.recv().await,
+        }
+    }
+
+    /// Receives a batch only if one is already queued.
+    pub fn try_recv(&mut self) -> Option<Vec<Edge>> {
+        match self {
+            UpdateReceiver::Bounded(receiver) => receiver.try_recv().ok(),
+            UpdateReceiver::Unbounded(receiver) => receiver.try_recv().ok(),
+        }
+    }
+}
+
+impl From<mpsc::Receiver<Vec<Edge>>> for UpdateReceiver {
+    fn from(receiver: mpsc::Receiver<Vec<Edge>>) -> Self {
+        UpdateReceiver::Bounded(receiver)
+    }
+}
+
+impl From<mpsc::UnboundedReceiver<Vec<Edge>>> for UpdateReceiver {
+    fn from(receiver: mpsc::UnboundedReceiver<Vec<Edge>>) -> Self {
+        UpdateReceiver::Unbounded(receiver)
+    }
+}
+
+/// Creates the producer → writer channel of the configured kind.
+///
+/// `buffer_size` is only used for [`ChannelKind::Bounded`].
+pub fn update_channel(kind: ChannelKind, buffer_size: usize) -> (UpdateSender, UpdateReceiver) {
+    match kind {
+        ChannelKind::Bounded => {
+            let (sender, receiver) = mpsc::channel(buffer_size);
+            (sender.into(), receiver.into())
+        }
+        ChannelKind::Unbounded => {
+            let (sender, receiver) = mpsc::unbounded_channel();
+            (sender.into(), receiver.into())
+        }
+    }
+}
+
+/// Async counterpart of [`GraphSolver`] so cycle searches can be awaited from async tasks.
+///
+/// Every `GraphSolver` that is `Clone + Send + Sync + 'static` gets this trait for free:
+/// the blanket implementation moves the search onto Tokio's blocking thread pool via
+/// `spawn_blocking`, keeping CPU-bound SPFA work off the async worker threads.
+#[async_trait::async_trait]
+pub trait AsyncGraphSolver: Send + Sync + 'static {
+    async fn find_profitable_cycle_async(
+        &self,
+        graph: Arc<GraphCSR>,
+        source: usize,
+        hop_cap: usize,
+    ) -> Result<Option<WeightedCycle>, Error>;
+}
+
+#[async_trait::async_trait]
+impl<S> AsyncGraphSolver for S
+where
+    S: GraphSolver + Clone + Send + Sync + 'static,
+{
+    async fn find_profitable_cycle_async(
+        &self,
+        graph: Arc<GraphCSR>,
+        source: usize,
+        hop_cap: usize,
+    ) -> Result<Option<WeightedCycle>, Error> {
+        let solver = self.clone();
+        let cycle = tokio::task::spawn_blocking(move || {
+            solver.find_profitable_cycle(&graph, source, hop_cap)
+        })
+        .await??;
+
+        Ok(cycle)
+    }
+}
+
+/// A profitable cycle reported by the searcher, valid only until rates are expected to move.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Opportunity {
+    pub cycle: WeightedCycle,
+    /// Unix epoch milliseconds after which the opportunity should be dropped.
+    pub valid_until_epoch: u64,
+}
+
+impl Opportunity {
+    /// Returns `true` if the opportunity has expired at `epoch_ms`.
+    pub fn is_expired_at(&self, epoch_ms: u64) -> bool {
+        epoch_ms > self.valid_until_epoch
+    }
+
+    /// Returns `true` if the opportunity has already expired.
+    pub fn is_expired(&self) -> bool {
+        self.is_expired_at(now_epoch_ms())
+    }
+}
+
+/// Current wall-clock time in Unix epoch milliseconds.
+pub fn now_epoch_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
 }
 
 pub type SharedGraph = Arc<RwLock<GraphCSR>>;
 
+/// Nodes touched by applied edge updates, shared between the writer (which marks them)
+/// and an incremental searcher (which drains them each scan).
+#[derive(Debug, Default)]
+pub struct DirtyNodes {
+    nodes: Mutex<HashSet<usize>>,
+}
+
+impl DirtyNodes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks both endpoints of every edge in `edges`.
+    pub fn mark(&self, edges: &[Edge]) {
+        let mut nodes = self.nodes.lock().unwrap();
+        for &(u, v, _) in edges {
+            nodes.insert(u);
+            nodes.insert(v);
+        }
+    }
+
+    /// Returns and clears the nodes marked since the previous call.
+    pub fn take(&self) -> HashSet<usize> {
+        std::mem::take(&mut *self.nodes.lock().unwrap())
+    }
+}
+
 pub type JoinHandleResult = tokio::task::JoinHandle<Result<(), Error>>;
 
 pub enum DataSource {
     SIM,
-    CSV(String),
+    /// One or more CSV files, streamed in order as a single feed.
+    CSV(Vec<String>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arb_solver_core::solver::SPFASolver;
+    use tokio::time::{Duration, timeout};
+
+    const BURST: usize = 1_000;
+
+    /// Sends a burst of batches while the receiver is held but never drained,
+    /// simulating a writer stuck in a long rebuild.
+    async fn burst_send(kind: ChannelKind) -> bool {
+        let (sender, _stalled_receiver) = update_channel(kind, 10);
+
+        timeout(Duration::from_millis(500), async move {
+            for i in 0..BURST {
+                sender.send(vec![(i, i + 1, 1.0)]).await.unwrap();
+            }
+        })
+        .await
+        .is_ok()
+    }
+
+    #[tokio::test]
+    async fn unbounded_channel_never_blocks_burst_producer() {
+        assert!(burst_send(ChannelKind::Unbounded).await);
+    }
+
+    #[tokio::test]
+    async fn bounded_channel_backpressures_burst_producer() {
+        assert!(!burst_send(ChannelKind::Bounded).await);
+    }
+
+    #[tokio::test]
+    async fn async_solver_matches_sync_solver() {
+        let mut edges = vec![(0, 1, 0.5), (1, 2, 1.0), (2, 0, 2.1), (2, 3, 0.9)];
+        let graph = GraphCSR::from_edges(4, &mut edges, 10);
+        let solver = SPFASolver;
+
+        let sync_cycle = solver
+            .find_profitable_cycle(&graph, 0, 5)
+            .unwrap()
+            .expect("Sync solver should find the cycle");
+
+        let async_cycle = solver
+            .find_profitable_cycle_async(Arc::new(graph), 0, 5)
+            .await
+            .unwrap()
+            .expect("Async solver should find the cycle");
+
+        assert_eq!(async_cycle.path, sync_cycle.path);
+        assert_eq!(async_cycle.log_rate_sum, sync_cycle.log_rate_sum);
+    }
 }
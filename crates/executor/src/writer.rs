@@ -1,75 +1,386 @@
-use tokio::sync::mpsc::Receiver;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::time::{Duration, Instant};
 
+use super::config::{UpdateKind, WriterConfig};
 use super::error::Error;
-use super::types::SharedGraph;
+use super::filter::{validate_batches, validate_edges};
+use super::metrics::{Metrics, RejectionStats};
+use super::types::{DirtyNodes, SharedGraph, UpdateReceiver};
+use arb_solver_core::GraphCSR;
 use arb_solver_core::csr::AddEdgeResult;
+use common::numeric_kernel::{eps_gate_closed, log_mul};
 use common::types::Edge;
 
 /// Async consumer that applies edge updates to the shared graph.
 pub struct Writer {
     graph: SharedGraph,
-    receiver: Receiver<Vec<Edge>>,
+    receiver: UpdateReceiver,
     batch_buffer: Vec<Edge>,
+    /// Length of each received batch in `batch_buffer`, in order.
+    batch_lens: Vec<usize>,
     batch_capacity: usize,
+    max_nodes: usize,
+    max_in_flight: usize,
+    rate_epsilon: Option<f64>,
+    /// Drop whole batches containing an invalid update.
+    strict: bool,
+    update_kind: UpdateKind,
+    /// Latest rate per `(from, to)` edge that deltas apply to; only tracked for
+    /// `UpdateKind::Delta`.
+    quoted_rates: HashMap<(usize, usize), f64>,
+    /// Last committed rate per `(from, to)` edge; only tracked when `rate_epsilon` is set.
+    last_rates: HashMap<(usize, usize), f64>,
+    /// Latest rate admitted per edge since the last rebuild, not yet committed; only
+    /// tracked when `rate_epsilon` is set.
+    pending_rates: HashMap<(usize, usize), f64>,
+    /// How long a triggered rebuild waits for more batches to fold in.
+    rebuild_debounce: Duration,
+    /// Largest node id seen in accepted updates, used to grow the graph eagerly.
+    max_node_id: Option<usize>,
+    metrics: Arc<Metrics>,
+    dirty: Arc<DirtyNodes>,
 }
 
 impl Writer {
-    pub fn new(graph: SharedGraph, receiver: Receiver<Vec<Edge>>, batch_capacity: usize) -> Self {
+    pub fn new(
+        graph: SharedGraph,
+        receiver: UpdateReceiver,
+        config: WriterConfig,
+        metrics: Arc<Metrics>,
+    ) -> Self {
         Self {
             graph,
             receiver,
-            batch_capacity,
-            batch_buffer: Vec::with_capacity(batch_capacity),
+            batch_capacity: config.batch_capacity,
+            batch_buffer: Vec::with_capacity(config.batch_capacity),
+            batch_lens: Vec::new(),
+            max_nodes: config.max_nodes,
+            max_in_flight: config.max_in_flight,
+            rate_epsilon: config.rate_epsilon,
+            strict: config.strict,
+            update_kind: config.update_kind,
+            quoted_rates: HashMap::new(),
+            last_rates: HashMap::new(),
+            pending_rates: HashMap::new(),
+            rebuild_debounce: Duration::from_millis(config.rebuild_debounce_ms),
+            max_node_id: None,
+            metrics,
+            dirty: Arc::new(DirtyNodes::new()),
         }
     }
 
+    /// Marks the endpoints of every applied update in `dirty` (read by incremental scans).
+    pub fn with_dirty_nodes(mut self, dirty: Arc<DirtyNodes>) -> Self {
+        self.dirty = dirty;
+        self
+    }
+
+    /// Drops updates within `rate_epsilon` of the edge's latest admitted rate (or, if
+    /// none is pending, its last committed one), so feed jitter neither touches weights
+    /// nor counts toward a rebuild.
+    fn skip_jitter(
+        rate_epsilon: Option<f64>,
+        last_rates: &HashMap<(usize, usize), f64>,
+        pending_rates: &mut HashMap<(usize, usize), f64>,
+        edges: &mut Vec<Edge>,
+    ) -> usize {
+        let Some(eps) = rate_epsilon else {
+            return 0;
+        };
+
+        let before = edges.len();
+        edges.retain(|&(u, v, rate)| {
+            match pending_rates
+                .get(&(u, v))
+                .or_else(|| last_rates.get(&(u, v)))
+            {
+                Some(&last) if eps_gate_closed(last, rate, eps) => false,
+                _ => {
+                    pending_rates.insert((u, v), rate);
+                    true
+                }
+            }
+        });
+        before - edges.len()
+    }
+
+    /// Turns bps deltas into absolute rates, in order, starting from each edge's latest
+    /// rate (or its committed rate in `graph`). Deltas compound within `updates`; an
+    /// edge without a known rate gets NaN.
+    fn resolve_deltas(&self, graph: &GraphCSR, mut updates: Vec<Edge>) -> Vec<Edge> {
+        let mut latest: HashMap<(usize, usize), f64> = HashMap::new();
+        for (u, v, rate) in updates.iter_mut() {
+            let base = latest
+                .get(&(*u, *v))
+                .or_else(|| self.quoted_rates.get(&(*u, *v)))
+                .copied()
+                .or_else(|| graph.gross_rate(*u, *v))
+                .unwrap_or(f64::NAN);
+            *rate = log_mul(base, 1.0 + *rate / 10_000.0);
+            latest.insert((*u, *v), *rate);
+        }
+        updates
+    }
+
+    /// Drops invalid updates (see [`validate_edges`]; with `strict` set, their whole
+    /// batches, see [`validate_batches`]) and, with `rate_epsilon` set, sub-epsilon
+    /// jitter, then grows `graph` to cover newly seen node ids (cheap, no rebuild).
+    /// Returns the updates to apply and the rejection counts.
+    ///
+    /// With `UpdateKind::Delta`, updates are first resolved to absolute rates (see
+    /// [`Writer::resolve_deltas`]); valid ones become the base for later deltas even if
+    /// skipped as jitter. `batch_lens` gives the received batches making up `updates`.
+    fn admit(
+        &mut self,
+        graph: &mut GraphCSR,
+        updates: Vec<Edge>,
+        batch_lens: &[usize],
+    ) -> (Vec<Edge>, RejectionStats) {
+        let updates = match self.update_kind {
+            UpdateKind::Absolute => updates,
+            UpdateKind::Delta => self.resolve_deltas(graph, updates),
+        };
+
+        let (mut valid, rejected) = if self.strict {
+            let (valid, rejected, dropped) =
+                validate_batches(updates, batch_lens, graph, self.max_nodes);
+            if dropped > 0 {
+                eprintln!(
+                    "Writer Error: Rejected {} batch(es) with invalid updates; none of their edges were applied.",
+                    dropped
+                );
+            }
+            (valid, rejected)
+        } else {
+            validate_edges(updates, graph, self.max_nodes)
+        };
+        if self.update_kind == UpdateKind::Delta {
+            self.quoted_rates
+                .extend(valid.iter().map(|&(u, v, rate)| ((u, v), rate)));
+        }
+        let skipped = Self::skip_jitter(
+            self.rate_epsilon,
+            &self.last_rates,
+            &mut self.pending_rates,
+            &mut valid,
+        );
+        if skipped > 0 {
+            println!("Writer: Skipped {} sub-epsilon updates", skipped);
+        }
+
+        self.max_node_id = valid
+            .iter()
+            .map(|&(u, v, _)| u.max(v))
+            .chain(self.max_node_id)
+            .max();
+        // Validation keeps `max_id + 1` within `max_nodes`, so this never overflows.
+        if let Some(num_nodes) = self.max_node_id.and_then(|max_id| max_id.checked_add(1)) {
+            graph.grow_nodes(num_nodes);
+        }
+        (valid, rejected)
+    }
+
+    fn report_rejections(&self, rejected: &RejectionStats) {
+        if rejected.total() == 0 {
+            return;
+        }
+
+        let totals = self.metrics.record_rejections(rejected);
+        eprintln!(
+            "Writer: Dropped {} invalid edges (out-of-range: {}, self-loops: {}, non-finite: {}). Totals: {:?}",
+            rejected.total(),
+            rejected.out_of_range,
+            rejected.self_loops,
+            rejected.non_finite,
+            totals
+        );
+    }
+
+    /// Collects the batches arriving within `rebuild_debounce` from now, with their lengths.
+    async fn absorb_burst(&mut self) -> (Vec<Edge>, Vec<usize>) {
+        let deadline = Instant::now() + self.rebuild_debounce;
+        let mut absorbed = Vec::new();
+        let mut batch_lens = Vec::new();
+        while let Ok(Some(updates)) = tokio::time::timeout_at(deadline, self.receiver.recv()).await
+        {
+            self.metrics.stamp_updates(&updates);
+            batch_lens.push(updates.len());
+            absorbed.extend(updates);
+        }
+        (absorbed, batch_lens)
+    }
+
     /// Flushes accumulated edge updates to the shared graph using a **Two-Phase Lock** strategy.
     ///
-    /// Phase 1 (short lock): Atomically transfers pending updates out of the graph if a rebuild is needed.
+    /// Phase 1 (short lock): Admits the batch (see [`Writer::admit`]), then atomically
+    ///                       transfers pending updates out of the graph if a rebuild is needed.
+    /// Debounce (unlocked): With `rebuild_debounce_ms` set, batches arriving within the
+    ///                      window are admitted too and folded into the same rebuild.
     /// Unlocked Work: We **sort the edges** here (outside the lock) to perform the high-cost computation
     ///                without blocking readers.
     /// Phase 2 (short lock): Acquires lock briefly to commit the final, rebuilt graph state.
+    ///
+    /// Updates only become visible, and count as processed, once a rebuild commits them.
+    /// Every committed update (including those buffered by earlier flushes) then marks
+    /// its nodes dirty, so a scan that drains the dirty set before snapshotting never
+    /// misses them.
     async fn flush(&mut self) -> Result<(), Error> {
         if self.batch_buffer.is_empty() {
             return Ok(());
         }
 
-        let rebuild_data = {
+        let (rebuild_data, rejected) = {
             println!("Flushing {} edges to graph", self.batch_buffer.len());
 
-            let mut graph = self.graph.write().await;
-            graph.add_edges_and_extract_data(std::mem::take(&mut self.batch_buffer))
+            // Own a handle to the lock so `admit` can borrow the writer mutably.
+            let shared = self.graph.clone();
+            let mut graph = shared.write().await;
+            let updates = std::mem::take(&mut self.batch_buffer);
+            let batch_lens = std::mem::take(&mut self.batch_lens);
+            let (valid, rejected) = self.admit(&mut graph, updates, &batch_lens);
+            if valid.is_empty() {
+                (AddEdgeResult::Success, rejected)
+            } else {
+                (graph.add_edges_and_extract_data(valid), rejected)
+            }
         };
+        self.report_rejections(&rejected);
 
         if let AddEdgeResult::RebuildNeeded(mut edges) = rebuild_data {
-            // We sort the edges for optimal efficiency before re-acquiring the lock
-            edges.sort_by_key(|(src, _, _)| *src);
-            println!("Initiating graph rebuild...");
+            if !self.rebuild_debounce.is_zero() {
+                let (absorbed, batch_lens) = self.absorb_burst().await;
+                if !absorbed.is_empty() {
+                    println!(
+                        "Writer: Folding {} more edges into the rebuild",
+                        absorbed.len()
+                    );
+                    let (valid, rejected) = {
+                        let shared = self.graph.clone();
+                        let mut graph = shared.write().await;
+                        self.admit(&mut graph, absorbed, &batch_lens)
+                    };
+                    self.report_rejections(&rejected);
+                    edges.extend(valid);
+                }
+            }
 
-            {
-                let mut graph = self.graph.write().await;
-                graph.rebuild_with_edges(edges);
+            self.commit(edges).await;
+        }
+
+        Ok(())
+    }
+
+    /// Phase 2 of [`Writer::flush`]: rebuilds the graph with `edges`, the extracted
+    /// pending updates, and records what was committed.
+    async fn commit(&mut self, mut edges: Vec<Edge>) {
+        // We sort the edges for optimal efficiency before re-acquiring the lock
+        edges.sort_by_key(|(src, _, _)| *src);
+        println!("Initiating graph rebuild...");
+
+        let (committed, stale, result) = {
+            let mut graph = self.graph.write().await;
+            // Updates buffered by earlier flushes were validated against the graph
+            // as it was then (e.g. before a fee change); drop those no longer valid
+            // so they cannot fail the rebuild for the rest.
+            let (edges, stale) = validate_edges(edges, &graph, self.max_nodes);
+            let committed = edges.clone();
+            (committed, stale, graph.rebuild_with_edges(edges))
+        };
+        self.report_rejections(&stale);
+
+        // Every pending update was either committed or dropped.
+        self.pending_rates.clear();
+
+        // A rejected batch leaves the graph untouched; keep consuming the feed.
+        match result {
+            Ok(()) => {
+                if self.rate_epsilon.is_some() {
+                    self.last_rates
+                        .extend(committed.iter().map(|&(u, v, rate)| ((u, v), rate)));
+                }
+                self.metrics.record_rebuild();
+                self.metrics.record_edges_processed(committed.len() as u64);
+                self.dirty.mark(&committed);
+                println!("Graph rebuild complete.");
+            }
+            Err(e) => {
+                let total = self.metrics.record_dropped_updates(committed.len() as u64);
+                eprintln!(
+                    "Writer Error: Graph rebuild rejected: {}. Dropped {} updates ({} total). Continuing.",
+                    e,
+                    committed.len(),
+                    total
+                );
             }
-            println!("Graph rebuild complete.");
         }
+    }
 
+    /// Flushes the batch buffer, then commits every update still pending in the graph,
+    /// whatever the rebuild policy, so nothing received is lost at shutdown.
+    async fn finish(&mut self) -> Result<(), Error> {
+        self.flush().await?;
+        let pending = self.graph.write().await.take_pending();
+        if !pending.is_empty() {
+            println!(
+                "Writer: Committing {} pending edges before shutdown",
+                pending.len()
+            );
+            self.commit(pending).await;
+        }
         Ok(())
     }
 
+    /// Moves the receiver into a forwarding task feeding a `max_in_flight`-batch queue,
+    /// so producers keep making progress while a flush holds the writer.
+    fn start_prefetch(&mut self) {
+        let (sender, receiver) = mpsc::channel(self.max_in_flight);
+        let mut upstream = std::mem::replace(&mut self.receiver, receiver.into());
+
+        tokio::spawn(async move {
+            while let Some(updates) = upstream.recv().await {
+                if sender.send(updates).await.is_err() {
+                    break; // Writer stopped.
+                }
+            }
+        });
+    }
+
     /// Run the writer asynchronously.
     ///
     /// Consumes batches from the receiver and applies them to the graph.
     /// Releases the write lock immediately after each batch.
-    /// Exits when the receiver is closed or shutdown signal is received.
+    /// Exits when the receiver is closed or shutdown signal is received, after committing
+    /// everything still buffered (see [`Writer::finish`]).
+    ///
+    /// With `max_in_flight > 0`, up to that many batches are prefetched during a
+    /// flush and folded into the next one, so a burst costs one rebuild instead of
+    /// one per batch.
     pub async fn process_updates(mut self) -> Result<(), Error> {
         println!("Writer ready.");
 
+        if self.max_in_flight > 0 {
+            self.start_prefetch();
+        }
+
         loop {
             let message_option = self.receiver.recv().await;
 
             match message_option {
                 Some(updates) => {
+                    // Stamp on receipt so detection latency includes batching and rebuild delays.
+                    self.metrics.stamp_updates(&updates);
+                    self.batch_lens.push(updates.len());
                     self.batch_buffer.extend(updates);
+                    for _ in 0..self.max_in_flight {
+                        let Some(updates) = self.receiver.try_recv() else {
+                            break;
+                        };
+                        self.metrics.stamp_updates(&updates);
+                        self.batch_lens.push(updates.len());
+                        self.batch_buffer.extend(updates);
+                    }
                     if self.batch_buffer.len() >= self.batch_capacity {
                         self.flush().await?;
                     }
@@ -77,6 +388,7 @@ impl Writer {
 
                 None => {
                     println!("Receiver closed, shutting down writer.");
+                    self.finish().await?;
                     break;
                 }
             }
@@ -93,3 +405,515 @@ impl Writer {
         tokio::spawn(self.process_updates())
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::config::{ChannelKind, UpdateKind};
+    use crate::metrics::RejectionStats;
+    use crate::types::update_channel;
+    use arb_solver_core::GraphCSR;
+    use tokio::sync::RwLock;
+
+    /// Flushes and rebuilds per batch with every optional behaviour off; tests override
+    /// only the fields they exercise.
+    pub(crate) fn writer_config() -> WriterConfig {
+        WriterConfig {
+            batch_capacity: 1,
+            max_nodes: usize::MAX,
+            max_in_flight: 0,
+            rate_epsilon: None,
+            rebuild_debounce_ms: 0,
+            strict: false,
+            update_kind: UpdateKind::Absolute,
+        }
+    }
+
+    #[tokio::test]
+    async fn invalid_edges_are_dropped_and_counted_by_reason() {
+        let graph: SharedGraph = Arc::new(RwLock::new(GraphCSR::from_edges(0, &mut [], 1)));
+        let metrics = Arc::new(Metrics::new());
+        let config = WriterConfig {
+            max_nodes: 10,
+            ..writer_config()
+        };
+
+        let (sender, receiver) = update_channel(ChannelKind::Bounded, 4);
+        let writer = Writer::new(graph.clone(), receiver, config, metrics.clone());
+        let handle = tokio::spawn(writer.process_updates());
+
+        sender
+            .send(vec![
+                (0, 1, 1.1),
+                (1, 2, 0.9),
+                (3, 3, 1.0),
+                (4, 4, 2.0),
+                (2, 10, 1.0),
+                (usize::MAX, 0, 1.0),
+                (5, 6, f64::NAN),
+                (6, 5, 0.0),
+                (7, 8, -1.0),
+            ])
+            .await
+            .unwrap();
+        drop(sender);
+        handle.await.unwrap().unwrap();
+
+        assert_eq!(
+            metrics.rejections(),
+            RejectionStats {
+                out_of_range: 2,
+                self_loops: 2,
+                non_finite: 3,
+            }
+        );
+        assert_eq!(graph.read().await.edge_targets.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn new_high_node_id_grows_graph_before_rebuild() {
+        let graph: SharedGraph = Arc::new(RwLock::new(GraphCSR::from_edges(0, &mut [], 2)));
+        let metrics = Arc::new(Metrics::new());
+        let config = writer_config();
+
+        let (sender, receiver) = update_channel(ChannelKind::Bounded, 4);
+        let writer = Writer::new(graph.clone(), receiver, config, metrics);
+        let handle = tokio::spawn(writer.process_updates());
+
+        // Two updates reach the rebuild limit and commit the first edges.
+        sender.send(vec![(0, 1, 1.0)]).await.unwrap();
+        sender.send(vec![(1, 0, 1.1)]).await.unwrap();
+        // A brand-new id is only pending, but the graph already has room for it.
+        sender.send(vec![(1, 50, 1.0)]).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while graph.read().await.num_nodes < 51 {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .expect("Graph should grow before the rebuild");
+        assert_eq!(graph.read().await.edge_targets.len(), 2);
+
+        sender.send(vec![(50, 0, 1.0)]).await.unwrap();
+        drop(sender);
+        handle.await.unwrap().unwrap();
+
+        let graph = graph.read().await;
+        assert_eq!(graph.num_nodes, 51);
+        let mut edges: Vec<_> = graph.to_edges().iter().map(|&(u, v, _)| (u, v)).collect();
+        edges.sort();
+        assert_eq!(edges, vec![(0, 1), (1, 0), (1, 50), (50, 0)]);
+    }
+
+    #[tokio::test]
+    async fn huge_node_ids_are_rejected_without_growing_the_graph() {
+        let graph: SharedGraph = Arc::new(RwLock::new(GraphCSR::from_edges(0, &mut [], 1)));
+        let metrics = Arc::new(Metrics::new());
+        let config = writer_config();
+
+        let (sender, receiver) = update_channel(ChannelKind::Bounded, 4);
+        let writer = Writer::new(graph.clone(), receiver, config, metrics.clone());
+        let handle = tokio::spawn(writer.process_updates());
+
+        // `usize::MAX + 1` nodes cannot be represented, whatever `max_nodes` allows.
+        sender.send(vec![(0, usize::MAX, 1.0)]).await.unwrap();
+        sender.send(vec![(0, 1, 1.0)]).await.unwrap();
+        drop(sender);
+        handle.await.unwrap().unwrap();
+
+        assert_eq!(graph.read().await.num_nodes, 2);
+        assert_eq!(metrics.rejections().out_of_range, 1);
+    }
+
+    #[tokio::test]
+    async fn applied_updates_mark_their_nodes_dirty() {
+        let graph: SharedGraph = Arc::new(RwLock::new(GraphCSR::from_edges(0, &mut [], 1)));
+        let dirty = Arc::new(DirtyNodes::new());
+        let config = writer_config();
+
+        let (sender, receiver) = update_channel(ChannelKind::Bounded, 4);
+        let writer = Writer::new(graph, receiver, config, Arc::new(Metrics::new()))
+            .with_dirty_nodes(dirty.clone());
+        let handle = tokio::spawn(writer.process_updates());
+
+        sender.send(vec![(0, 1, 1.0)]).await.unwrap();
+        // Rejected self-loop: never reaches the graph, so never dirty.
+        sender.send(vec![(7, 7, 1.0)]).await.unwrap();
+        drop(sender);
+        handle.await.unwrap().unwrap();
+
+        let mut touched: Vec<_> = dirty.take().into_iter().collect();
+        touched.sort();
+        assert_eq!(touched, vec![0, 1]);
+        assert!(dirty.take().is_empty());
+    }
+
+    #[tokio::test]
+    async fn buffered_updates_are_marked_dirty_when_a_later_rebuild_commits_them() {
+        let graph: SharedGraph = Arc::new(RwLock::new(GraphCSR::from_edges(0, &mut [], 2)));
+        let dirty = Arc::new(DirtyNodes::new());
+        let metrics = Arc::new(Metrics::new());
+        let config = writer_config();
+
+        let (sender, receiver) = update_channel(ChannelKind::Bounded, 4);
+        let writer = Writer::new(graph.clone(), receiver, config, metrics.clone())
+            .with_dirty_nodes(dirty.clone());
+        let handle = tokio::spawn(writer.process_updates());
+
+        // Below the rebuild limit: buffered, not yet visible to a scan.
+        sender.send(vec![(0, 1, 1.0)]).await.unwrap();
+        while graph.read().await.pending_len() < 1 {
+            tokio::task::yield_now().await;
+        }
+        assert!(dirty.take().is_empty());
+        assert_eq!(metrics.run_report().edges_processed, 0);
+
+        sender.send(vec![(2, 3, 1.0)]).await.unwrap();
+        drop(sender);
+        handle.await.unwrap().unwrap();
+
+        let mut touched: Vec<_> = dirty.take().into_iter().collect();
+        touched.sort();
+        assert_eq!(touched, vec![0, 1, 2, 3]);
+        assert_eq!(metrics.run_report().edges_processed, 2);
+    }
+
+    #[tokio::test]
+    async fn buffered_update_invalidated_by_a_fee_change_does_not_fail_the_rebuild() {
+        let graph: SharedGraph = Arc::new(RwLock::new(GraphCSR::from_edges(0, &mut [], 2)));
+        let metrics = Arc::new(Metrics::new());
+        let config = writer_config();
+
+        let (sender, receiver) = update_channel(ChannelKind::Bounded, 4);
+        let writer = Writer::new(graph.clone(), receiver, config, metrics.clone());
+        let handle = tokio::spawn(writer.process_updates());
+
+        // The smallest subnormal rate is valid without a fee...
+        sender.send(vec![(0, 1, 5e-324)]).await.unwrap();
+        while graph.read().await.pending_len() < 1 {
+            tokio::task::yield_now().await;
+        }
+        // ...but rounds to zero once a 50% fee applies, while it sits in the buffer.
+        graph.write().await.set_edge_fee_bps(0, 1, 5_000.0).unwrap();
+
+        sender.send(vec![(1, 2, 1.0)]).await.unwrap();
+        drop(sender);
+        handle.await.unwrap().unwrap();
+
+        let graph = graph.read().await;
+        let edges: Vec<_> = graph.to_edges().iter().map(|&(u, v, _)| (u, v)).collect();
+        assert_eq!(edges, vec![(1, 2)]);
+        assert_eq!(metrics.rejections().non_finite, 1);
+        let report = metrics.run_report();
+        assert_eq!(report.rebuilds, 1);
+        assert_eq!(report.edges_processed, 1);
+        assert_eq!(metrics.dropped_updates(), 0);
+    }
+
+    #[tokio::test]
+    async fn closing_the_feed_commits_updates_below_the_rebuild_limit() {
+        let graph: SharedGraph = Arc::new(RwLock::new(GraphCSR::from_edges(0, &mut [], 10)));
+        let metrics = Arc::new(Metrics::new());
+        let config = WriterConfig {
+            batch_capacity: 2,
+            ..writer_config()
+        };
+
+        let (sender, receiver) = update_channel(ChannelKind::Bounded, 4);
+        let writer = Writer::new(graph.clone(), receiver, config, metrics.clone());
+        let handle = tokio::spawn(writer.process_updates());
+
+        // Flushed but below the rebuild limit of 10...
+        sender.send(vec![(0, 1, 1.0), (1, 2, 1.0)]).await.unwrap();
+        // ...and below the batch capacity of 2, so not even flushed.
+        sender.send(vec![(2, 0, 1.1)]).await.unwrap();
+        drop(sender);
+        handle.await.unwrap().unwrap();
+
+        let graph = graph.read().await;
+        assert_eq!(graph.num_edges(), 3);
+        assert!(graph.pending_updates().is_empty());
+        assert_eq!(metrics.run_report().edges_processed, 3);
+    }
+
+    #[tokio::test]
+    async fn debounced_rebuild_absorbs_a_second_rapid_batch() {
+        let graph: SharedGraph = Arc::new(RwLock::new(GraphCSR::from_edges(0, &mut [], 1)));
+        let metrics = Arc::new(Metrics::new());
+        let config = WriterConfig {
+            rebuild_debounce_ms: 200,
+            ..writer_config()
+        };
+
+        let (sender, receiver) = update_channel(ChannelKind::Bounded, 4);
+        let writer = Writer::new(graph.clone(), receiver, config, metrics.clone());
+        let handle = tokio::spawn(writer.process_updates());
+
+        // Each batch adds a new edge and alone reaches the rebuild limit of 1.
+        sender.send(vec![(0, 1, 1.0)]).await.unwrap();
+        sender.send(vec![(1, 2, 1.0)]).await.unwrap();
+        while metrics.run_report().rebuilds < 1 {
+            tokio::task::yield_now().await;
+        }
+        drop(sender);
+        handle.await.unwrap().unwrap();
+
+        let graph = graph.read().await;
+        let mut edges: Vec<_> = graph.to_edges().iter().map(|&(u, v, _)| (u, v)).collect();
+        edges.sort();
+        assert_eq!(edges, vec![(0, 1), (1, 2)]);
+        assert!(graph.pending_updates().is_empty());
+        let report = metrics.run_report();
+        assert_eq!(report.rebuilds, 1);
+        assert_eq!(report.edges_processed, 2);
+    }
+
+    #[tokio::test]
+    async fn strict_mode_rejects_a_batch_with_one_bad_edge_entirely() {
+        let graph: SharedGraph = Arc::new(RwLock::new(GraphCSR::from_edges(0, &mut [], 1)));
+        let metrics = Arc::new(Metrics::new());
+        let config = WriterConfig {
+            strict: true,
+            ..writer_config()
+        };
+
+        let (sender, receiver) = update_channel(ChannelKind::Bounded, 4);
+        let writer = Writer::new(graph.clone(), receiver, config, metrics.clone());
+        let handle = tokio::spawn(writer.process_updates());
+
+        sender
+            .send(vec![(0, 1, 1.0), (1, 2, f64::NAN), (2, 0, 1.1)])
+            .await
+            .unwrap();
+        // A fully valid batch is still applied.
+        sender.send(vec![(3, 4, 1.0)]).await.unwrap();
+        drop(sender);
+        handle.await.unwrap().unwrap();
+
+        let graph = graph.read().await;
+        let edges: Vec<_> = graph.to_edges().iter().map(|&(u, v, _)| (u, v)).collect();
+        assert_eq!(edges, vec![(3, 4)]);
+        assert!(graph.pending_updates().is_empty());
+        assert_eq!(metrics.run_report().edges_processed, 1);
+        assert_eq!(
+            metrics.rejections(),
+            RejectionStats {
+                non_finite: 1,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn delta_updates_compound_on_the_edge_rate() {
+        let graph: SharedGraph = Arc::new(RwLock::new(GraphCSR::from_edges(
+            2,
+            &mut [(0, 1, 1.2), (1, 0, 0.8)],
+            1,
+        )));
+        let metrics = Arc::new(Metrics::new());
+        let config = WriterConfig {
+            update_kind: UpdateKind::Delta,
+            ..writer_config()
+        };
+
+        let (sender, receiver) = update_channel(ChannelKind::Bounded, 8);
+        let writer = Writer::new(graph.clone(), receiver, config, metrics.clone());
+        let handle = tokio::spawn(writer.process_updates());
+
+        let deltas_bps = [10.0, -5.0, 2.5, 0.5];
+        for delta in deltas_bps {
+            sender.send(vec![(0, 1, delta)]).await.unwrap();
+        }
+        // Two deltas in one batch compound too.
+        sender
+            .send(vec![(1, 0, 100.0), (1, 0, 100.0)])
+            .await
+            .unwrap();
+        // No known rate to apply a delta to.
+        sender.send(vec![(0, 2, 1.0)]).await.unwrap();
+        drop(sender);
+        handle.await.unwrap().unwrap();
+
+        let expected = deltas_bps
+            .iter()
+            .fold(1.2, |rate, bps| rate * (1.0 + bps / 10_000.0));
+        let graph = graph.read().await;
+        assert!((graph.gross_rate(0, 1).unwrap() - expected).abs() < 1e-12);
+        assert!((graph.gross_rate(1, 0).unwrap() - 0.8 * 1.01 * 1.01).abs() < 1e-12);
+        assert_eq!(graph.gross_rate(0, 2), None);
+        assert_eq!(metrics.rejections().non_finite, 1);
+    }
+
+    #[tokio::test]
+    async fn sub_epsilon_jitter_changes_no_weights_and_triggers_no_rebuild() {
+        let graph: SharedGraph = Arc::new(RwLock::new(GraphCSR::from_edges(2, &mut [], 1)));
+        let metrics = Arc::new(Metrics::new());
+        let config = WriterConfig {
+            rate_epsilon: Some(1e-6),
+            ..writer_config()
+        };
+
+        let (sender, receiver) = update_channel(ChannelKind::Bounded, 4);
+        let writer = Writer::new(graph.clone(), receiver, config, metrics.clone());
+        let handle = tokio::spawn(writer.process_updates());
+
+        sender.send(vec![(0, 1, 1.1), (1, 0, 0.95)]).await.unwrap();
+        while metrics.run_report().rebuilds < 1 {
+            tokio::task::yield_now().await;
+        }
+        let weights = graph.read().await.edge_weights.clone();
+
+        for i in 0..1000 {
+            let jitter = if i % 2 == 0 { 1e-8 } else { -1e-8 };
+            sender
+                .send(vec![(0, 1, 1.1 + jitter), (1, 0, 0.95 - jitter)])
+                .await
+                .unwrap();
+        }
+        drop(sender);
+        handle.await.unwrap().unwrap();
+
+        let graph = graph.read().await;
+        assert_eq!(graph.edge_weights, weights);
+        assert!(graph.pending_updates().is_empty());
+        let report = metrics.run_report();
+        assert_eq!(report.rebuilds, 1);
+        assert_eq!(report.edges_processed, 2);
+    }
+
+    #[tokio::test]
+    async fn jitter_is_measured_against_committed_rates_only() {
+        let graph: SharedGraph = Arc::new(RwLock::new(GraphCSR::from_edges(0, &mut [], 2)));
+        let metrics = Arc::new(Metrics::new());
+        let config = WriterConfig {
+            rate_epsilon: Some(1e-9),
+            ..writer_config()
+        };
+
+        let (sender, receiver) = update_channel(ChannelKind::Bounded, 4);
+        let writer = Writer::new(graph.clone(), receiver, config, metrics.clone());
+        let handle = tokio::spawn(writer.process_updates());
+
+        // Admitted, then invalidated by a 50% fee before its rebuild: never committed.
+        sender.send(vec![(0, 1, 5e-324)]).await.unwrap();
+        while graph.read().await.pending_len() < 1 {
+            tokio::task::yield_now().await;
+        }
+        graph.write().await.set_edge_fee_bps(0, 1, 5_000.0).unwrap();
+        sender.send(vec![(1, 2, 1.0)]).await.unwrap();
+        while metrics.run_report().rebuilds < 1 {
+            tokio::task::yield_now().await;
+        }
+        graph.write().await.set_edge_fee_bps(0, 1, 0.0).unwrap();
+
+        // The same quote again is not jitter: the edge never held that rate.
+        sender
+            .send(vec![(0, 1, 5e-324), (2, 0, 1.0)])
+            .await
+            .unwrap();
+        drop(sender);
+        handle.await.unwrap().unwrap();
+
+        let graph = graph.read().await;
+        let mut edges: Vec<_> = graph.to_edges().iter().map(|&(u, v, _)| (u, v)).collect();
+        edges.sort();
+        assert_eq!(edges, vec![(0, 1), (1, 2), (2, 0)]);
+    }
+
+    /// Applies a burst of `BURST` single-edge batches to a graph large enough that
+    /// every rebuild is costly. Returns the elapsed time and the number of rebuilds.
+    async fn run_burst(max_in_flight: usize) -> (std::time::Duration, usize) {
+        const BURST: usize = 40;
+
+        let mut graph = GraphCSR::random(2_000, 50_000, 0.9..1.1, 1);
+        graph.rebuild_limit = 1;
+        let rebuilds = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = rebuilds.clone();
+        graph.set_on_rebuild(move |_| {
+            counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        });
+        let graph: SharedGraph = Arc::new(RwLock::new(graph));
+
+        let config = WriterConfig {
+            max_in_flight,
+            ..writer_config()
+        };
+        let (sender, receiver) = update_channel(ChannelKind::Bounded, 1);
+        let writer = Writer::new(graph, receiver, config, Arc::new(Metrics::new()));
+
+        let start = std::time::Instant::now();
+        let handle = tokio::spawn(writer.process_updates());
+        for i in 0..BURST {
+            sender.send(vec![(i, i + 1, 1.01)]).await.unwrap();
+        }
+        drop(sender);
+        handle.await.unwrap().unwrap();
+
+        (
+            start.elapsed(),
+            rebuilds.load(std::sync::atomic::Ordering::SeqCst),
+        )
+    }
+
+    #[tokio::test]
+    async fn batches_prefetched_during_a_flush_are_coalesced_into_one_rebuild() {
+        let mut graph = GraphCSR::from_edges(0, &mut [], 1);
+        let rebuilds = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = rebuilds.clone();
+        graph.set_on_rebuild(move |_| {
+            counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        });
+        let graph: SharedGraph = Arc::new(RwLock::new(graph));
+
+        let config = WriterConfig {
+            max_in_flight: 8,
+            ..writer_config()
+        };
+        let (sender, receiver) = mpsc::channel(1);
+        let writer = Writer::new(
+            graph.clone(),
+            receiver.into(),
+            config,
+            Arc::new(Metrics::new()),
+        );
+
+        // Holding the read lock stalls the first flush until every batch is prefetched.
+        let reader = graph.read().await;
+        let handle = tokio::spawn(writer.process_updates());
+        for i in 0..9 {
+            sender.send(vec![(i, i + 1, 1.01)]).await.unwrap();
+        }
+        while sender.capacity() < sender.max_capacity() {
+            tokio::task::yield_now().await;
+        }
+        tokio::task::yield_now().await;
+        drop(reader);
+        drop(sender);
+        handle.await.unwrap().unwrap();
+
+        // One rebuild for the first batch, one for the eight queued behind it.
+        assert_eq!(rebuilds.load(std::sync::atomic::Ordering::SeqCst), 2);
+        assert_eq!(graph.read().await.num_edges(), 9);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    #[ignore = "asserts wall-clock throughput; run with --ignored on an idle machine"]
+    async fn prefetching_sustains_higher_throughput_under_a_burst() {
+        let (baseline_time, baseline_rebuilds) = run_burst(0).await;
+        let (prefetch_time, prefetch_rebuilds) = run_burst(8).await;
+
+        assert_eq!(baseline_rebuilds, 40);
+        assert!(
+            prefetch_rebuilds < baseline_rebuilds / 2,
+            "prefetch rebuilt {} times",
+            prefetch_rebuilds
+        );
+        assert!(
+            prefetch_time < baseline_time,
+            "prefetch {:?} vs baseline {:?}",
+            prefetch_time,
+            baseline_time
+        );
+    }
+}
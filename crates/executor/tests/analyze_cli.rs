@@ -0,0 +1,26 @@
+use std::process::Command;
+
+#[test]
+fn analyze_prints_ranked_cycles_for_mock_csv() {
+    let csv = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/mock_rates.csv");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_executor"))
+        .args(["analyze", csv])
+        .output()
+        .expect("Failed to run executor");
+    assert!(
+        output.status.success(),
+        "analyze exited with {}",
+        output.status
+    );
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let ranked: Vec<&str> = stdout.lines().filter(|l| l.starts_with('#')).collect();
+
+    assert!(stdout.contains("Found 2 profitable cycle(s)"), "{stdout}");
+    assert_eq!(ranked.len(), 2, "{stdout}");
+    assert!(ranked[0].starts_with("#1 profit: 10.0000%"), "{stdout}");
+    assert!(ranked[0].ends_with("path: 0 -> 1 -> 2 -> 0"), "{stdout}");
+    assert!(ranked[1].starts_with("#2 profit: 2.0000%"), "{stdout}");
+    assert!(ranked[1].ends_with("path: 3 -> 4 -> 3"), "{stdout}");
+}
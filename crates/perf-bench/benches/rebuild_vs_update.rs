@@ -0,0 +1,42 @@
+use std::hint::black_box;
+
+use arb_solver_core::GraphCSR;
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use perf_bench::*;
+
+/// 1,000 rate changes on existing pairs of a 100,000-edge graph, applied once as a
+/// single batched rebuild and once in place per change.
+fn rebuild_vs_update(c: &mut Criterion) {
+    let graph = GraphCSR::random(REBUILD_BENCH_NODES, NUM_EDGES, 0.9..1.1, 7);
+    let changes = generate_rate_changes(&graph, NUM_RATE_CHANGES);
+
+    let mut group = c.benchmark_group("rate_changes");
+    group.bench_function("full_rebuild", |b| {
+        b.iter_batched(
+            || graph.clone(),
+            |mut rebuilt| {
+                rebuilt
+                    .rebuild_with_edges(changes.clone())
+                    .expect("Rate changes are valid");
+                black_box(rebuilt)
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("try_update_weight", |b| {
+        b.iter_batched(
+            || graph.clone(),
+            |mut updated| {
+                for &(u, v, rate) in &changes {
+                    assert!(updated.try_update_weight(u, v, rate));
+                }
+                black_box(updated)
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, rebuild_vs_update);
+criterion_main!(benches);
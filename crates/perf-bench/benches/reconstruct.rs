@@ -0,0 +1,46 @@
+use std::hint::black_box;
+
+use arb_solver_core::solver::SPFASolver;
+use criterion::{Criterion, criterion_group, criterion_main};
+use perf_bench::*;
+
+/// Reconstructs a loop from SPFA-style predecessor edges on a 100,000-node graph,
+/// with the two-pass baseline and the single-pass `reconstruct_cycle`.
+fn reconstruct(c: &mut Criterion) {
+    // (name, ring length, flagged node): a short loop flagged on the loop, the same
+    // loop flagged at the end of a long tail, and a loop through every node.
+    let tail_end = RECONSTRUCT_BENCH_NODES - 1;
+    let fixtures = [
+        ("short_loop", 16, 0),
+        ("short_loop_long_tail", 16, tail_end),
+        ("full_loop", RECONSTRUCT_BENCH_NODES, 0),
+    ];
+
+    for (name, ring_len, start) in fixtures {
+        let (graph, pred) = ring_with_tail(RECONSTRUCT_BENCH_NODES, ring_len);
+        assert_eq!(
+            SPFASolver
+                .reconstruct_cycle_two_pass(start, &pred, &graph)
+                .expect("Ring is intact")
+                .path,
+            SPFASolver
+                .reconstruct_cycle(start, &pred, &graph)
+                .expect("Ring is intact")
+                .path
+        );
+
+        let mut group = c.benchmark_group(format!("reconstruct/{}", name));
+        group.bench_function("two_pass", |b| {
+            b.iter(|| {
+                SPFASolver.reconstruct_cycle_two_pass(black_box(start), black_box(&pred), &graph)
+            })
+        });
+        group.bench_function("single_pass", |b| {
+            b.iter(|| SPFASolver.reconstruct_cycle(black_box(start), black_box(&pred), &graph))
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, reconstruct);
+criterion_main!(benches);
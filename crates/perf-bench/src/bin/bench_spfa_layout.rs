@@ -0,0 +1,36 @@
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use arb_solver_core::csr::EdgeLayout;
+use arb_solver_core::solver::SPFASolver;
+use perf_bench::*;
+
+fn main() {
+    let graph = generate_acyclic_graph(SPFA_BENCH_NODES, NUM_EDGES);
+    let hop_cap = graph.num_nodes + 1;
+
+    let time_layout = |layout: EdgeLayout| {
+        let mut total = Duration::ZERO;
+        let mut relaxations = 0;
+        for _ in 0..SPFA_BENCH_RUNS {
+            let start_time = Instant::now();
+            let (cycle, stats) = SPFASolver
+                .find_profitable_cycle_with_layout(&graph, 0, hop_cap, layout)
+                .expect("Benchmark graph is valid");
+            total += start_time.elapsed();
+            relaxations = black_box(stats).relaxations;
+            assert!(cycle.is_none());
+        }
+        (total / SPFA_BENCH_RUNS as u32, relaxations)
+    };
+
+    let (split_time, relaxations) = time_layout(EdgeLayout::Split);
+    let (packed_time, _) = time_layout(EdgeLayout::Packed);
+
+    println!(
+        "--- SPFA Edge Layout ({} Edges, {} Relaxations, mean of {} runs) ---",
+        NUM_EDGES, relaxations, SPFA_BENCH_RUNS
+    );
+    println!("Split (edge_targets + edge_weights): {:?}", split_time);
+    println!("Packed ((target, weight) pairs): {:?}", packed_time);
+}
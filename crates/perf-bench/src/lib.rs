@@ -2,6 +2,8 @@
 // Task 2: Benchmark Layouts
 // ----------------------------
 
+use arb_solver_core::GraphCSR;
+
 /// Array of Structs (AoS) - Individual edge data is contiguous.
 pub struct Edge {
     pub from: usize,
@@ -43,6 +45,49 @@ impl From<EdgeSOA> for EdgeAOS {
     }
 }
 
+/// Loads SoA benchmark data into the solver's CSR graph.
+///
+/// The node count is derived from the largest node id, and the rebuild limit is set
+/// to the number of edges so that a benchmark graph is never rebuilt implicitly.
+impl From<&EdgeSOA> for GraphCSR {
+    fn from(soa: &EdgeSOA) -> Self {
+        let mut edges: Vec<(usize, usize, f64)> = soa
+            .from
+            .iter()
+            .zip(&soa.to)
+            .zip(&soa.rate)
+            .map(|((&from, &to), &rate)| (from, to, rate))
+            .collect();
+
+        let num_nodes = edges
+            .iter()
+            .flat_map(|&(u, v, _)| [u, v])
+            .max()
+            .map_or(0, |max_id| max_id + 1);
+        let rebuild_limit = edges.len().max(1);
+
+        GraphCSR::from_edges(num_nodes, &mut edges, rebuild_limit)
+    }
+}
+
+/// Extracts a CSR graph's committed edges (in CSR order) back into SoA layout.
+impl From<&GraphCSR> for EdgeSOA {
+    fn from(graph: &GraphCSR) -> Self {
+        let edges = graph.to_edges();
+        let mut soa = EdgeSOA {
+            from: Vec::with_capacity(edges.len()),
+            to: Vec::with_capacity(edges.len()),
+            rate: Vec::with_capacity(edges.len()),
+        };
+        for (from, to, rate) in edges {
+            soa.from.push(from);
+            soa.to.push(to);
+            soa.rate.push(rate);
+        }
+        soa
+    }
+}
+
 pub const NUM_EDGES: usize = 100_000;
 pub const FEE_MULTIPLIER: f64 = 0.997; // 30 basis points fee (1 - 0.0030)
 
@@ -60,3 +105,123 @@ pub fn generate_benchmark_edges_aos() -> EdgeAOS {
         })
         .collect()
 }
+
+pub const REBUILD_BENCH_NODES: usize = 10_000;
+pub const NUM_RATE_CHANGES: usize = 1_000;
+
+/// Picks `count` committed edges of `graph` (evenly spaced in CSR order) and gives
+/// each a new rate, as a feed of quote updates on existing pairs would.
+pub fn generate_rate_changes(graph: &GraphCSR, count: usize) -> Vec<(usize, usize, f64)> {
+    let edges = graph.to_edges();
+    let step = (edges.len() / count.max(1)).max(1);
+    edges
+        .iter()
+        .step_by(step)
+        .take(count)
+        .map(|&(u, v, rate)| (u, v, rate * 1.0001))
+        .collect()
+}
+
+pub const SPFA_BENCH_NODES: usize = 10_000;
+pub const SPFA_BENCH_RUNS: usize = 20;
+
+/// Builds a random acyclic graph with every rate above 1.0.
+///
+/// All weights are negative but no cycle exists, so SPFA keeps relaxing until its
+/// queue drains and never exits early on a detected cycle: each search exercises
+/// the full inner loop.
+pub fn generate_acyclic_graph(num_nodes: usize, num_edges: usize) -> GraphCSR {
+    let mut edges: Vec<(usize, usize, f64)> = GraphCSR::random(num_nodes, num_edges, 0.9..1.1, 7)
+        .to_edges()
+        .into_iter()
+        .map(|(u, v, rate)| (u.min(v), u.max(v), 1.0 + (rate - 1.0).abs()))
+        .collect();
+
+    GraphCSR::from_edges(num_nodes, &mut edges, num_edges.max(1))
+}
+
+pub const RECONSTRUCT_BENCH_NODES: usize = 100_000;
+
+/// Builds a `ring_len`-node profitable ring (`0 -> 1 -> ... -> 0`) with a tail
+/// `ring_len - 1 -> ring_len -> ... -> num_nodes - 1` hanging off it, plus the
+/// predecessor edges SPFA would leave behind after detecting the ring.
+pub fn ring_with_tail(num_nodes: usize, ring_len: usize) -> (GraphCSR, Vec<Option<usize>>) {
+    let mut edges: Vec<(usize, usize, f64)> = (0..ring_len)
+        .map(|i| (i, (i + 1) % ring_len, 1.001))
+        .collect();
+    edges.extend((ring_len - 1..num_nodes - 1).map(|i| (i, i + 1, 1.0)));
+    let rebuild_limit = edges.len().max(1);
+    let graph = GraphCSR::from_edges(num_nodes, &mut edges, rebuild_limit);
+
+    let mut pred_edge_idx = vec![None; num_nodes];
+    for (idx, &target) in graph.edge_targets.iter().enumerate() {
+        pred_edge_idx[target] = Some(idx);
+    }
+    (graph, pred_edge_idx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edge_soa_round_trips_through_graph_csr() {
+        let soa = EdgeSOA {
+            from: vec![0, 0, 1, 2],
+            to: vec![1, 2, 2, 0],
+            rate: vec![1.1, 0.9, 1.05, 0.97],
+        };
+
+        let graph = GraphCSR::from(&soa);
+        assert_eq!(graph.num_nodes, 3);
+        assert_eq!(graph.edge_targets.len(), 4);
+
+        let back = EdgeSOA::from(&graph);
+        assert_eq!(back.from, soa.from);
+        assert_eq!(back.to, soa.to);
+        for (got, expected) in back.rate.iter().zip(&soa.rate) {
+            assert!((got - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn benchmark_aos_data_loads_into_graph() {
+        let soa: EdgeSOA = generate_benchmark_edges_aos().into();
+        let graph = GraphCSR::from(&soa);
+
+        assert_eq!(graph.num_nodes, NUM_EDGES + 1);
+        assert_eq!(graph.edge_targets.len(), NUM_EDGES);
+    }
+
+    #[test]
+    fn rebuild_and_in_place_update_agree() {
+        let graph = GraphCSR::random(100, 1_000, 0.9..1.1, 7);
+        let changes = generate_rate_changes(&graph, 50);
+        assert_eq!(changes.len(), 50);
+
+        let mut rebuilt = graph.clone();
+        rebuilt.rebuild_with_edges(changes.clone()).unwrap();
+        let mut updated = graph.clone();
+        for &(u, v, rate) in &changes {
+            assert!(updated.try_update_weight(u, v, rate));
+        }
+
+        assert!(rebuilt.diff(&updated).is_empty());
+    }
+
+    #[test]
+    fn acyclic_graph_has_no_profitable_cycle() {
+        use arb_solver_core::solver::SPFASolver;
+        use arb_solver_core::traits::GraphSolver;
+
+        let graph = generate_acyclic_graph(200, 2_000);
+        assert_eq!(graph.edge_targets.len(), 2_000);
+        assert!(graph.edge_weights.iter().all(|&w| w <= 0.0));
+        assert!(
+            SPFASolver
+                .find_profitable_cycle(&graph, 0, graph.num_nodes + 1)
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,30 @@
+#![no_main]
+
+use std::io::Write;
+
+use executor::csv_streamer::{CsvStreamer, parse_csv_bytes};
+use libfuzzer_sys::fuzz_target;
+use tempfile::NamedTempFile;
+
+// Arbitrary bytes, read back from a file in small batches, must never panic the
+// parser and must parse exactly as they do in memory.
+fuzz_target!(|data: &[u8]| {
+    let mut file = NamedTempFile::new().expect("Failed to create temp file");
+    file.write_all(data).expect("Failed to write fuzz input");
+    let path = file.path().to_str().expect("Failed to get path string");
+
+    let from_file = CsvStreamer::new(path.to_string(), 3).parse_csv_to_edges();
+    match (from_file, parse_csv_bytes(data)) {
+        (Ok(file_edges), Ok(edges)) => {
+            // Compare rates bitwise: a "NaN" cell parses to a rate unequal to itself.
+            let bits = |edges: Vec<(usize, usize, f64)>| -> Vec<(usize, usize, u64)> {
+                edges
+                    .into_iter()
+                    .map(|(u, v, rate)| (u, v, rate.to_bits()))
+                    .collect()
+            };
+            assert_eq!(bits(file_edges), bits(edges));
+        }
+        (from_file, in_memory) => assert_eq!(from_file.is_ok(), in_memory.is_ok()),
+    }
+});